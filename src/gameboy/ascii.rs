@@ -0,0 +1,41 @@
+// Text art of the frame for looking at the screen without a window, like in CI or over ssh.
+// Every character covers ratio pixels across and twice that down, as characters in a terminal
+// are about twice as tall as they are wide. The block is averaged and drawn with one of
+// SHADES, lightest first. Works on one byte per pixel, like the frame buffer.
+const SHADES: [char; 4] = [' ', '.', '+', '#'];
+
+pub fn ascii_art(pixels: &[u8], width: usize, height: usize, ratio: u8) -> String {
+    assert!(ratio > 0, "Can't downsample by 0");
+    assert_eq!(pixels.len(), width * height);
+
+    let block_width = ratio as usize;
+    let block_height = ratio as usize * 2;
+
+    let columns = width.div_ceil(block_width);
+    let rows = height.div_ceil(block_height);
+    let mut art = String::with_capacity((columns + 1) * rows);
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let x_end = ((column + 1) * block_width).min(width);
+            let y_end = ((row + 1) * block_height).min(height);
+
+            let mut sum = 0;
+            let mut count = 0;
+            for y in row * block_height..y_end {
+                for x in column * block_width..x_end {
+                    sum += pixels[y * width + x] as usize;
+                    count += 1;
+                }
+            }
+
+            // 255 is white, rounded to the nearest shade
+            let darkness = 255 - sum / count;
+            art.push(SHADES[(darkness * (SHADES.len() - 1) + 127) / 255]);
+        }
+
+        art.push('\n');
+    }
+
+    art
+}
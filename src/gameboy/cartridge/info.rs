@@ -0,0 +1,88 @@
+// https://gbdev.io/pandocs/The_Cartridge_Header.html
+
+// What the cartridge header says about the game, for showing what was loaded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CartridgeInfo {
+    pub title: String,
+    // 0x013F - 0x0142, only newer games have one, it's part of the title on older ones
+    pub manufacturer_code: String,
+    // 0x80 works on both, 0xC0 is CGB only
+    pub cgb_flag: u8,
+    pub sgb_flag: bool,
+    pub cartridge_type: u8,
+    pub cartridge_type_name: &'static str,
+    // In bytes
+    pub rom_size: usize,
+    pub ram_size: usize,
+    // 0x00 = Japan, 0x01 = everywhere else
+    pub destination_code: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16
+}
+
+impl CartridgeInfo {
+    // The ram size is passed in as MBC2's built in ram isn't in the header
+    pub fn from_header(rom: &[u8], ram_size: usize) -> Self {
+        let cartridge_type = rom[0x147];
+
+        // on CGB carts the last byte of the title is the CGB flag
+        let title_end = if rom[0x143] & 0x80 != 0 { 0x142 } else { 0x143 };
+
+        Self {
+            title: header_string(&rom[0x134..=title_end]),
+            manufacturer_code: header_string(&rom[0x13F..=0x142]),
+            cgb_flag: rom[0x143],
+            sgb_flag: rom[0x146] == 0x03,
+            cartridge_type,
+            cartridge_type_name: cartridge_type_name(cartridge_type),
+            rom_size: rom.len(),
+            ram_size,
+            destination_code: rom[0x14A],
+            header_checksum: rom[0x14D],
+            global_checksum: u16::from_be_bytes([rom[0x14E], rom[0x14F]])
+        }
+    }
+}
+
+// Up to the first 0
+fn header_string(bytes: &[u8]) -> String {
+    bytes.iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as char)
+        .collect()
+}
+
+pub(crate) fn cartridge_type_name(code: u8) -> &'static str {
+    match code {
+        0x00 => "ROM ONLY",
+        0x01 => "MBC1",
+        0x02 => "MBC1+RAM",
+        0x03 => "MBC1+RAM+BATTERY",
+        0x05 => "MBC2",
+        0x06 => "MBC2+BATTERY",
+        0x08 => "ROM+RAM",
+        0x09 => "ROM+RAM+BATTERY",
+        0x0B => "MMM01",
+        0x0C => "MMM01+RAM",
+        0x0D => "MMM01+RAM+BATTERY",
+        0x0F => "MBC3+TIMER+BATTERY",
+        0x10 => "MBC3+TIMER+RAM+BATTERY",
+        0x11 => "MBC3",
+        0x12 => "MBC3+RAM",
+        0x13 => "MBC3+RAM+BATTERY",
+        0x19 => "MBC5",
+        0x1A => "MBC5+RAM",
+        0x1B => "MBC5+RAM+BATTERY",
+        0x1C => "MBC5+RUMBLE",
+        0x1D => "MBC5+RUMBLE+RAM",
+        0x1E => "MBC5+RUMBLE+RAM+BATTERY",
+        0x20 => "MBC6",
+        0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+        0xFC => "POCKET CAMERA",
+        0xFD => "BANDAI TAMA5",
+        0xFE => "HuC3",
+        0xFF => "HuC1+RAM+BATTERY",
+
+        _ => "UNKNOWN"
+    }
+}
@@ -1,106 +1,109 @@
-use std::{fs::File, io::{Read, Write}, path::{Path, PathBuf}};
+use crate::gameboy::save_state::{StateReader, StateWriter};
 
-use super::{Cartridge, get_save_file_path_from_rom_path, try_read_save_file};
+use super::{Mapper, MemoryLayout, ram_bank_addr};
 
-pub struct MBC1 {
+// https://gbdev.io/pandocs/MBC1.html
+// BANK1 (0x2000 - 0x3FFF) is the low bits of the rom bank. BANK2 (0x4000 - 0x5FFF) is 2 more bits
+// that go above it, and in mode 1 they also pick the bank at 0x0000 and the ram bank.
+//
+// MBC1M multicarts wire BANK1's top bit up to nothing, so BANK2 sits on top of 4 bits
+// instead of 5 and picks which 256KB game is mapped in.
+
+pub struct Mbc1 {
     is_ram_enabled: bool,
-    current_rom_bank: usize,
-    current_ram_bank: usize,
+    bank1: u8,
+    bank2: u8,
     mode: u8, // 0 = ROM 1 = RAM
 
-    rom_banks: Vec<[u8; 0x4000]>,
-    ram_banks: Vec<[u8; 0x2000]>,
-
-    save_file_path: PathBuf
+    multicart: bool
 }
 
-impl MBC1 {
-    pub fn new(
-        mut file: File,
-        path: &Path,
-        rom_bank_0: [u8; 0x4000],
-        cartridge_type_code: u8, 
-        num_rom_banks: u16, 
-        num_ram_banks: u16
-    ) -> Self {
-        let mut rom_banks = Vec::new();
-        rom_banks.push(rom_bank_0);
-
-        for _ in 0..num_rom_banks - 1 {
-            let mut bank = [0; 0x4000];
-            file.read_exact(&mut bank).ok();
-            rom_banks.push(bank);
-        }
-
-        let mut ram_banks = Vec::new();
-        let save_file_path = get_save_file_path_from_rom_path(path);
-
-        // try to open save file
-        let sav_file = File::open(&save_file_path);
-        try_read_save_file(sav_file, num_ram_banks, &mut ram_banks);
+impl Default for Mbc1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl Mbc1 {
+    pub fn new() -> Self {
         Self {
             is_ram_enabled: false,
-            current_rom_bank: 1,
-            current_ram_bank: 0,
+            bank1: 1,
+            bank2: 0,
             mode: 0,
 
-            rom_banks,
-            ram_banks,
+            multicart: false
+        }
+    }
 
-            save_file_path
+    pub fn new_multicart() -> Self {
+        Self {
+            multicart: true,
+            ..Self::new()
         }
     }
-}
 
-impl Drop for MBC1 {
-    fn drop(&mut self) {
-        // create save file
-        let mut sav_file = File::create(&self.save_file_path).unwrap();
-        for bank in &self.ram_banks {
-            sav_file.write_all(bank).unwrap();
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+
+    // Banks past the end of the rom wrap around, as the upper address lines aren't connected
+    fn rom_bank(rom: &[u8], bank: usize) -> usize {
+        bank % (rom.len() / 0x4000).max(1)
+    }
+
+    // 0x0000 - 0x3FFF
+    fn low_rom_bank(&self, rom: &[u8]) -> usize {
+        match self.mode {
+            1 => Self::rom_bank(rom, (self.bank2 as usize) << self.bank2_shift()),
+            _ => 0
         }
-        println!("Save file written!");
+    }
 
+    // 0x4000 - 0x7FFF
+    fn high_rom_bank(&self, rom: &[u8]) -> usize {
+        let shift = self.bank2_shift();
+        let bank1_mask = (1 << shift) - 1;
+
+        Self::rom_bank(rom, ((self.bank2 as usize) << shift) | (self.bank1 & bank1_mask) as usize)
     }
-}
 
-impl Cartridge for MBC1 {
-    fn read_rom(&self, addr: u16) -> u8 {
-        match addr & 0xF000 {
-            0x0000 | 0x1000 | 0x2000 | 0x3000 => {
-                self.rom_banks[0][addr as usize]
-            }
+    fn ram_bank(&self, ram: &[u8]) -> usize {
+        let num_banks = (ram.len() / 0x2000).max(1);
 
-            0x4000 | 0x5000 | 0x6000 | 0x7000 => {
-                self.rom_banks[self.current_rom_bank][(addr - 0x4000) as usize]
-            }
+        match self.mode {
+            1 => self.bank2 as usize % num_banks,
+            _ => 0
+        }
+    }
+}
+
+impl Mapper for Mbc1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let bank = match addr & 0xF000 {
+            0x0000 | 0x1000 | 0x2000 | 0x3000 => self.low_rom_bank(rom),
+            0x4000 | 0x5000 | 0x6000 | 0x7000 => self.high_rom_bank(rom),
 
             _ => panic!()
-        }
+        };
+
+        rom[bank * 0x4000 + (addr & 0x3FFF) as usize]
     }
 
-    fn write_rom(&mut self, addr: u16, value: u8) {
+    fn write_control(&mut self, addr: u16, value: u8) {
         match addr & 0xF000 {
             0x0000 | 0x1000 => {
                 self.is_ram_enabled = (value & 0x0F) == 0x0A;
             }
 
-            // TODO: The below match arms are wrong in some way
-
+            // the 0 check is on all 5 bits, even on a multicart where only 4 of them get used
             0x2000 | 0x3000 => {
-                self.current_rom_bank = (self.current_rom_bank & 0b0110_0000) + value as usize;
-                if self.current_rom_bank == 0 { self.current_rom_bank = 1 }
+                self.bank1 = value & 0b0001_1111;
+                if self.bank1 == 0 { self.bank1 = 1 }
             }
 
             0x4000 | 0x5000 => {
-                if self.mode == 0 {
-                    self.current_rom_bank =
-                        (self.current_rom_bank & 0x0b0001_1111) +
-                        ((value & 0b0000_0011) << 5) as usize;
-                } else {
-                    panic!("mode 1 unimpl");
-                }
+                self.bank2 = value & 0b0000_0011;
             }
 
             0x6000 | 0x7000 => {
@@ -111,15 +114,39 @@ impl Cartridge for MBC1 {
         }
     }
 
-    fn read_ram(&self, addr: u16) -> u8 {
-        if !self.is_ram_enabled { return 0xFF; }
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.is_ram_enabled || ram.is_empty() { return 0xFF; }
 
-        self.ram_banks[self.current_ram_bank][addr as usize]
+        ram[ram_bank_addr(ram, self.ram_bank(ram), addr)]
     }
 
-    fn write_ram(&mut self, addr: u16, value: u8) {
-        if !self.is_ram_enabled { return }
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.is_ram_enabled || ram.is_empty() { return }
+
+        ram[ram_bank_addr(ram, self.ram_bank(ram), addr)] = value;
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.is_ram_enabled);
+        state.write_u8(self.bank1);
+        state.write_u8(self.bank2);
+        state.write_u8(self.mode);
+    }
 
-        self.ram_banks[self.current_ram_bank][addr as usize] = value;
+    fn load_state(&mut self, state: &mut StateReader) {
+        self.is_ram_enabled = state.read_bool();
+        self.bank1 = state.read_u8();
+        self.bank2 = state.read_u8();
+        self.mode = state.read_u8();
     }
-}
\ No newline at end of file
+
+    fn memory_layout(&self, rom: &[u8], ram: &[u8]) -> MemoryLayout {
+        MemoryLayout {
+            low_rom_bank: self.low_rom_bank(rom),
+            rom_bank: self.high_rom_bank(rom),
+            ram_bank: self.ram_bank(ram),
+            ram_enabled: self.is_ram_enabled,
+            banking_mode: self.mode
+        }
+    }
+}
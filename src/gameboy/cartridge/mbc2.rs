@@ -0,0 +1,84 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
+use super::{Mapper, MemoryLayout, read_banked_rom};
+
+// https://gbdev.io/pandocs/MBC2.html
+// MBC2 has 512 half-bytes of ram built in instead of external ram banks.
+// Only the low nibble of each byte exists, the high nibble reads back as 1s.
+pub const RAM_SIZE: usize = 0x200;
+
+pub struct Mbc2 {
+    is_ram_enabled: bool,
+    num_rom_banks: u16,
+    current_rom_bank: usize
+}
+
+impl Mbc2 {
+    pub fn new(num_rom_banks: u16) -> Self {
+        Self {
+            is_ram_enabled: false,
+            num_rom_banks,
+            current_rom_bank: 1
+        }
+    }
+}
+
+impl Mapper for Mbc2 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        read_banked_rom(rom, self.current_rom_bank, addr)
+    }
+
+    fn write_control(&mut self, addr: u16, value: u8) {
+        match addr & 0xF000 {
+            // bit 8 of the address picks the register:
+            // clear = ram enable, set = rom bank
+            0x0000 | 0x1000 | 0x2000 | 0x3000 => {
+                if addr & 0x0100 == 0 {
+                    self.is_ram_enabled = (value & 0x0F) == 0x0A;
+                }
+                else {
+                    let mut bank = (value & 0x0F) as usize;
+                    if bank == 0 { bank = 1 }
+
+                    self.current_rom_bank = bank % self.num_rom_banks as usize;
+                }
+            }
+
+            // no registers up here
+            0x4000 | 0x5000 | 0x6000 | 0x7000 => { }
+
+            _ => panic!()
+        }
+    }
+
+    // The 512 half-bytes repeat through the whole of 0xA000 - 0xBFFF
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.is_ram_enabled { return 0xFF; }
+
+        0xF0 | (ram[addr as usize & (RAM_SIZE - 1)] & 0x0F)
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.is_ram_enabled { return }
+
+        ram[addr as usize & (RAM_SIZE - 1)] = value & 0x0F;
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.is_ram_enabled);
+        state.write_u32(self.current_rom_bank as u32);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) {
+        self.is_ram_enabled = state.read_bool();
+        self.current_rom_bank = state.read_u32() as usize;
+    }
+
+    fn memory_layout(&self, _rom: &[u8], _ram: &[u8]) -> MemoryLayout {
+        MemoryLayout {
+            rom_bank: self.current_rom_bank,
+            ram_enabled: self.is_ram_enabled,
+            ..MemoryLayout::default()
+        }
+    }
+}
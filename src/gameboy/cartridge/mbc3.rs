@@ -1,47 +1,28 @@
-use std::{fs::File, io::{Read, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::{Cartridge, get_save_file_path_from_rom_path, try_read_save_file};
+use crate::gameboy::save_state::{StateReader, StateWriter};
 
-pub struct MBC3 {
+use super::{Mapper, MemoryLayout, ram_bank_addr, read_banked_rom};
+
+pub struct Mbc3 {
     is_ram_rtc_enabled: bool,
     current_rom_bank: usize,
     current_ram_bank: usize,
 
-    rom_banks: Vec<[u8; 0x4000]>,
-    ram_banks: Vec<[u8; 0x2000]>,
-
     rtc_regs: [u8; 5],
     rtc_banked: bool,
 
-    prev_latch_val: u8,
-    save_file_path: PathBuf
+    prev_latch_val: u8
 }
 
-impl MBC3 {
-    pub fn new(
-        mut file: File,
-        path: &Path,
-        rom_bank_0: [u8; 0x4000],
-        cartridge_type_code: u8, 
-        num_rom_banks: u16, 
-        num_ram_banks: u16
-    ) -> Self {
-        let mut rom_banks = Vec::new();
-        rom_banks.push(rom_bank_0);
-
-        for _ in 0..num_rom_banks - 1 {
-            let mut bank = [0; 0x4000];
-            file.read_exact(&mut bank).ok();
-            rom_banks.push(bank);
-        }
-
-        let mut ram_banks = Vec::new();
-        let save_file_path = get_save_file_path_from_rom_path(path);
+impl Default for Mbc3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        // try to open save file
-        let sav_file = File::open(&save_file_path);
-        try_read_save_file(sav_file, num_ram_banks, &mut ram_banks);
-        
+impl Mbc3 {
+    pub fn new() -> Self {
         Self {
             is_ram_rtc_enabled: false,
             current_rom_bank: 1,
@@ -50,42 +31,17 @@ impl MBC3 {
             rtc_regs: [0; 5],
             rtc_banked: false,
 
-            rom_banks,
-            ram_banks,
-            prev_latch_val: 204, // random val
-            save_file_path
-        }
-    }
-}
-
-impl Drop for MBC3 {
-    fn drop(&mut self) {
-        // create save file
-        let mut sav_file = File::create(&self.save_file_path).unwrap();
-        for bank in &self.ram_banks {
-            sav_file.write_all(bank).unwrap();
+            prev_latch_val: 204 // random val
         }
-        println!("Save file written!");
-
     }
 }
 
-impl Cartridge for MBC3 {
-    fn read_rom(&self, addr: u16) -> u8 {
-        match addr & 0xF000 {
-            0x0000 | 0x1000 | 0x2000 | 0x3000 => {
-                self.rom_banks[0][addr as usize]
-            }
-
-            0x4000 | 0x5000 | 0x6000 | 0x7000 => {
-                self.rom_banks[self.current_rom_bank][(addr - 0x4000) as usize]
-            }
-
-            _ => panic!()
-        }
+impl Mapper for Mbc3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        read_banked_rom(rom, self.current_rom_bank, addr)
     }
 
-    fn write_rom(&mut self, addr: u16, value: u8) {
+    fn write_control(&mut self, addr: u16, value: u8) {
         match addr & 0xF000 {
             0x0000 | 0x1000 => {
                 self.is_ram_rtc_enabled = (value & 0x0F) == 0x0A;
@@ -124,21 +80,52 @@ impl Cartridge for MBC3 {
         }
     }
 
-    fn read_ram(&self, addr: u16) -> u8 {
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
         if !self.is_ram_rtc_enabled { return 0xFF; }
 
         if self.rtc_banked {
             return self.rtc_regs[(addr - 0x08) as usize];
         }
 
-        self.ram_banks[self.current_ram_bank][addr as usize]
+        if ram.is_empty() { return 0xFF; }
+
+        ram[ram_bank_addr(ram, self.current_ram_bank, addr)]
     }
 
-    fn write_ram(&mut self, addr: u16, value: u8) {
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
         if !self.is_ram_rtc_enabled { return }
 
         // what to do if rtc is banked?
 
-        self.ram_banks[self.current_ram_bank][addr as usize] = value;
+        if ram.is_empty() { return }
+
+        ram[ram_bank_addr(ram, self.current_ram_bank, addr)] = value;
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.is_ram_rtc_enabled);
+        state.write_u32(self.current_rom_bank as u32);
+        state.write_u32(self.current_ram_bank as u32);
+        state.write_bytes(&self.rtc_regs);
+        state.write_bool(self.rtc_banked);
+        state.write_u8(self.prev_latch_val);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) {
+        self.is_ram_rtc_enabled = state.read_bool();
+        self.current_rom_bank = state.read_u32() as usize;
+        self.current_ram_bank = state.read_u32() as usize;
+        state.read_bytes(&mut self.rtc_regs);
+        self.rtc_banked = state.read_bool();
+        self.prev_latch_val = state.read_u8();
     }
-}
\ No newline at end of file
+
+    fn memory_layout(&self, _rom: &[u8], _ram: &[u8]) -> MemoryLayout {
+        MemoryLayout {
+            rom_bank: self.current_rom_bank,
+            ram_bank: self.current_ram_bank,
+            ram_enabled: self.is_ram_rtc_enabled,
+            ..MemoryLayout::default()
+        }
+    }
+}
@@ -1,95 +1,41 @@
-use std::{fs::File, io::{Read, Write}, path::{Path, PathBuf}};
+use crate::gameboy::save_state::{StateReader, StateWriter};
 
-use super::{Cartridge, get_save_file_path_from_rom_path, try_read_save_file};
+use super::{Mapper, MemoryLayout, ram_bank_addr, read_banked_rom};
 
 
-pub struct MBC5 {
+pub struct Mbc5 {
     is_ram_enabled: bool,
-    num_rom_banks: u16,
-    num_ram_banks: u16,
 
     current_rom_bank: usize,
     current_ram_bank: usize,
 
-    mode: u8, // 0 = ROM 1 = RAM
-
-    rom_banks: Vec<[u8; 0x4000]>,
-    ram_banks: Vec<[u8; 0x2000]>,
-
-    save_file_path: PathBuf
+    mode: u8 // 0 = ROM 1 = RAM
 }
 
-impl MBC5 {
-    pub fn new(
-        mut file: File,
-        path: &Path,
-        rom_bank_0: [u8; 0x4000],
-        cartridge_type_code: u8, 
-        num_rom_banks: u16, 
-        num_ram_banks: u16
-    ) -> Self {
-        let mut rom_banks = Vec::new();
-        rom_banks.push(rom_bank_0);
-
-        for _ in 0..num_rom_banks - 1 {
-            let mut bank = [0; 0x4000];
-            file.read_exact(&mut bank).ok();
-            rom_banks.push(bank);
-        }
-
-        let mut ram_banks = Vec::new();
-
-        let save_file_path = get_save_file_path_from_rom_path(path);
-
-        // try to open save file
-        let sav_file = File::open(&save_file_path);
-        try_read_save_file(sav_file, num_ram_banks, &mut ram_banks);
+impl Default for Mbc5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl Mbc5 {
+    pub fn new() -> Self {
         Self {
             is_ram_enabled: false,
-            num_rom_banks,
-            num_ram_banks,
 
             current_rom_bank: 1,
             current_ram_bank: 0,
-            mode: 0,
-
-            rom_banks,
-            ram_banks,
-
-            save_file_path
+            mode: 0
         }
     }
 }
 
-impl Drop for MBC5 {
-    fn drop(&mut self) {
-        // create save file
-        let mut sav_file = File::create(&self.save_file_path).unwrap();
-        for bank in &self.ram_banks {
-            sav_file.write_all(bank).unwrap();
-        }
-        println!("Save file written!");
-
-    }
-}
-
-impl Cartridge for MBC5 {
-    fn read_rom(&self, addr: u16) -> u8 {
-        match addr & 0xF000 {
-            0x0000 | 0x1000 | 0x2000 | 0x3000 => {
-                self.rom_banks[0][addr as usize]
-            }
-
-            0x4000 | 0x5000 | 0x6000 | 0x7000 => {
-                self.rom_banks[self.current_rom_bank][(addr - 0x4000) as usize]
-            }
-
-            _ => panic!()
-        }
+impl Mapper for Mbc5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        read_banked_rom(rom, self.current_rom_bank, addr)
     }
 
-    fn write_rom(&mut self, addr: u16, value: u8) {
+    fn write_control(&mut self, addr: u16, value: u8) {
         match addr & 0xF000 {
             0x0000 | 0x1000 => {
                 self.is_ram_enabled = (value & 0x0F) == 0x0A;
@@ -118,15 +64,38 @@ impl Cartridge for MBC5 {
         }
     }
 
-    fn read_ram(&self, addr: u16) -> u8 {
-        if !self.is_ram_enabled { return 0xFF; }
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.is_ram_enabled || ram.is_empty() { return 0xFF; }
 
-        self.ram_banks[self.current_ram_bank][addr as usize]
+        ram[ram_bank_addr(ram, self.current_ram_bank, addr)]
     }
 
-    fn write_ram(&mut self, addr: u16, value: u8) {
-        if !self.is_ram_enabled { return }
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.is_ram_enabled || ram.is_empty() { return }
 
-        self.ram_banks[self.current_ram_bank][addr as usize] = value;
+        ram[ram_bank_addr(ram, self.current_ram_bank, addr)] = value;
     }
-}
\ No newline at end of file
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.is_ram_enabled);
+        state.write_u32(self.current_rom_bank as u32);
+        state.write_u32(self.current_ram_bank as u32);
+        state.write_u8(self.mode);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) {
+        self.is_ram_enabled = state.read_bool();
+        self.current_rom_bank = state.read_u32() as usize;
+        self.current_ram_bank = state.read_u32() as usize;
+        self.mode = state.read_u8();
+    }
+
+    fn memory_layout(&self, _rom: &[u8], _ram: &[u8]) -> MemoryLayout {
+        MemoryLayout {
+            rom_bank: self.current_rom_bank,
+            ram_bank: self.current_ram_bank,
+            ram_enabled: self.is_ram_enabled,
+            ..MemoryLayout::default()
+        }
+    }
+}
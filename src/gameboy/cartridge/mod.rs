@@ -1,45 +1,297 @@
-use std::{fs::File, io::{Error, Read}, path::{Path, PathBuf}};
+use std::{fs::File, io::{Read, Write}, path::{Path, PathBuf}};
 
-use crate::gameboy::cartridge::{mbc1::MBC1, mbc3::MBC3, mbc5::MBC5, rom::ROM};
+use crate::gameboy::{cartridge::{info::CartridgeInfo, mbc1::Mbc1, mbc2::Mbc2, mbc3::Mbc3, mbc5::Mbc5, no_mbc::NoMbc}, error::Error, save_state::{StateReader, StateWriter}};
 
 // https://gbdev.io/pandocs/#the-cartridge-header
 // http://marc.rawer.de/Gameboy/Docs/GBCPUman.pdf Section 2.6 (page 13)
 
 
-pub mod rom;
+// https://gbdev.io/pandocs/The_Cartridge_Header.html#0104-0133---nintendo-logo
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+pub mod info;
+pub mod no_mbc;
 pub mod mbc1;
+pub mod mbc2;
 pub mod mbc3;
 pub mod mbc5;
 
-pub trait Cartridge {
-    fn read_rom(&self, addr: u16) -> u8;
-    fn write_rom(&mut self, addr: u16, value: u8);
+// Which banks a mapper has switched in, for debuggers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MemoryLayout {
+    // 0x0000 - 0x3FFF, only an MBC1 in mode 1 maps anything but bank 0 here
+    pub low_rom_bank: usize,
+    // 0x4000 - 0x7FFF
+    pub rom_bank: usize,
+    // 0xA000 - 0xBFFF
+    pub ram_bank: usize,
+    pub ram_enabled: bool,
+    // MBC1's mode register, 0 = rom banking 1 = ram banking. Always 0 for other mappers.
+    pub banking_mode: u8
+}
+
+// A 32KB cart with nothing to bank
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        Self {
+            low_rom_bank: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            ram_enabled: false,
+            banking_mode: 0
+        }
+    }
+}
+
+// The mappers there are, for overriding the one the header picks with force_mapper
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapperKind {
+    NoMbc,
+    Mbc1,
+    // An MBC1 wired up for a multicart, see Mbc1::new_multicart
+    Mbc1Multicart,
+    Mbc2,
+    Mbc3,
+    Mbc5
+}
+
+impl MapperKind {
+    fn create(&self, num_rom_banks: u16) -> Box<dyn Mapper> {
+        match self {
+            MapperKind::NoMbc => Box::new(NoMbc::new()),
+            MapperKind::Mbc1 => Box::new(Mbc1::new()),
+            MapperKind::Mbc1Multicart => Box::new(Mbc1::new_multicart()),
+            MapperKind::Mbc2 => Box::new(Mbc2::new(num_rom_banks)),
+            MapperKind::Mbc3 => Box::new(Mbc3::new()),
+            MapperKind::Mbc5 => Box::new(Mbc5::new())
+        }
+    }
+}
+
+// The banking hardware in a cartridge. The cartridge owns the rom and ram,
+// the mapper only decides which parts of them the cpu sees.
+pub trait Mapper {
+    // 0x0000 - 0x7FFF
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8;
+    // Writes to 0x0000 - 0x7FFF set the mapper's registers
+    fn write_control(&mut self, addr: u16, value: u8);
+
+    // The address is relative to 0xA000
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8;
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8);
 
-    fn read_ram(&self, addr: u16) -> u8;
-    fn write_ram(&mut self, addr: u16, value: u8);
+    // Just the mapper's registers, the cartridge saves the ram
+    fn save_state(&self, state: &mut StateWriter);
+    fn load_state(&mut self, state: &mut StateReader);
+
+    // Mappers that don't say are reported as having nothing to bank
+    fn memory_layout(&self, _rom: &[u8], _ram: &[u8]) -> MemoryLayout {
+        MemoryLayout::default()
+    }
 }
 
-pub fn create(rom_path: &str) -> Box<dyn Cartridge> {
-    let mut rom_bank_0 = [0u8; 0x4000];
+pub struct Cartridge {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mapper: Box<dyn Mapper>,
+
+    // Banks forced in by a debugger, until the game next writes to the mapper. Not saved.
+    rom_bank_override: Option<usize>,
+    ram_bank_override: Option<usize>,
+
+    // The ram gets written here when the cartridge is dropped
+    save_file_path: Option<PathBuf>
+}
+
+impl Cartridge {
+    // The rom is padded with 0s to a whole number of 16KB banks, and to at least the 32KB
+    // of the smallest real cartridge, so every bank the mappers can pick is there.
+    pub fn new(mut rom: Vec<u8>, ram: Vec<u8>, mapper: Box<dyn Mapper>, save_file_path: Option<PathBuf>) -> Self {
+        let num_banks = rom.len().div_ceil(0x4000).max(2);
+        rom.resize(num_banks * 0x4000, 0);
+
+        Self {
+            rom,
+            ram,
+            mapper,
+
+            rom_bank_override: None,
+            ram_bank_override: None,
+
+            save_file_path
+        }
+    }
+
+    // What's left in the slot once the cartridge has been taken out, there's nothing
+    // driving the bus so the rom reads as 0xFF
+    pub fn empty() -> Self {
+        Self::new(vec![0xFF; 0x8000], Vec::new(), Box::new(NoMbc::new()), None)
+    }
+
+    // Empty if the cartridge doesn't have any
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn read_rom(&self, addr: u16) -> u8 {
+        if let Some(bank) = self.rom_bank_override {
+            if addr >= 0x4000 {
+                return self.rom[bank * 0x4000 + (addr & 0x3FFF) as usize];
+            }
+        }
+
+        self.mapper.read_rom(&self.rom, addr)
+    }
+
+    // The game picking a bank for itself takes over from the debugger again
+    pub fn write_rom(&mut self, addr: u16, value: u8) {
+        self.rom_bank_override = None;
+        self.ram_bank_override = None;
+        self.mapper.write_control(addr, value);
+    }
+
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        if self.ram_bank_override.is_some() {
+            return match self.ram.is_empty() {
+                true => 0xFF,
+                false => self.ram[self.overridden_ram_addr(addr)]
+            };
+        }
+
+        self.mapper.read_ram(&self.ram, addr)
+    }
+
+    pub fn write_ram(&mut self, addr: u16, value: u8) {
+        if self.ram_bank_override.is_some() {
+            if !self.ram.is_empty() {
+                let ram_addr = self.overridden_ram_addr(addr);
+                self.ram[ram_addr] = value;
+            }
+            return;
+        }
+
+        self.mapper.write_ram(&mut self.ram, addr, value);
+    }
+
+    fn overridden_ram_addr(&self, addr: u16) -> usize {
+        ram_bank_addr(&self.ram, self.ram_bank_override.unwrap(), addr)
+    }
 
+    pub fn memory_layout(&self) -> MemoryLayout {
+        let mut layout = self.mapper.memory_layout(&self.rom, &self.ram);
+
+        if let Some(bank) = self.rom_bank_override {
+            layout.rom_bank = bank;
+        }
+        if let Some(bank) = self.ram_bank_override {
+            layout.ram_bank = bank;
+        }
+
+        layout
+    }
+
+    // For debuggers: maps the bank in at 0x4000 - 0x7FFF whatever the mapper says,
+    // until the game writes to the mapper itself. Banks past the end of the rom wrap around.
+    pub fn set_rom_bank(&mut self, bank: u16) {
+        let num_banks = (self.rom.len() / 0x4000).max(1);
+        self.rom_bank_override = Some(bank as usize % num_banks);
+    }
+
+    // Like set_rom_bank for 0xA000 - 0xBFFF. The ram reads and writes even if the game
+    // hasn't enabled it, and carts with RTC registers only ever see their ram.
+    pub fn set_ram_bank(&mut self, bank: u8) {
+        let num_banks = (self.ram.len() / 0x2000).max(1);
+        self.ram_bank_override = Some(bank as usize % num_banks);
+    }
+
+    // Overrides the mapper the header's cartridge type picked, for homebrew and bad dumps with
+    // the wrong byte at 0x147. The new mapper starts out as it would at power on. The ram is resized
+    // to what the header's ram size at 0x149 asks for, even if the header's cartridge type has no ram,
+    // apart from MBC2 which has its own built in. It's only kept in a save file if the header's
+    // cartridge type had one.
+    pub fn force_mapper(&mut self, kind: MapperKind) {
+        let num_rom_banks = (self.rom.len() / 0x4000) as u16;
+        self.mapper = kind.create(num_rom_banks);
+
+        let ram_size = match kind {
+            MapperKind::Mbc2 => mbc2::RAM_SIZE,
+            _ => ram_size_from_code(self.rom[0x149]).unwrap_or(0)
+        };
+        self.ram.resize(ram_size, 0);
+
+        self.rom_bank_override = None;
+        self.ram_bank_override = None;
+    }
+
+    // The real boot rom locks up if this is false, some games check it themselves too.
+    // Nothing here enforces it.
+    pub fn has_nintendo_logo(&self) -> bool {
+        self.rom[0x104..0x104 + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+    }
+
+    pub fn info(&self) -> CartridgeInfo {
+        CartridgeInfo::from_header(&self.rom, self.ram.len())
+    }
+
+    // The rom itself isn't saved, just the ram and the mapper registers
+    pub fn save_state(&self, state: &mut StateWriter) {
+        self.mapper.save_state(state);
+        state.write_bytes(&self.ram);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.rom_bank_override = None;
+        self.ram_bank_override = None;
+        self.mapper.load_state(state);
+        state.read_bytes(&mut self.ram);
+    }
+}
+
+impl Drop for Cartridge {
+    fn drop(&mut self) {
+        if self.save_file_path.is_none() { return }
+
+        // create save file
+        let mut sav_file = File::create(self.save_file_path.as_ref().unwrap()).unwrap();
+        sav_file.write_all(&self.ram).unwrap();
+        emu_info!(target: "gameboy_rs::cartridge", "Save file written!");
+    }
+}
+
+pub fn create(rom_path: &str) -> Result<Cartridge, Error> {
     let path = Path::new(rom_path);
-    let file = File::open(path);
-    let mut file = match file {
-        Ok(f) => f,
-        Err(err) => panic!("Something went wrong reading the ROM: {}", err)
-    };
+    let mut file = File::open(path)?;
+
+    create_from_reader(&mut file, path)
+}
 
-    file.read_exact(&mut rom_bank_0).ok();
+// The path is only used to work out where the save file goes
+pub fn create_from_reader(file: &mut dyn Read, path: &Path) -> Result<Cartridge, Error> {
+    let mut rom = Vec::new();
+    file.read_to_end(&mut rom)?;
+
+    create_from_rom(rom, Some(path))
+}
+
+// Without a path there's nowhere to keep a save file, so the ram starts out blank
+pub fn create_from_rom(mut rom: Vec<u8>, path: Option<&Path>) -> Result<Cartridge, Error> {
+    if rom.len() < 0x4000 {
+        rom.resize(0x4000, 0);
+    }
 
     // parse cart header
     // CGB flag
-    if rom_bank_0[0x143] == 0xC0 {
-        panic!("This rom is only supported for game boy color");
+    if rom[0x143] == 0xC0 {
+        return Err(Error::CgbOnly);
     }
 
-    let cartridge_type_code = rom_bank_0[0x147];
-    let rom_size_code = rom_bank_0[0x148];
-    let ram_size_code = rom_bank_0[0x149];
+    let cartridge_type_code = rom[0x147];
+    let rom_size_code = rom[0x148];
+    let ram_size_code = rom[0x149];
 
     // This includes rom bank 0
     let num_rom_banks: u16 = match rom_size_code {
@@ -59,63 +311,107 @@ pub fn create(rom_path: &str) -> Box<dyn Cartridge> {
         0x52 => 72,  // 1.1MB
         0x53 => 80,  // 1.2MB
         0x54 => 96,  // 1.5MB
-        
-        _ => panic!("Cartridge has invalid ROM size code? Code: {:#04X}", rom_size_code)
+
+        _ => return Err(Error::InvalidRomSize(rom_size_code))
     };
 
-    let num_ram_banks: u16 = match ram_size_code {
+    let ram_size = match ram_size_from_code(ram_size_code) {
+        Some(ram_size) => ram_size,
+        None => return Err(Error::InvalidRamSize(ram_size_code))
+    };
+
+    // a rom that's shorter than the header says reads as 0s past the end
+    rom.resize(num_rom_banks as usize * 0x4000, 0);
+
+    // the ram size and whether it's kept in a save file
+    let (mapper, ram_size, has_save_file): (Box<dyn Mapper>, usize, bool) = match cartridge_type_code {
+        0x00 => (Box::new(NoMbc::new()), 0, false),
+
+        0x01..=0x03 if is_mbc1_multicart(&rom) => {
+            emu_info!(target: "gameboy_rs::cartridge", "MBC1M cart created!");
+            (Box::new(Mbc1::new_multicart()), ram_size, true)
+        }
+
+        0x01..=0x03 => {
+            emu_info!(target: "gameboy_rs::cartridge", "MBC1 cart created!");
+            (Box::new(Mbc1::new()), ram_size, true)
+        }
+
+        0x05 | 0x06 => {
+            emu_info!(target: "gameboy_rs::cartridge", "MBC2 cart created!");
+            (Box::new(Mbc2::new(num_rom_banks)), mbc2::RAM_SIZE, true)
+        }
+
+        0x0F..=0x13 => {
+            emu_info!(target: "gameboy_rs::cartridge", "MBC3 cart created!");
+            (Box::new(Mbc3::new()), ram_size, true)
+        }
+
+        0x1A..=0x1E => {
+            emu_info!(target: "gameboy_rs::cartridge", "MBC5 cart created!");
+            (Box::new(Mbc5::new()), ram_size, true)
+        }
+
+        _ => return Err(Error::UnsupportedMapper(cartridge_type_code))
+    };
+
+    let path = match path {
+        Some(path) if has_save_file => path,
+        _ => return Ok(Cartridge::new(rom, vec![0; ram_size], mapper, None))
+    };
+
+    let save_file_path = get_save_file_path_from_rom_path(path);
+    let ram = read_save_file(&save_file_path, ram_size);
+
+    Ok(Cartridge::new(rom, ram, mapper, Some(save_file_path)))
+}
+
+// The header's ram size, 0x149
+fn ram_size_from_code(ram_size_code: u8) -> Option<usize> {
+    let num_ram_banks = match ram_size_code {
         0x00 => 0,
         0x02 => 1,
         0x03 => 4,
         0x04 => 16,
         0x05 => 8,
 
-        _ => panic!("Cartridge has invalid RAM size code? Code: {:#04X}", ram_size_code)
+        _ => return None
     };
 
-    match cartridge_type_code {
-        0x00 => Box::new(ROM::new(file, rom_bank_0)),
-        
-        0x01 | 0x02 | 0x03 => {
-            println!("MBC1 cart created!");
-            Box::new(MBC1::new(
-                file,
-                path,
-                rom_bank_0,
-                cartridge_type_code, 
-                num_rom_banks, 
-                num_ram_banks
-            ))
-        }
-        
-        0x0F..=0x13 => {
-            println!("MBC3 cart created!");
-            Box::new(MBC3::new(
-                file,
-                path,
-                rom_bank_0,
-                cartridge_type_code,
-                num_rom_banks,
-                num_ram_banks
-            ))
+    Some(num_ram_banks * 0x2000)
+}
+
+// MBC1M multicarts say they're plain MBC1 in the header. They're all 1MB with
+// a game every 256KB, so the second game's header having the logo gives them away.
+fn is_mbc1_multicart(rom: &[u8]) -> bool {
+    if rom.len() != 64 * 0x4000 { return false }
+
+    let logo_addr = 0x10 * 0x4000 + 0x104;
+    rom[logo_addr..logo_addr + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+}
+
+// Reads from the fixed bank 0 or the switchable bank at 0x4000 - 0x7FFF.
+// Banks past the end of the rom wrap around, as the upper address lines aren't connected.
+fn read_banked_rom(rom: &[u8], bank: usize, addr: u16) -> u8 {
+    match addr & 0xF000 {
+        0x0000 | 0x1000 | 0x2000 | 0x3000 => {
+            rom[addr as usize]
         }
 
-        0x1A..=0x1E => {
-            println!("MBC5 cart created!");
-            Box::new(MBC5::new(
-                file,
-                path,
-                rom_bank_0,
-                cartridge_type_code,
-                num_rom_banks,
-                num_ram_banks
-            ))
+        0x4000 | 0x5000 | 0x6000 | 0x7000 => {
+            let bank = bank % (rom.len() / 0x4000).max(1);
+            rom[bank * 0x4000 + (addr - 0x4000) as usize]
         }
 
-        _ => unimplemented!("Unable to handle cartridge type: {:#04X}", cartridge_type_code)
+        _ => panic!()
     }
 }
 
+// Same goes for ram, which also might be smaller than a bank. It can't be empty.
+fn ram_bank_addr(ram: &[u8], bank: usize, addr: u16) -> usize {
+    (bank * 0x2000 + addr as usize) % ram.len()
+}
+
 fn get_save_file_path_from_rom_path(path: &Path) -> PathBuf {
     let mut save_file_path = PathBuf::from(path);
     save_file_path.pop();
@@ -126,51 +422,29 @@ fn get_save_file_path_from_rom_path(path: &Path) -> PathBuf {
     save_file_path
 }
 
-fn try_read_save_file(sav_file: Result<File, Error>, num_ram_banks: u16, ram_banks: &mut Vec<[u8; 0x2000]>,) {
-    match sav_file {
-        Ok(mut file) => {
-            let mut buf: Vec<u8> = Vec::new();
-            let read_result = file.read_to_end(&mut buf);
-            match read_result {
-                Ok(_) => {
-                    let bytes_read = buf.len();
-                    if bytes_read != num_ram_banks as usize * 0x2000 {
-                        println!(
-                            "Save file was an unexpected length. Expected {}, actual: {}",
-                            num_ram_banks as usize * 0x2000,
-                            bytes_read
-                        );
-                    }
-                    else {
-                        // load save file
-                        load_new_ram(ram_banks, num_ram_banks);
-                        let mut index: usize = 0;
-                        for bank in ram_banks {
-                            for i in 0..0x2000 {
-                                bank[i] = buf[index];
-                                index += 1;
-                            }
-                        }
-                        println!("Save file loaded!");
-                    }
-                }
-
-                Err(_) => {
-                    load_new_ram(ram_banks, num_ram_banks);
-                }
-            }
-        }
+// Blank ram if there's no save file, or it's the wrong size
+fn read_save_file(save_file_path: &Path, ram_size: usize) -> Vec<u8> {
+    let mut ram = vec![0; ram_size];
 
-        Err(_) => {
-            load_new_ram(ram_banks, num_ram_banks);
-        }
-    }
-}
+    let sav_file = File::open(save_file_path);
+    if sav_file.is_err() { return ram }
 
-fn load_new_ram(ram_banks: &mut Vec<[u8; 0x2000]>, num_ram_banks: u16) {
-    // fill ram banks with blank memory
-    for _ in 0..num_ram_banks {
-        let bank = [0; 0x2000];
-        ram_banks.push(bank);
+    let mut buf: Vec<u8> = Vec::new();
+    if sav_file.unwrap().read_to_end(&mut buf).is_err() { return ram }
+
+    if buf.len() != ram_size {
+        emu_warn!(
+            target: "gameboy_rs::cartridge",
+            "Save file was an unexpected length. Expected {}, actual: {}",
+            ram_size,
+            buf.len()
+        );
+    }
+    else {
+        // load save file
+        ram.copy_from_slice(&buf);
+        emu_info!(target: "gameboy_rs::cartridge", "Save file loaded!");
     }
+
+    ram
 }
@@ -0,0 +1,53 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
+use super::Mapper;
+
+// Just 32KB of rom, nothing to bank
+pub struct NoMbc;
+
+impl Default for NoMbc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoMbc {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Mapper for NoMbc {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr & 0xF000 {
+            0x0000 | 0x1000 | 0x2000 | 0x3000 |
+            0x4000 | 0x5000 | 0x6000 | 0x7000 => {
+                rom[addr as usize]
+            }
+
+            _ => panic!("Invalid address when reading from ROM cart")
+        }
+    }
+
+    fn write_control(&mut self, _addr: u16, _value: u8) {
+        // NOP
+    }
+
+    // This cart has no ram?
+
+    fn read_ram(&self, _ram: &[u8], _addr: u16) -> u8 {
+        0
+    }
+
+    fn write_ram(&mut self, _ram: &mut [u8], _addr: u16, _value: u8) {
+        
+    }
+
+    fn save_state(&self, _state: &mut StateWriter) {
+        // nothing to save
+    }
+
+    fn load_state(&mut self, _state: &mut StateReader) {
+
+    }
+}
@@ -0,0 +1,144 @@
+use std::{error, fmt};
+
+// Game Genie codes patch ROM reads, GameShark codes overwrite RAM every VBlank.
+// https://gbdev.io/pandocs/Shark_Cheats.html
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CheatError {
+    // Game Genie codes are 6 or 9 digits and GameShark codes are 8, ignoring dashes
+    InvalidLength,
+    InvalidCharacter(char)
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheatError::InvalidLength =>
+                write!(f, "Cheat codes must be 6 or 9 (Game Genie) or 8 (GameShark) digits long"),
+            CheatError::InvalidCharacter(c) =>
+                write!(f, "Invalid character in cheat code: {}", c)
+        }
+    }
+}
+
+impl error::Error for CheatError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CheatKind {
+    // Reads of the address return the value, if the ROM has the compare value there.
+    // Without a compare value the address is always patched.
+    GameGenie { addr: u16, val: u8, compare: Option<u8> },
+    GameShark { addr: u16, val: u8 }
+}
+
+struct Cheat {
+    code: String,
+    kind: CheatKind
+}
+
+pub struct Cheats {
+    cheats: Vec<Cheat>
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Self {
+            cheats: Vec::new()
+        }
+    }
+
+    // Dashes and spaces are ignored and the code isn't case sensitive
+    fn normalise(code: &str) -> String {
+        code.chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase()
+    }
+
+    fn parse(code: &str) -> Result<CheatKind, CheatError> {
+        let mut digits = Vec::with_capacity(code.len());
+        for c in code.chars() {
+            match c.to_digit(16) {
+                Some(digit) => digits.push(digit as u8),
+                None => return Err(CheatError::InvalidCharacter(c))
+            }
+        }
+
+        match digits.len() {
+            // ABC-DEF-GHI
+            // AB is the new value, FCDE is the address xored with 0xF000,
+            // GI is the compare value xored with 0xBA and rotated left twice, H is unused
+            6 | 9 => {
+                let val = (digits[0] << 4) | digits[1];
+                let addr = ((digits[5] as u16 ^ 0xF) << 12) |
+                    ((digits[2] as u16) << 8) |
+                    ((digits[3] as u16) << 4) |
+                    digits[4] as u16;
+
+                let compare = match digits.len() {
+                    9 => Some(((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xBA),
+                    _ => None
+                };
+
+                Ok(CheatKind::GameGenie { addr, val, compare })
+            }
+
+            // ttvvaaaa
+            // tt is the type (the ram bank on the CGB), vv the value and aaaa the address, low byte first
+            8 => {
+                let val = (digits[2] << 4) | digits[3];
+                let addr = ((digits[6] as u16) << 12) |
+                    ((digits[7] as u16) << 8) |
+                    ((digits[4] as u16) << 4) |
+                    digits[5] as u16;
+
+                Ok(CheatKind::GameShark { addr, val })
+            }
+
+            _ => Err(CheatError::InvalidLength)
+        }
+    }
+
+    pub fn add(&mut self, code: &str) -> Result<(), CheatError> {
+        let code = Self::normalise(code);
+        let kind = Self::parse(&code)?;
+
+        self.cheats.push(Cheat { code, kind });
+        Ok(())
+    }
+
+    // Returns false if the code wasn't active
+    pub fn remove(&mut self, code: &str) -> bool {
+        let code = Self::normalise(code);
+        let len = self.cheats.len();
+        self.cheats.retain(|cheat| cheat.code != code);
+
+        self.cheats.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+    }
+
+    pub fn patch_rom_read(&self, addr: u16, val: u8) -> u8 {
+        for cheat in &self.cheats {
+            if let CheatKind::GameGenie { addr: cheat_addr, val: cheat_val, compare } = cheat.kind {
+                if cheat_addr == addr && (compare.is_none() || compare.unwrap() == val) {
+                    return cheat_val;
+                }
+            }
+        }
+
+        val
+    }
+
+    // (address, value) pairs to write at VBlank
+    pub fn ram_writes(&self) -> Vec<(u16, u8)> {
+        self.cheats.iter()
+            .filter_map(|cheat| match cheat.kind {
+                CheatKind::GameShark { addr, val } => Some((addr, val)),
+                _ => None
+            })
+            .collect()
+    }
+}
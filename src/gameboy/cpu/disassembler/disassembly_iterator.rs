@@ -0,0 +1,71 @@
+use std::{collections::VecDeque, ops::Deref};
+
+use crate::gameboy::mmu::Mmu;
+
+use super::{Instruction, disassemble, disassemble_cb_prefix_op};
+
+// These opcodes don't exist, real hardware locks up if it tries to run one
+const ILLEGAL_OPCODES: [u8; 11] = [
+    0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD
+];
+
+pub fn is_illegal_opcode(opcode: u8) -> bool {
+    ILLEGAL_OPCODES.contains(&opcode)
+}
+
+// Walks through memory one instruction at a time, yielding the address of each
+// instruction, the instruction and the operand bytes following the opcode.
+// Illegal opcodes are yielded as an "ILLEGAL" instruction that is one byte long.
+// Stops once the end of the address space is reached.
+pub struct DisassemblyIterator<M: Deref<Target = Mmu>> {
+    mmu: M,
+    addr: u16,
+    done: bool
+}
+
+impl<M: Deref<Target = Mmu>> DisassemblyIterator<M> {
+    pub fn new(mmu: M, addr: u16) -> Self {
+        Self {
+            mmu,
+            addr,
+            done: false
+        }
+    }
+}
+
+impl<M: Deref<Target = Mmu>> Iterator for DisassemblyIterator<M> {
+    type Item = (u16, Instruction, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+
+        let addr = self.addr;
+        let opcode = self.mmu.peek_byte(addr);
+
+        let instruction = if is_illegal_opcode(opcode) {
+            Instruction {
+                opcode_val: opcode,
+                human_readable: String::from("ILLEGAL"),
+                length: 1,
+                steps: VecDeque::new()
+            }
+        } else if opcode == 0xCB {
+            disassemble_cb_prefix_op(self.mmu.peek_byte(addr.wrapping_add(1)))
+        } else {
+            disassemble(opcode)
+        };
+
+        let operands = (1..instruction.length as u16)
+            .map(|i| self.mmu.peek_byte(addr.wrapping_add(i)))
+            .collect();
+
+        let next_addr = addr as u32 + instruction.length as u32;
+        if next_addr > 0xFFFF {
+            self.done = true;
+        } else {
+            self.addr = next_addr as u16;
+        }
+
+        Some((addr, instruction, operands))
+    }
+}
@@ -6,6 +6,7 @@ use self::disassembler_table::ArithmeticOp;
 
 use super::{Cpu};
 mod disassembler_table;
+pub mod disassembly_iterator;
 
 // TODO: from line ~100 to ~300, improve usage of closures using the move keyword. op?
 
@@ -18,10 +19,12 @@ pub struct Instruction {
     pub opcode_val: u8,
     pub human_readable: String,
     pub length: u8, // bytes
-    pub steps: VecDeque<InstructionStep> // bool is if we branch or not
+    pub steps: VecDeque<InstructionStep>
 }
 
 pub enum InstructionStep {
+    // Checks a branch condition, if it returns false the rest of the steps are dropped.
+    // A taken branch's extra cycles go after it so they are only spent when the branch is taken.
     InstantConditional(Box<dyn Fn(&mut Cpu) -> bool>),
     Standard(Box<dyn Fn(&mut Cpu)>), // takes 4 clock cycles
     Instant(Box<dyn Fn(&mut Cpu)>)
@@ -86,14 +89,14 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                     // 12t
                     push_fetch_operand16_closures(&mut steps);
 
-                    // 16t
-                    steps.push_back(InstructionStep::Standard(Box::new(|_cpu: &mut Cpu| { 
-                        // blank instr
+                    // 16t, low byte first
+                    steps.push_back(InstructionStep::Standard(Box::new(|cpu: &mut Cpu| { 
+                        (*cpu.mmu).borrow_mut().write_byte(cpu.operand16, cpu.sp as u8);
                     })));
 
                     // 20t
                     steps.push_back(InstructionStep::Standard(Box::new(|cpu: &mut Cpu| { 
-                        (*cpu.mmu).borrow_mut().write_word(cpu.operand16, cpu.sp);
+                        (*cpu.mmu).borrow_mut().write_byte(cpu.operand16.wrapping_add(1), (cpu.sp >> 8) as u8);
                     })));
 
                     Instruction {
@@ -107,7 +110,7 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                 2 => {
                     steps.push_back(InstructionStep::Instant(Box::new(|cpu: &mut Cpu| { 
                         cpu.stopped = true;
-                        println!("ENTERED STOP MODE");
+                        emu_info!(target: "gameboy_rs::cpu", "ENTERED STOP MODE");
                     })));
                     Instruction {
                         opcode_val: opcode,
@@ -339,6 +342,8 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                 _ => unreachable!()
             };
             let op_fn = |cpu: &mut Cpu, setter: fn(&mut Cpu, val: u16), getter: fn(&Cpu) -> u16, is_inc: bool| {
+                (*cpu.mmu).borrow_mut().oam_bug_write(getter(cpu));
+
                 let result = match is_inc {
                     true => getter(cpu).wrapping_add(1),
                     false => getter(cpu).wrapping_sub(1)
@@ -406,7 +411,7 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
             Instruction {
                 opcode_val: opcode,
                 human_readable: format!("{} {}", op_str, reg.to_string()),
-                length: if matches!(reg, Register::HLMem) { 12 } else { 4 },
+                length: 1,
                 steps
             }
         }
@@ -438,7 +443,7 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
             Instruction {
                 opcode_val: opcode,
                 human_readable: format!("LD {}, u8", reg.to_string()),
-                length: if matches!(reg, Register::HLMem) { 12 } else { 8 },
+                length: 2,
                 steps
             }
         }
@@ -476,6 +481,8 @@ fn dissassemble_x_0(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                     String::from("RRCA")
                 }
 
+                // RLA and RRA rotate through the carry, the old carry has to be
+                // read before C is set from the bit that's rotated out
                 2 => {
                     let instruction_step = InstructionStep::Instant(Box::new(|cpu| {
                         let is_carry_set = cpu.is_flag_set(Flag::C);
@@ -598,8 +605,16 @@ fn dissassemble_x_1(y: u8, z: u8, _p: u8, _q: u8, opcode: u8) -> Instruction {
             let mut mmu = (*cpu.mmu).borrow_mut();
             
             if mmu.interupts.is_master_enabled() {
-                // IME set
-                cpu.halted = true;
+                if cpu.ei_took_effect && mmu.interupts.enable & mmu.interupts.flags & 0x1F != 0 {
+                    // IME only just set by an EI right before, interupt pending
+                    // the interupt is taken straight away but the pc never got past
+                    // the HALT, so the handler returns to the HALT and it runs again
+                    cpu.halt_bug = true;
+                }
+                else {
+                    // IME set
+                    cpu.halted = true;
+                }
             } 
             else {
                 if mmu.interupts.enable & mmu.interupts.flags & 0x1F != 0 {
@@ -721,7 +736,7 @@ fn dissassemble_x_2(y: u8, z: u8, _p: u8, _q: u8, opcode: u8) -> Instruction {
     Instruction {
         opcode_val: opcode,
         human_readable: format!("{}, {}", arithmetic_op.to_string(), register_operand.to_string()),
-        length: if matches!(register_operand, Register::HLMem) { 8 } else { 4 },
+        length: 1,
         steps
     }
 }
@@ -867,10 +882,7 @@ fn dissassemble_x_3(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                             RegisterPair2::BC => cpu.c = low,
                             RegisterPair2::DE => cpu.e = low,
                             RegisterPair2::HL => cpu.l = low,
-                            RegisterPair2::AF => { 
-                                cpu.f = low;
-                                cpu.f = cpu.f & 0xF0;
-                            },
+                            RegisterPair2::AF => cpu.set_af(((cpu.a as u16) << 8) | low as u16),
                         }
                     })));
 
@@ -881,10 +893,7 @@ fn dissassemble_x_3(y: u8, z: u8, p: u8, q: u8, opcode: u8) -> Instruction {
                             RegisterPair2::BC => cpu.b = high,
                             RegisterPair2::DE => cpu.d = high,
                             RegisterPair2::HL => cpu.h = high,
-                            RegisterPair2::AF => { 
-                                cpu.a = high;
-                                cpu.f = cpu.f & 0xF0;
-                            },
+                            RegisterPair2::AF => cpu.set_af(((high as u16) << 8) | cpu.f as u16),
                         }
                     })));
 
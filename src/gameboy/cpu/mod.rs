@@ -1,11 +1,52 @@
-use std::{cell::RefCell, fmt, fs::File, io::Write, rc::Rc, time::{SystemTime, UNIX_EPOCH}};
+use std::{cell::RefCell, collections::VecDeque, fmt, fs::File, io::Write, rc::Rc};
 use crate::gameboy::cpu::disassembler::disassemble_cb_prefix_op;
 
-use self::disassembler::{Instruction, InstructionStep, disassemble};
-use super::mmu::Mmu;
+use self::disassembler::{Instruction, InstructionStep, disassemble, disassembly_iterator::is_illegal_opcode};
+use super::{interupt::{InterruptFlag, InterruptState, Interupt}, mmu::Mmu, save_state::{StateReader, StateWriter}, status::{DebugWarning, EmuError}};
 
 pub mod disassembler;
 
+// Clock cycles (dots) per machine cycle. CGB double speed would halve this, but there's no CGB mode.
+pub(super) const CYCLES_PER_MACHINE_CYCLE: u8 = 4;
+
+// Jumps that do nothing but jump, if one of these lands back on itself the cpu
+// can't do anything else until an interrupt: JR, JR cc, JP and JP cc
+const IDLE_LOOP_OPCODES: [u8; 10] = [0x18, 0x20, 0x28, 0x30, 0x38, 0xC3, 0xC2, 0xCA, 0xD2, 0xDA];
+
+// A copy of the cpu's registers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+
+    pub sp: u16,
+    pub pc: u16
+}
+
+// The flags in F, decoded. The lower nibble of F is always 0 so there's nothing else in it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flags {
+    pub z: bool,
+    pub n: bool,
+    pub h: bool,
+    pub c: bool
+}
+
+// What the current instruction was disassembled from, so it can be disassembled again when a
+// state saved part way through it is loaded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum InstructionSource {
+    Opcode(u8),
+    CbPrefixed(u8),
+    Interrupt
+}
+
 enum Flag {
     Z = 0b10000000,
     N = 0b01000000, // N = last math op was subtract
@@ -30,12 +71,21 @@ pub struct Cpu {
 
     operand8: u8,
     operand16: u16,
+    // Fetched instead of the bytes at PC while execute_opcode runs
+    fetch_override: VecDeque<u8>,
     pub temp_val8: u8,
     pub temp_val_16: u16,
 
     pub is_fetching: bool,
     instruction: Option<Instruction>,
+    instruction_source: InstructionSource,
+    // where the current instruction's opcode was fetched from, None for an interrupt dispatch
+    pub instruction_addr: Option<u16>,
     machine_cycles_taken_for_current_step: u8,
+    // Every instruction fetched since power on, for performance metrics. Not saved.
+    pub instruction_count: u64,
+    // Spinning on a jump to itself, see IDLE_LOOP_OPCODES. Worked out again on every fetch, so not saved.
+    pub idle: bool,
 
     pub stopped: bool,
     pub halted: bool,
@@ -43,6 +93,16 @@ pub struct Cpu {
     halt_bug: bool,
     ei_delay: bool,
     ei_delay_cycles: u8,
+    // IME was turned on by an EI since the current instruction was fetched, for HALT straight after an EI
+    ei_took_effect: bool,
+
+    pub error: Option<EmuError>,
+    // Off by default, see check_stack
+    pub trap_stack_corruption: bool,
+
+    pub debug_warnings: Vec<DebugWarning>,
+    stack_out_of_range: bool,
+    pc_out_of_range: bool,
 
     debug: bool,
     pub start_log: bool,
@@ -85,12 +145,17 @@ impl Cpu {
 
             operand8: 0,
             operand16: 0,
+            fetch_override: VecDeque::new(),
             temp_val8: 0,
             temp_val_16: 0,
 
             is_fetching: false,
             instruction: None,
+            instruction_source: InstructionSource::Opcode(0x00),
+            instruction_addr: None,
             machine_cycles_taken_for_current_step: 0,
+            instruction_count: 0,
+            idle: false,
 
             stopped: false,
             halted: false,
@@ -98,6 +163,14 @@ impl Cpu {
             halt_bug: false,
             ei_delay: false,
             ei_delay_cycles: 0,
+            ei_took_effect: false,
+
+            error: None,
+            trap_stack_corruption: false,
+
+            debug_warnings: Vec::new(),
+            stack_out_of_range: false,
+            pc_out_of_range: false,
 
             debug: true,
             start_log: false,
@@ -109,13 +182,41 @@ impl Cpu {
         self.instruction.is_some()
     }
 
+    // How many clock cycles one time round an idle loop takes, if the cpu is about to fetch the
+    // loop's jump again and it can only do exactly what it did last time. The jump has to be read
+    // from somewhere the ppu can't lock, with no DMA in the way, and the flags a conditional jump
+    // tests can't have changed as nothing else runs. An interrupt is the only way out.
+    pub fn idle_loop_cycles(&self) -> Option<u64> {
+        if !self.idle || self.instruction.is_some() || self.instruction_addr != Some(self.pc) { return None }
+        if self.halted || self.stopped || self.halt_bug || self.ei_delay { return None }
+
+        let mmu = (*self.mmu).borrow();
+        let readable = self.pc < 0x8000 || (0xC000..0xFE00).contains(&self.pc) || (0xFF80..0xFFFF).contains(&self.pc);
+        if !readable || mmu.is_dma_running() { return None }
+
+        match mmu.read_byte(self.pc) {
+            0x18 | 0x20 | 0x28 | 0x30 | 0x38 => Some(12),
+            0xC3 | 0xC2 | 0xCA | 0xD2 | 0xDA => Some(16),
+            _ => None
+        }
+    }
+
     pub fn set_interrupt_instruction(&mut self, instruction: Instruction) {
         if self.instruction.is_some() {
             // undo the fetch?
             self.pc -= 1;
         }
+        else if self.halt_bug {
+            // the byte after HALT hasn't been re-read yet, the pc still never
+            // got incremented past it so the handler returns to the HALT itself
+            self.pc = self.pc.wrapping_sub(1);
+            self.halt_bug = false;
+        }
         self.instruction = Some(instruction);
+        self.instruction_source = InstructionSource::Interrupt;
+        self.instruction_addr = None;
         self.is_fetching = false;
+        self.idle = false;
     }
 
     // FLAG FUNCS
@@ -152,6 +253,7 @@ impl Cpu {
         }
     }
 
+    // INC and DEC leave C alone, so counting a loop down can't lose a pending carry
     fn inc(&mut self, val: u8) -> u8 {
         self.set_flag_if_cond_else_clear((val & 0x0F) == 0x0F, Flag::H);
 
@@ -418,7 +520,8 @@ impl Cpu {
     fn set_af(&mut self, val: u16) {
         // a = high bits, f = low bits
         self.a = ((val & 0xFF00) >> 8) as u8;
-        self.f = (val & 0x00FF) as u8; // not sure if we need to & here
+        // the lower nibble of F doesn't exist
+        self.f = (val & 0x00F0) as u8;
     }
 
     fn set_bc(&mut self, val: u16) {
@@ -436,8 +539,13 @@ impl Cpu {
         self.l = (val & 0x00FF) as u8;
     }
 
+    // Goes through the memory bus like any other read, so code in 0x4000 - 0x7FFF comes
+    // from whichever bank is switched in at the moment each byte is fetched
     fn fetch(&mut self) -> u8 {
-        let op = (*self.mmu).borrow().read_byte(self.pc);
+        let op = match self.fetch_override.pop_front() {
+            Some(op) => op,
+            None => (*self.mmu).borrow().read_byte(self.pc)
+        };
         self.pc += 1;
         op
     }
@@ -451,34 +559,363 @@ impl Cpu {
     fn write_word_to_stack(&mut self, val: u16) {
         // self.sp -= 2;
         self.sp = self.sp.wrapping_sub(2);
+        self.check_stack();
+        self.check_stack_range();
         (*self.mmu).borrow_mut().write_word(self.sp, val);
     }
 
     pub(super) fn write_byte_to_stack(&mut self, val: u8) {
         self.sp = self.sp.wrapping_sub(1);
+        self.check_stack();
+        self.check_stack_range();
         (*self.mmu).borrow_mut().write_byte(self.sp, val);
     }
 
+    // No game keeps its stack in ROM, if it gets pushed to then the game has crashed.
+    // Test ROMs do it on purpose though, so it's only an error when asked for.
+    fn check_stack(&mut self) {
+        if self.trap_stack_corruption && self.sp < 0x8000 {
+            self.error = Some(EmuError::StackCorruption { sp: self.sp, pc: self.pc });
+        }
+    }
+
     fn read_word_from_stack(&mut self) -> u16 {
+        self.check_stack_range();
         let val: u16 = (*self.mmu).borrow().read_word(self.sp);
-        self.sp += 2;
+        self.sp = self.sp.wrapping_add(2);
         val
     }
 
     fn read_byte_from_stack(&mut self) -> u8 {
+        self.check_stack_range();
         let val: u8 = (*self.mmu).borrow().read_byte(self.sp);
-        self.sp += 1;
+        self.sp = self.sp.wrapping_add(1);
         val
     }
 
+    // DEBUG CHECKS
+    // These only run in debug builds, and only warn when first going out of range
+    // so a crashed game doesn't flood the warnings.
+
+    #[cfg(debug_assertions)]
+    fn check_stack_range(&mut self) {
+        let in_range = (self.sp >= 0xC000 && self.sp <= 0xDFFF) || 
+            (self.sp >= 0xFF80 && self.sp <= 0xFFFE);
+
+        if !in_range && !self.stack_out_of_range {
+            self.debug_warn(DebugWarning::StackOutOfRange { sp: self.sp, pc: self.pc });
+        }
+
+        self.stack_out_of_range = !in_range;
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn check_stack_range(&mut self) { }
+
+    #[cfg(debug_assertions)]
+    fn check_pc_range(&mut self, pc: u16) {
+        let in_range = pc < 0xFEA0 || (0xFF80..=0xFFFE).contains(&pc);
+
+        if !in_range && !self.pc_out_of_range {
+            self.debug_warn(DebugWarning::WildPc { pc });
+        }
+
+        self.pc_out_of_range = !in_range;
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn check_pc_range(&mut self, _pc: u16) { }
+
+    #[cfg(debug_assertions)]
+    fn debug_warn(&mut self, warning: DebugWarning) {
+        emu_warn!(target: "gameboy_rs::cpu", "{}", warning);
+        self.debug_warnings.push(warning);
+    }
+
     // MISC
 
     pub(super) fn set_pc(&mut self, pc: u16) {
         self.pc = pc;
     }
 
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self.a,
+            f: self.f,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            h: self.h,
+            l: self.l,
+
+            sp: self.sp,
+            pc: self.pc
+        }
+    }
+
+    pub fn flags(&self) -> Flags {
+        Flags {
+            z: self.is_flag_set(Flag::Z),
+            n: self.is_flag_set(Flag::N),
+            h: self.is_flag_set(Flag::H),
+            c: self.is_flag_set(Flag::C)
+        }
+    }
+
+    pub fn set_flags(&mut self, flags: Flags) {
+        self.set_flag_if_cond_else_clear(flags.z, Flag::Z);
+        self.set_flag_if_cond_else_clear(flags.n, Flag::N);
+        self.set_flag_if_cond_else_clear(flags.h, Flag::H);
+        self.set_flag_if_cond_else_clear(flags.c, Flag::C);
+    }
+
+    pub fn interrupt_state(&self) -> InterruptState {
+        self.mmu.borrow().interupts.state()
+    }
+
+    // Sets the bit in IF straight away, whether or not it's enabled is still up to IE and IME
+    pub fn force_interrupt(&mut self, flag: InterruptFlag) {
+        self.mmu.borrow_mut().interupts.request_interupt(flag);
+    }
+
+    // Does everything the dispatch does at once instead of over 5 machine cycles:
+    // pushes PC, clears the flag in IF, disables IME and jumps to the vector.
+    // IE and IME aren't checked, the interrupt is taken regardless.
+    pub fn dispatch_interrupt(&mut self, flag: InterruptFlag) -> u16 {
+        self.write_byte_to_stack((self.pc >> 8) as u8);
+        self.write_byte_to_stack((self.pc & 0x00FF) as u8);
+
+        {
+            let mut mmu = self.mmu.borrow_mut();
+            mmu.interupts.clear_interupt(flag);
+            mmu.interupts.disable_master();
+        }
+
+        self.halted = false;
+        self.idle = false;
+        self.set_pc(Interupt::get_interupt_vector(flag));
+        self.pc
+    }
+
+    // Both ways out of HALT end up here, on the cycle the cpu first sees the interrupt. With
+    // IME set the 20 cycle dispatch starts on it, with IME clear the instruction after HALT
+    // is fetched on it. TCAGBD (section 4.9) has leaving HALT take an extra 4 cycles, but with
+    // how this cpu and ppu line up, adding them makes mooneye's halt_ime0_nointr_timing,
+    // halt_ime1_timing2 and ppu intr_* tests fail, so the wake up counts as 0 cycles.
+    pub fn wake_from_halt(&mut self) {
+        self.halted = false;
+        self.halted_waiting_for_interupt_pending = false;
+    }
+
+    // For testing opcodes on their own: runs the whole instruction straight away, without
+    // waiting on any cycles. Its operand is op8 or op16 rather than the bytes after PC, op8 is
+    // the second byte for CB opcodes. PC still moves on past the opcode and operand as if they
+    // had been read from there. Returns the machine cycles the instruction would have taken.
+    pub fn execute_opcode(&mut self, opcode: u8, op8: u8, op16: u16) -> Result<u32, EmuError> {
+        if is_illegal_opcode(opcode) {
+            return Err(EmuError::IllegalOpcode { opcode, addr: self.pc });
+        }
+
+        self.pc = self.pc.wrapping_add(1);
+
+        let mut instruction = match opcode {
+            0xCB => {
+                self.pc = self.pc.wrapping_add(1);
+                disassemble_cb_prefix_op(op8)
+            }
+
+            _ => disassemble(opcode)
+        };
+
+        self.fetch_override.clear();
+        match instruction.length {
+            2 if opcode != 0xCB => self.fetch_override.push_back(op8),
+            3 => self.fetch_override.extend(op16.to_le_bytes().iter()),
+            _ => { }
+        }
+
+        let mut machine_cycles = 0;
+        while let Some(step) = instruction.steps.pop_front() {
+            match step {
+                InstructionStep::Standard(func) => {
+                    func(self);
+                    machine_cycles += 1;
+                }
+
+                InstructionStep::Instant(func) => func(self),
+
+                InstructionStep::InstantConditional(func) => {
+                    if !func(self) { break }
+                }
+            }
+        }
+
+        self.fetch_override.clear();
+        Ok(machine_cycles)
+    }
+
+    // How many machine cycles an opcode takes, without running it. cb_opcode is the second
+    // byte for CB opcodes. Conditional branches take longer when branch_taken, it makes no
+    // difference to anything else. None for the illegal opcodes.
+    pub fn cycles_for_opcode(opcode: u8, cb_opcode: u8, branch_taken: bool) -> Option<u32> {
+        if is_illegal_opcode(opcode) { return None }
+
+        let instruction = match opcode {
+            0xCB => disassemble_cb_prefix_op(cb_opcode),
+            _ => disassemble(opcode)
+        };
+
+        let mut machine_cycles = 0;
+        for step in instruction.steps.iter() {
+            match step {
+                InstructionStep::Standard(_) => machine_cycles += 1,
+                InstructionStep::Instant(_) => { }
+                InstructionStep::InstantConditional(_) => if !branch_taken { break }
+            }
+        }
+
+        Some(machine_cycles)
+    }
+
+    // The lower nibble of F doesn't exist, so it always reads back as 0
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.a = registers.a;
+        self.f = registers.f & 0xF0;
+        self.b = registers.b;
+        self.c = registers.c;
+        self.d = registers.d;
+        self.e = registers.e;
+        self.h = registers.h;
+        self.l = registers.l;
+
+        self.sp = registers.sp;
+        self.pc = registers.pc;
+    }
+
+    // SAVE STATES
+
+    // The steps of an instruction are closures which can't be saved, so an instruction in
+    // progress is saved as its opcode and how many steps it has left, see rebuild_instruction.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.a);
+        state.write_u8(self.b);
+        state.write_u8(self.c);
+        state.write_u8(self.d);
+        state.write_u8(self.e);
+        state.write_u8(self.f);
+        state.write_u8(self.h);
+        state.write_u8(self.l);
+
+        state.write_u16(self.pc);
+        state.write_u16(self.sp);
+
+        state.write_u8(self.operand8);
+        state.write_u16(self.operand16);
+        state.write_u8(self.temp_val8);
+        state.write_u16(self.temp_val_16);
+
+        state.write_bool(self.is_fetching);
+        state.write_u8(self.machine_cycles_taken_for_current_step);
+
+        match self.instruction {
+            Some(ref instruction) => {
+                match self.instruction_source {
+                    InstructionSource::Opcode(opcode) => { state.write_u8(1); state.write_u8(opcode); }
+                    InstructionSource::CbPrefixed(cb_opcode) => { state.write_u8(2); state.write_u8(cb_opcode); }
+                    InstructionSource::Interrupt => { state.write_u8(3); state.write_u8(0); }
+                }
+                state.write_u8(instruction.steps.len() as u8);
+            }
+
+            None => {
+                state.write_u8(0);
+                state.write_u8(0);
+                state.write_u8(0);
+            }
+        }
+
+        state.write_bool(self.instruction_addr.is_some());
+        state.write_u16(self.instruction_addr.unwrap_or(0));
+
+        state.write_bool(self.stopped);
+        state.write_bool(self.halted);
+        state.write_bool(self.halted_waiting_for_interupt_pending);
+        state.write_bool(self.halt_bug);
+        state.write_bool(self.ei_delay);
+        state.write_u8(self.ei_delay_cycles);
+        state.write_bool(self.ei_took_effect);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.a = state.read_u8();
+        self.b = state.read_u8();
+        self.c = state.read_u8();
+        self.d = state.read_u8();
+        self.e = state.read_u8();
+        self.f = state.read_u8();
+        self.h = state.read_u8();
+        self.l = state.read_u8();
+
+        self.pc = state.read_u16();
+        self.sp = state.read_u16();
+
+        self.operand8 = state.read_u8();
+        self.operand16 = state.read_u16();
+        self.temp_val8 = state.read_u8();
+        self.temp_val_16 = state.read_u16();
+
+        self.is_fetching = state.read_bool();
+        self.machine_cycles_taken_for_current_step = state.read_u8();
+
+        let source = match (state.read_u8(), state.read_u8()) {
+            (1, opcode) => Some(InstructionSource::Opcode(opcode)),
+            (2, cb_opcode) => Some(InstructionSource::CbPrefixed(cb_opcode)),
+            (3, _) => Some(InstructionSource::Interrupt),
+            _ => None
+        };
+        let steps_left = state.read_u8() as usize;
+
+        self.instruction = None;
+        if let Some(source) = source {
+            self.instruction_source = source;
+            self.instruction = Some(Self::rebuild_instruction(source, steps_left));
+        }
+
+        let has_instruction_addr = state.read_bool();
+        let instruction_addr = state.read_u16();
+        self.instruction_addr = if has_instruction_addr { Some(instruction_addr) } else { None };
+
+        self.stopped = state.read_bool();
+        self.halted = state.read_bool();
+        self.halted_waiting_for_interupt_pending = state.read_bool();
+        self.halt_bug = state.read_bool();
+        self.ei_delay = state.read_bool();
+        self.ei_delay_cycles = state.read_u8();
+        self.ei_took_effect = state.read_bool();
+    }
+
+    // Disassembles the instruction again and drops the steps that had already run. The steps
+    // don't hold anything of their own, whatever they pass between each other is kept in the
+    // cpu's fields (operand8, temp_val_16 etc.) which are saved.
+    fn rebuild_instruction(source: InstructionSource, steps_left: usize) -> Instruction {
+        let mut instruction = match source {
+            InstructionSource::Opcode(opcode) => disassemble(opcode),
+            InstructionSource::CbPrefixed(cb_opcode) => disassemble_cb_prefix_op(cb_opcode),
+            InstructionSource::Interrupt => Interupt::create_interupt_instruction()
+        };
+
+        while instruction.steps.len() > steps_left {
+            instruction.steps.pop_front();
+        }
+
+        instruction
+    }
+
     // CYCLE FUNCTIONS
 
+    // Called once per clock cycle, the cpu only does something on the last
+    // clock cycle of each machine cycle
     pub fn tick(&mut self) {
         if self.ei_delay {
             self.ei_delay_cycles -= 1;
@@ -486,44 +923,58 @@ impl Cpu {
             if self.ei_delay_cycles == 0 {
                 (*self.mmu).borrow_mut().interupts.enable_master();   
                 self.ei_delay = false;
+                self.ei_took_effect = true;
             }
         }
 
         if self.halted { 
-            if self.halted_waiting_for_interupt_pending {
+            if !self.halted_waiting_for_interupt_pending { return }
+
+            {
                 let mut mmu = (*self.mmu).borrow_mut();
-                
+
                 if !mmu.interupts.halt_interupt_pending { return }
 
                 mmu.interupts.halt_interupt_pending = false;
                 mmu.interupts.waiting_for_halt_if = false;
-                self.halted_waiting_for_interupt_pending = false;
-                self.halted = false;
-            } 
-            else {
-                return
             }
+            self.wake_from_halt();
         }
 
         if self.instruction.is_none() {
             self.is_fetching = true;
+            self.ei_took_effect = false;
+            let opcode_addr = self.pc;
+            self.check_pc_range(opcode_addr);
             let opcode = self.fetch();
-            
-            {
-                let mut mmu = self.mmu.borrow_mut();
-                if mmu.bios_enabled && self.pc >= 0x100{
-                    mmu.bios_enabled = false;
-                }
-            }
 
+            // HALT bug: the pc failed to increment after this fetch, so the
+            // byte gets read again as the next opcode or operand
             if self.halt_bug {
-                self.pc -= 1;
+                self.pc = self.pc.wrapping_sub(1);
                 self.halt_bug = false;
             }
 
-            let instruction = match opcode {
-                0xCB => disassemble_cb_prefix_op(self.fetch()),
-                _ => disassemble(opcode)
+            if is_illegal_opcode(opcode) {
+                // the cpu locks up, the pc stays on the illegal opcode
+                self.pc = opcode_addr;
+                self.error = Some(EmuError::IllegalOpcode { opcode, addr: opcode_addr });
+                self.is_fetching = false;
+                return;
+            }
+
+            self.instruction_count += 1;
+
+            // instruction_addr is still the last instruction's, the only way back here is if it jumped here
+            self.idle = self.instruction_addr == Some(opcode_addr) && IDLE_LOOP_OPCODES.contains(&opcode);
+
+            let (instruction, source) = match opcode {
+                0xCB => {
+                    let cb_opcode = self.fetch();
+                    (disassemble_cb_prefix_op(cb_opcode), InstructionSource::CbPrefixed(cb_opcode))
+                }
+
+                _ => (disassemble(opcode), InstructionSource::Opcode(opcode))
             };
 
             if self.start_log {
@@ -559,11 +1010,13 @@ impl Cpu {
 
             self.machine_cycles_taken_for_current_step += 1;
             self.instruction = Some(instruction);
+            self.instruction_source = source;
+            self.instruction_addr = Some(opcode_addr);
             return;
         }
 
         self.machine_cycles_taken_for_current_step += 1;
-        if self.machine_cycles_taken_for_current_step < 4 {
+        if self.machine_cycles_taken_for_current_step < CYCLES_PER_MACHINE_CYCLE {
             return;
         }
 
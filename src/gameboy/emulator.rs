@@ -0,0 +1,49 @@
+use super::{GameBoy, input::Button, save_state::StateError, status::{EmuError, FrameStatus}};
+
+// Everything a frontend needs to run a game, so the same frontend code can drive
+// anything that implements it. It's object safe, a frontend can hold a Box<dyn Emulator>.
+pub trait Emulator {
+    // Runs until the next frame is ready
+    fn step_frame(&mut self) -> Result<FrameStatus, EmuError>;
+    // 160x144, one greyscale byte per pixel
+    fn framebuffer(&self) -> &[u8];
+
+    fn press_button(&mut self, button: Button);
+    fn release_button(&mut self, button: Button);
+
+    // Interleaved left/right samples at spu::SAMPLE_RATE
+    fn drain_audio(&mut self) -> Vec<f32>;
+
+    fn save_state(&mut self) -> Vec<u8>;
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError>;
+}
+
+impl Emulator for GameBoy {
+    fn step_frame(&mut self) -> Result<FrameStatus, EmuError> {
+        GameBoy::step_frame(self)
+    }
+
+    fn framebuffer(&self) -> &[u8] {
+        self.get_frame_buffer()
+    }
+
+    fn press_button(&mut self, button: Button) {
+        GameBoy::press_button(self, button);
+    }
+
+    fn release_button(&mut self, button: Button) {
+        GameBoy::release_button(self, button);
+    }
+
+    fn drain_audio(&mut self) -> Vec<f32> {
+        GameBoy::drain_audio(self)
+    }
+
+    fn save_state(&mut self) -> Vec<u8> {
+        GameBoy::save_state(self)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        GameBoy::load_state(self, state)
+    }
+}
@@ -0,0 +1,59 @@
+use std::{error, fmt, io};
+
+use super::{cartridge::info::cartridge_type_name, save_state::StateError};
+
+// Everything that can go wrong loading a ROM or a save state, so embedders
+// have one type to pass around with ? or as a Box<dyn Error>
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    // The header says the ROM only runs on a CGB
+    CgbOnly,
+    // The header's size codes aren't ones there's a size for
+    InvalidRomSize(u8),
+    InvalidRamSize(u8),
+    // The cartridge type from the header, its mapper hasn't been written yet
+    UnsupportedMapper(u8),
+    State(StateError)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => 
+                write!(f, "Unable to read the ROM: {}", err),
+            Error::CgbOnly => 
+                write!(f, "This ROM only runs on a Game Boy Color"),
+            Error::InvalidRomSize(code) => 
+                write!(f, "Cartridge header has an invalid ROM size code: {:#04X}", code),
+            Error::InvalidRamSize(code) => 
+                write!(f, "Cartridge header has an invalid RAM size code: {:#04X}", code),
+            Error::UnsupportedMapper(code) => 
+                write!(f, "Unsupported cartridge type {:#04X} ({})", code, cartridge_type_name(*code)),
+            Error::State(err) => 
+                write!(f, "Unable to load the save state: {}", err)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::State(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<StateError> for Error {
+    fn from(err: StateError) -> Self {
+        Error::State(err)
+    }
+}
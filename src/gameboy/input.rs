@@ -1,8 +1,86 @@
+use std::collections::HashMap;
+
 use sdl2::keyboard::Keycode;
 
+use super::save_state::{StateReader, StateWriter};
+
 
 // http://imrannazar.com/GameBoy-Emulation-in-JavaScript:-Input
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    Start,
+    Select,
+    A,
+    B
+}
+
+impl Button {
+    pub const ALL: [Button; 8] = [
+        Button::Right, Button::Left, Button::Up, Button::Down,
+        Button::A, Button::B, Button::Select, Button::Start
+    ];
+
+    // The bit for this button in a button mask. The directions are in the low nibble
+    // and the action buttons in the high nibble, in the same order as in JOYP.
+    pub fn mask(self) -> u8 {
+        match self {
+            Button::Right => 1 << 0,
+            Button::Left => 1 << 1,
+            Button::Up => 1 << 2,
+            Button::Down => 1 << 3,
+            Button::A => 1 << 4,
+            Button::B => 1 << 5,
+            Button::Select => 1 << 6,
+            Button::Start => 1 << 7
+        }
+    }
+}
+
+// Which buttons are held down, for frontends that draw the gamepad
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ButtonState {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub start: bool,
+    pub select: bool,
+    pub a: bool,
+    pub b: bool
+}
+
+// A key or button on the host side. Frontends turn whatever their input
+// library gives them into one of these, the raw values are up to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HostKey {
+    Keyboard(i32),
+    Gamepad(u32)
+}
+
+impl From<Keycode> for HostKey {
+    fn from(code: Keycode) -> Self {
+        HostKey::Keyboard(code as i32)
+    }
+}
+
+pub fn default_mapping() -> HashMap<HostKey, Button> {
+    let mut mapping = HashMap::new();
+    mapping.insert(Keycode::W.into(), Button::Up);
+    mapping.insert(Keycode::A.into(), Button::Left);
+    mapping.insert(Keycode::S.into(), Button::Down);
+    mapping.insert(Keycode::D.into(), Button::Right);
+    mapping.insert(Keycode::O.into(), Button::A);
+    mapping.insert(Keycode::K.into(), Button::B);
+    mapping.insert(Keycode::N.into(), Button::Select);
+    mapping.insert(Keycode::M.into(), Button::Start);
+    mapping
+}
+
 pub struct Input {
     pub up: u8,
     pub down: u8,
@@ -13,7 +91,14 @@ pub struct Input {
     pub a: u8,
     pub b: u8,
 
-    column_line: u8
+    column_line: u8,
+    mapping: HashMap<HostKey, Button>
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Input {
@@ -28,74 +113,143 @@ impl Input {
             a: 1,
             b: 1,
 
-            column_line: 0x30
-
+            column_line: 0x30,
+            mapping: default_mapping()
         }
     }
 
+    // The buttons follow the keyboard rather than the save state,
+    // otherwise a key let go of before loading would be stuck down.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.column_line);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.column_line = state.read_u8();
+    }
+
     pub fn set_column_line(&mut self, val: u8) {
         self.column_line = val & 0b0011_0000;
     }
 
+    // The select lines and the button lines are all active low.
+    // Bit 4 low selects the direction keys, bit 5 low selects the action buttons.
+    // If both are selected, a button reads as pressed if it is pressed in either column.
+    // If neither are selected, the lower nibble reads as 0x0F (nothing pressed).
+    // Bits 6 and 7 are unused and always read as 1.
     pub fn read_joyp(&self) -> u8 {
-        let joyp = match self.column_line {
-            // 4th bit 
-            0x10 => {
-                let mut result = 0 | self.a;
-                result = result | (self.b << 1);
-                result = result | (self.select << 2);
-                result = result | (self.start << 3);
-                result
-            }
+        let mut joyp = 0x0F;
+
+        // 4th bit
+        if self.column_line & 0b0001_0000 == 0 {
+            joyp &= self.right | (self.left << 1) | (self.up << 2) | (self.down << 3);
+        }
+
+        // 5th bit
+        if self.column_line & 0b0010_0000 == 0 {
+            joyp &= self.a | (self.b << 1) | (self.select << 2) | (self.start << 3);
+        }
 
-            // 5th bit
-            0x20 => {
-                let mut result = 0 | self.right;
-                result = result | (self.left << 1);
-                result = result | (self.up << 2);
-                result = result | (self.down << 3);
-                result
+        0b1100_0000 | self.column_line | joyp
+    }
+
+    pub fn set_mapping(&mut self, mapping: HashMap<HostKey, Button>) {
+        self.mapping = mapping;
+    }
+
+    pub fn get_mapping(&self) -> &HashMap<HostKey, Button> {
+        &self.mapping
+    }
+
+    fn button_line(&mut self, button: Button) -> &mut u8 {
+        match button {
+            Button::Up => &mut self.up,
+            Button::Down => &mut self.down,
+            Button::Left => &mut self.left,
+            Button::Right => &mut self.right,
+            Button::Start => &mut self.start,
+            Button::Select => &mut self.select,
+            Button::A => &mut self.a,
+            Button::B => &mut self.b
+        }
+    }
+
+    pub fn press_button(&mut self, button: Button) {
+        *self.button_line(button) = 0;
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        *self.button_line(button) = 1;
+    }
+
+    // A set bit means the button is pressed, see Button::mask for the layout
+    pub fn get_button_mask(&self) -> u8 {
+        let mut mask = 0;
+        for button in Button::ALL.iter() {
+            if self.is_pressed(*button) {
+                mask |= button.mask();
             }
+        }
 
-            // 4th & 5th
-            0x30 => {
-                self.a | (self.b << 1) | (self.select << 2) | (self.start << 3) |
-                (self.left << 1) | (self.up << 2) | (self.down << 3)
+        mask
+    }
+
+    pub fn set_button_mask(&mut self, mask: u8) {
+        for button in Button::ALL.iter() {
+            match mask & button.mask() != 0 {
+                true => self.press_button(*button),
+                false => self.release_button(*button)
             }
+        }
+    }
 
-            _ => 0
+    // The buttons themselves, whichever column JOYP has selected
+    pub fn button_state(&self) -> ButtonState {
+        ButtonState {
+            up: self.is_pressed(Button::Up),
+            down: self.is_pressed(Button::Down),
+            left: self.is_pressed(Button::Left),
+            right: self.is_pressed(Button::Right),
+            start: self.is_pressed(Button::Start),
+            select: self.is_pressed(Button::Select),
+            a: self.is_pressed(Button::A),
+            b: self.is_pressed(Button::B)
+        }
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        let line = match button {
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::Left => self.left,
+            Button::Right => self.right,
+            Button::Start => self.start,
+            Button::Select => self.select,
+            Button::A => self.a,
+            Button::B => self.b
         };
 
-        joyp | 0b1100_0000
+        line == 0
     }
 
-    pub fn key_down(&mut self, code: Keycode) -> bool {
-        match code {
-            Keycode::W => self.up = 0,
-            Keycode::A => self.left = 0,
-            Keycode::S => self.down = 0,
-            Keycode::D => self.right = 0,
-            Keycode::O => self.a = 0,
-            Keycode::K => self.b = 0,
-            Keycode::N => self.select = 0,
-            Keycode::M => self.start = 0,
-            _ => return false
-        };
+    // Returns the button the key is mapped to, if any
+    pub fn host_key_down(&mut self, key: HostKey) -> Option<Button> {
+        let button = *self.mapping.get(&key)?;
+        self.press_button(button);
+        Some(button)
+    }
 
-        true
+    pub fn host_key_up(&mut self, key: HostKey) -> Option<Button> {
+        let button = *self.mapping.get(&key)?;
+        self.release_button(button);
+        Some(button)
+    }
+
+    pub fn key_down(&mut self, code: Keycode) -> bool {
+        self.host_key_down(code.into()).is_some()
     }
 
     pub fn key_up(&mut self, code: Keycode) {
-        match code {
-            Keycode::W => self.up = 1,
-            Keycode::A => self.left = 1,
-            Keycode::S => self.down = 1,
-            Keycode::D => self.right = 1,
-            Keycode::O => self.a = 1,
-            Keycode::K => self.b = 1,
-            Keycode::N => self.select = 1,
-            Keycode::M => self.start = 1,
-            _ => {} // do nothing
-        }
+        self.host_key_up(code.into());
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, fmt};
 
-use super::{cpu::{Cpu, disassembler::{Instruction, InstructionStep}}};
+use super::{cpu::{Cpu, disassembler::{Instruction, InstructionStep}}, save_state::{StateReader, StateWriter}};
 
 // https://eldred.fr/gb-asm-tutorial/interrupts.html
 
@@ -13,7 +13,7 @@ pub struct Interupt {
     pub halt_interupt_pending: bool
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InterruptFlag {
     VBlank = 0b00000001,
     Stat   = 0b00000010,
@@ -22,6 +22,25 @@ pub enum InterruptFlag {
     Joypad = 0b00010000
 }
 
+// Where each interrupt jumps to, highest priority first
+pub const INTERRUPT_VECTORS: [(InterruptFlag, u16); 5] = [
+    (InterruptFlag::VBlank, 0x40),
+    (InterruptFlag::Stat, 0x48),
+    (InterruptFlag::Timer, 0x50),
+    (InterruptFlag::Serial, 0x58),
+    (InterruptFlag::Joypad, 0x60)
+];
+
+// A copy of the interrupt registers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InterruptState {
+    pub ime: bool,
+    // IE (0xFFFF)
+    pub enable: u8,
+    // IF (0xFF0F)
+    pub flags: u8
+}
+
 impl fmt::Debug for Interupt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("cpu")
@@ -45,6 +64,22 @@ impl Interupt {
         }
     }
 
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.master);
+        state.write_u8(self.enable);
+        state.write_u8(self.flags);
+        state.write_bool(self.waiting_for_halt_if);
+        state.write_bool(self.halt_interupt_pending);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.master = state.read_u8();
+        self.enable = state.read_u8();
+        self.flags = state.read_u8();
+        self.waiting_for_halt_if = state.read_bool();
+        self.halt_interupt_pending = state.read_bool();
+    }
+
     pub fn enable_master(&mut self) {
         self.master = 1;
     }
@@ -57,6 +92,14 @@ impl Interupt {
         self.master > 0
     }
 
+    pub fn state(&self) -> InterruptState {
+        InterruptState {
+            ime: self.is_master_enabled(),
+            enable: self.enable,
+            flags: self.flags
+        }
+    }
+
     pub fn get_interupt_state(&self) -> Option<InterruptFlag> {
         self.get_interupt_state_latched(self.enable, self.flags)
     }
@@ -110,18 +153,16 @@ impl Interupt {
             
             let interrupt_instr = Self::create_interupt_instruction();
             cpu.set_interrupt_instruction(interrupt_instr);
-            cpu.halted = false; // TODO un-halting here should take an extra 4t cycles (see TCAGBD 4.9)
+            cpu.wake_from_halt();
         }
     }
 
-    fn get_interupt_vector(flag: InterruptFlag) -> u16 {
-        match flag {
-            InterruptFlag::VBlank => 0x40,
-            InterruptFlag::Stat => 0x48,
-            InterruptFlag::Timer => 0x50,
-            InterruptFlag::Serial => 0x58,
-            InterruptFlag::Joypad => 0x60
-        }
+    pub(super) fn get_interupt_vector(flag: InterruptFlag) -> u16 {
+        let (_, vector) = INTERRUPT_VECTORS.iter()
+            .find(|(vector_flag, _)| *vector_flag == flag)
+            .unwrap();
+
+        *vector
     }
 
     // https://github.com/AntonioND/giibiiadvance/blob/master/docs/TCAGBD.pdf
@@ -132,8 +173,14 @@ impl Interupt {
     // 8t: 2 NOPS 
     // 8t: current PC pushed to stack
     // 4t: PC set to the interupt handler adress
-
-    fn create_interupt_instruction() -> Instruction {
+    //
+    // Which interrupt gets dispatched isn't decided until the upper byte of PC has been pushed.
+    // If that push lands on IE (SP = 0x0000) and clears the pending interrupt's enable bit, 
+    // the next highest priority interrupt is taken instead, or if there isn't one the dispatch
+    // is cancelled and PC is set to 0x0000. The lower byte push is too late to change anything.
+    // This is what mooneye's ie_push tests.
+
+    pub(super) fn create_interupt_instruction() -> Instruction {
         let mut steps: VecDeque<InstructionStep> = VecDeque::new();
 
         // NOP 1
@@ -144,45 +191,33 @@ impl Interupt {
         let step = Box::new(|_cpu: &mut Cpu| { });
         steps.push_back(InstructionStep::Standard(step));
 
-        // push pc higher byte
-        // latch interrupt enable flags?
+        // push pc higher byte, then latch the vector
         let step = Box::new(|cpu: &mut Cpu| {
             cpu.write_byte_to_stack((cpu.pc >> 8) as u8);
-            cpu.temp_val8 = cpu.mmu.borrow().interupts.enable;
+
+            let mut mmu = cpu.mmu.borrow_mut();
+            let itr_state = mmu.interupts.get_interupt_state();
+
+            cpu.temp_val_16 = match itr_state {
+                Some(flag) => {
+                    mmu.interupts.clear_interupt(flag);
+                    Self::get_interupt_vector(flag)
+                },
+                None => 0,
+            };
         });
         steps.push_back(InstructionStep::Standard(step));
 
         // push pc lower byte
-        // latch interrupt (request) flags?
         let step = Box::new(|cpu: &mut Cpu| {
-            let itr_if = cpu.mmu.borrow().interupts.flags;
             cpu.write_byte_to_stack((cpu.pc & 0x00FF) as u8);
-            cpu.temp_val_16 = itr_if as u16;
         });
         steps.push_back(InstructionStep::Standard(step));
 
         // Set new PC
         let step = Box::new(move |cpu: &mut Cpu| {
-            let address: u16;
-            {
-                let mut mmu = cpu.mmu.borrow_mut();
-                let itr_state = mmu.interupts.get_interupt_state_latched(
-                    cpu.temp_val8,
-                    cpu.temp_val_16 as u8
-                );
-
-                address = match itr_state {
-                    Some(flag) => {
-                        mmu.interupts.clear_interupt(flag);
-                        Self::get_interupt_vector(flag)
-                    },
-                    None => 0,
-                };
-
-                mmu.interupts.disable_master();
-            }
-
-            cpu.set_pc(address);
+            cpu.mmu.borrow_mut().interupts.disable_master();
+            cpu.set_pc(cpu.temp_val_16);
         });
         steps.push_back(InstructionStep::Standard(step));
 
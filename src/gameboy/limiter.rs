@@ -0,0 +1,147 @@
+use std::{collections::VecDeque, thread, time::{Duration, Instant}};
+
+// Keeps frames to a target rate using the wall clock, for frontends that don't have
+// the audio queue to hold them back.
+// Each frame has a deadline one frame time after the last one rather than after
+// whenever the last sleep happened to finish, so oversleeping on a thread with a
+// coarse sleep gets made up on the next frame.
+
+// If the emulator falls this far behind it starts again from now instead of
+// running frames back to back to catch up
+const MAX_FRAMES_BEHIND: u32 = 3;
+
+// current_fps is averaged over this many frames
+const FPS_SAMPLE_FRAMES: usize = 60;
+
+pub trait Clock {
+    // Time since some fixed point, only differences between calls matter
+    fn now(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+pub struct SystemClock {
+    start: Instant
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now()
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub struct FrameLimiter {
+    clock: Box<dyn Clock>,
+    frame_time: Option<Duration>,
+    next_deadline: Option<Duration>,
+
+    // oldest at the front
+    frame_times: VecDeque<Duration>
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameLimiter {
+    pub fn new() -> Self {
+        Self::with_clock(Box::new(SystemClock::new()))
+    }
+
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
+        Self {
+            clock,
+            frame_time: None,
+            next_deadline: None,
+
+            frame_times: VecDeque::with_capacity(FPS_SAMPLE_FRAMES)
+        }
+    }
+
+    // Anything measured with the old clock is thrown away
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+        self.next_deadline = None;
+        self.frame_times.clear();
+    }
+
+    // 0 or less turns the limiter off
+    pub fn set_target_fps(&mut self, fps: f64) {
+        self.frame_time = match fps > 0.0 {
+            true => Some(Duration::from_secs_f64(1.0 / fps)),
+            false => None
+        };
+
+        self.next_deadline = None;
+    }
+
+    pub fn target_fps(&self) -> f64 {
+        match self.frame_time {
+            Some(frame_time) => 1.0 / frame_time.as_secs_f64(),
+            None => 0.0
+        }
+    }
+
+    // Called once a frame has been run, sleeps until it's time for the next one.
+    // Returns how long it asked the clock to sleep for.
+    pub fn frame(&mut self) -> Duration {
+        let mut slept = Duration::ZERO;
+
+        if let Some(frame_time) = self.frame_time {
+            let now = self.clock.now();
+
+            let deadline = match self.next_deadline {
+                Some(deadline) if now <= deadline + frame_time * MAX_FRAMES_BEHIND => deadline,
+                _ => now
+            };
+
+            if deadline > now {
+                slept = deadline - now;
+                self.clock.sleep(slept);
+            }
+
+            self.next_deadline = Some(deadline + frame_time);
+        }
+
+        self.record_frame();
+        slept
+    }
+
+    fn record_frame(&mut self) {
+        if self.frame_times.len() == FPS_SAMPLE_FRAMES {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(self.clock.now());
+    }
+
+    // Measured over the last few frames, whether or not there's a target.
+    // 0 until at least 2 frames have been run.
+    pub fn current_fps(&self) -> f64 {
+        if self.frame_times.len() < 2 { return 0.0 }
+
+        let elapsed = *self.frame_times.back().unwrap() - *self.frame_times.front().unwrap();
+        if elapsed.is_zero() { return 0.0 }
+
+        (self.frame_times.len() - 1) as f64 / elapsed.as_secs_f64()
+    }
+}
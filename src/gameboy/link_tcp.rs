@@ -0,0 +1,68 @@
+use std::{io::{self, Read, Write}, net::{TcpListener, TcpStream, ToSocketAddrs}, sync::mpsc::{self, Receiver}, thread};
+
+use super::serial::{LinkCable, LinkMessage};
+
+// Each message is 3 bytes: the kind of message, the transfer's id, then the byte being shifted
+const TRANSFER: u8 = 0;
+const REPLY: u8 = 1;
+
+// A link cable to an emulator on another machine, or another process.
+// A thread reads incoming messages so checking for them never blocks the emulator.
+pub struct TcpCable {
+    stream: TcpStream,
+    incoming: Receiver<LinkMessage>
+}
+
+impl TcpCable {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Self::from_stream(TcpStream::connect(addr)?)
+    }
+
+    // Waits for the other side to connect
+    pub fn listen<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        Self::from_stream(stream)
+    }
+
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        // every transfer waits on a reply, don't let them get held back
+        stream.set_nodelay(true)?;
+
+        let mut reader = stream.try_clone()?;
+        let (sender, incoming) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut buf = [0; 3];
+            while reader.read_exact(&mut buf).is_ok() {
+                let message = match buf[0] {
+                    TRANSFER => LinkMessage::Transfer { id: buf[1], val: buf[2] },
+                    REPLY => LinkMessage::Reply { id: buf[1], val: buf[2] },
+                    _ => break
+                };
+
+                if sender.send(message).is_err() { break }
+            }
+        });
+
+        Ok(Self {
+            stream,
+            incoming
+        })
+    }
+}
+
+impl LinkCable for TcpCable {
+    fn send(&mut self, message: LinkMessage) -> bool {
+        let buf = match message {
+            LinkMessage::Transfer { id, val } => [TRANSFER, id, val],
+            LinkMessage::Reply { id, val } => [REPLY, id, val]
+        };
+
+        self.stream.write_all(&buf).is_ok()
+    }
+
+    fn receive(&mut self) -> Option<LinkMessage> {
+        self.incoming.try_recv().ok()
+    }
+}
@@ -0,0 +1,31 @@
+// Diagnostics go through the log crate when the "log" feature is enabled, so an embedder can route
+// and filter them with their own logger. The targets are gameboy_rs::memory, gameboy_rs::cpu and
+// gameboy_rs::cartridge. Without the feature they're printed to stdout.
+
+#[cfg(feature = "log")]
+macro_rules! emu_warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        log::warn!(target: $target, $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! emu_warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        println!("WARNING: {}", format_args!($($arg)+))
+    };
+}
+
+#[cfg(feature = "log")]
+macro_rules! emu_info {
+    (target: $target:expr, $($arg:tt)+) => {
+        log::info!(target: $target, $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! emu_info {
+    (target: $target:expr, $($arg:tt)+) => {
+        println!($($arg)+)
+    };
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+// What the last step_frame call did, for finding out where the time goes in a frontend.
+// Everything is counted over the whole call, so with a frame speed above 1 it covers
+// all of the frames that were run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameMetrics {
+    // Clock cycles, 70224 for a normal frame
+    pub cycles: u64,
+    // Interrupt dispatches aren't counted
+    pub instructions: u64,
+    // Only dots drawn with the lcd on
+    pub ppu_dots: u64,
+    // Wall clock time spent emulating, not counting any time the frame limiter slept for
+    pub emulation_time: Duration
+}
+
+impl FrameMetrics {
+    // The counts between two sets of running totals
+    pub(super) fn between(start: &FrameMetrics, end: &FrameMetrics, emulation_time: Duration) -> Self {
+        Self {
+            cycles: end.cycles - start.cycles,
+            instructions: end.instructions - start.instructions,
+            ppu_dots: end.ppu_dots - start.ppu_dots,
+            emulation_time
+        }
+    }
+}
@@ -1,17 +1,85 @@
-use rand::Rng;
+use std::cell::Cell;
 
-use super::{cartridge::Cartridge, input::Input, interupt::{InterruptFlag, Interupt}, ppu::PpuMode, spu::Spu, timer::Timer};
+
+use super::{cartridge::{Cartridge, MapperKind, MemoryLayout, info::CartridgeInfo}, cheats::Cheats, input::Input, interupt::{InterruptFlag, Interupt}, model::Model, ppu::PpuMode, save_state::{StateReader, StateWriter}, serial::Serial, spu::Spu, status::{DebugWarning, EmuError}, tile::decode_tile_row, timer::Timer};
 
 const PALETTE: [u8; 4] = [
     255, 192, 96, 0
 ];
 
+// Bits of the io registers that aren't connected to anything, they always read back as 1.
+// Only used for the registers that are read straight out of io, 0xFF is an address with no register.
+// https://gbdev.io/pandocs/Hardware_Reg_List.html
+const IO_READ_MASKS: [u8; 0x80] = [
+    // FF00 - FF0F: JOYP, SB, SC, -, DIV, TIMA, TMA, TAC, -, -, -, -, -, -, -, IF
+    0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00,
+    // FF10 - FF1F: sound, with gaps at FF15 and FF1F
+    0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF,
+    // FF20 - FF2F: sound, nothing past NR52
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // FF30 - FF3F: wave ram
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // FF40 - FF4F: LCDC, STAT, SCY, SCX, LY, LYC, DMA, BGP, OBP0, OBP1, WY, WX, -, KEY1, -, VBK
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+    // FF50 - FF5F: BOOT, HDMA1 - HDMA5, -
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // FF60 - FF6F: -, BCPS, BCPD, OCPS, OCPD, OPRI, -
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0xFF, 0xFF,
+    // FF70 - FF7F: SVBK, -
+    0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
+];
+
+// What reads from the unusable 0xFEA0 - 0xFEFF region return, this differs between models.
+// Writes to the region are ignored with every policy.
+// https://gbdev.io/pandocs/Memory_Map.html#fea0-feff-range
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnusableOamPolicy {
+    // DMG: 0x00, or 0xFF while the ppu has OAM locked
+    Zero,
+    // Always 0xFF
+    OpenBus,
+    // CGB rev E: the high nibble of the lower address byte, twice. 0xFEAx reads 0xAA
+    CgbQuirk
+}
+
+// What WRAM, VRAM and OAM hold at power on. Real hardware powers up with
+// semi-random junk, Zero is the default so every run starts the same.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RamInit {
+    #[default]
+    Zero,
+    Ones,
+    // Alternating runs of 8 0x00 and 8 0xFF bytes, close to what a lot of DMGs power up with
+    Pattern,
+    // The same seed always gives the same contents, on every platform, see SplitMix64
+    Random(u64)
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+// Written out here instead of using rand, whose generators aren't promised to give the
+// same numbers across versions or platforms, so a seed keeps giving the same RAM for replays.
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
 pub struct Mmu {
     pub spu: Spu,
     pub interupts: Interupt,
     pub input: Input,
     pub timer: Timer,
-    cartridge: Box<dyn Cartridge>,
+    pub serial: Serial,
+    cartridge: Cartridge,
+    pub cheats: Cheats,
 
     pub gpu_vram: [u8; 0x2000],
     working_ram: [u8; 0x2000],
@@ -34,20 +102,44 @@ pub struct Mmu {
 
     pub lock_vram: bool,
     pub lock_oam: bool,
+    // The row of OAM the ppu is reading while it scans OAM in mode 2, for the OAM bug
+    pub oam_scan_row: Option<u8>,
+    // Whether the OAM bug is emulated, only the models with a DMG cpu have it anyway. Not saved.
+    pub oam_bug: bool,
 
     stat_irq_state: bool,
 
+    pub unusable_oam_policy: UnusableOamPolicy,
+    // Only the quirks that differ between models look at this
+    pub model: Model,
+
+    // Debug builds only: writes to read only registers get reported as warnings
+    pub trap_read_only_writes: bool,
+    pub debug_warnings: Vec<DebugWarning>,
+
+    // Reads can't take &mut self, so errors are kept in a cell
+    pub error: Cell<Option<EmuError>>,
+    // Set while a debugger is looking around, so its accesses don't count as errors
+    peeking: Cell<bool>,
+
+    // Mapped over 0x0000 - 0x00FF until FF50 is written to
     pub bios_enabled: bool,
     bios: [u8; 0x100]
 }
 
 impl Mmu {
-    pub fn new(cartridge: Box<dyn Cartridge>, spu: Spu) -> Self {
+    pub fn new(cartridge: Cartridge, spu: Spu) -> Self {
+        Self::new_with_model(cartridge, spu, Model::default())
+    }
+
+    pub fn new_with_model(cartridge: Cartridge, spu: Spu, model: Model) -> Self {
         let mut mmu = Self {
             spu,
             interupts: Interupt::new(),
             input: Input::new(),
             timer: Timer::new(),
+            serial: Serial::new(),
+            cheats: Cheats::new(),
             cartridge,
 
             gpu_vram: [0; 0x2000],
@@ -76,9 +168,20 @@ impl Mmu {
 
             lock_oam: false,
             lock_vram: false,
+            oam_scan_row: None,
+            oam_bug: true,
 
             stat_irq_state: false,
 
+            unusable_oam_policy: UnusableOamPolicy::Zero,
+            model,
+
+            trap_read_only_writes: false,
+            debug_warnings: Vec::new(),
+
+            error: Cell::new(None),
+            peeking: Cell::new(false),
+
             bios_enabled: true,
             bios: [
                 0x31,0xFE,0xFF,0x21,0xFF,0x9F,0xAF,0x32,0xCB,0x7C,0x20,0xFA,0x0E,0x11,
@@ -103,8 +206,10 @@ impl Mmu {
             ]
         };
 
-        mmu.randomize_ram_values();
         mmu.setup_uninit_ram();
+        if model == Model::Cgb {
+            mmu.setup_cgb_registers();
+        }
 
         // set up zero page mem
         mmu.write_byte(0xFF02, 0x7E);
@@ -112,12 +217,64 @@ impl Mmu {
         mmu
     }
 
-    fn randomize_ram_values(&mut self) {
-        let mut rng = rand::thread_rng();
-        
-        for val in &mut self.working_ram {
-            *val = rng.gen_range(0..=u8::MAX);
+    // Hands back the cartridge that was in the slot
+    pub fn replace_cartridge(&mut self, cartridge: Cartridge) -> Cartridge {
+        std::mem::replace(&mut self.cartridge, cartridge)
+    }
+
+    pub fn force_mapper(&mut self, kind: MapperKind) {
+        self.cartridge.force_mapper(kind);
+    }
+
+    pub fn memory_layout(&self) -> MemoryLayout {
+        self.cartridge.memory_layout()
+    }
+
+    pub fn set_rom_bank(&mut self, bank: u16) {
+        self.cartridge.set_rom_bank(bank);
+    }
+
+    pub fn set_ram_bank(&mut self, bank: u8) {
+        self.cartridge.set_ram_bank(bank);
+    }
+
+    pub fn has_nintendo_logo(&self) -> bool {
+        self.cartridge.has_nintendo_logo()
+    }
+
+    pub fn cartridge_info(&self) -> CartridgeInfo {
+        self.cartridge.info()
+    }
+
+    // Sets the io registers the boot rom would have, for starting without it
+    pub fn skip_boot_rom(&mut self) {
+        self.bios_enabled = false;
+        self.write_byte(0xFF40, 0x91);
+        self.write_byte(0xFF47, 0xFC);
+        self.spu.skip_boot_rom();
+    }
+
+    pub fn init_ram(&mut self, ram_init: RamInit) {
+        let mut rng = match ram_init {
+            RamInit::Random(seed) => Some(SplitMix64 { state: seed }),
+            _ => None
+        };
+
+        let ram = self.working_ram.iter_mut()
+            .chain(self.gpu_vram.iter_mut())
+            .chain(self.sprite_table.iter_mut());
+
+        for (i, val) in ram.enumerate() {
+            *val = match ram_init {
+                RamInit::Zero => 0x00,
+                RamInit::Ones => 0xFF,
+                RamInit::Pattern => if i & 8 == 0 { 0x00 } else { 0xFF },
+                // the top byte, the low bits are the weakest
+                RamInit::Random(_) => (rng.as_mut().unwrap().next() >> 56) as u8
+            };
         }
+
+        self.rebuild_tileset();
     }
 
     fn setup_uninit_ram(&mut self) {
@@ -137,7 +294,192 @@ impl Mmu {
         }
     }
 
+    // VRAM and WRAM bank 0 are picked at power on
+    fn setup_cgb_registers(&mut self) {
+        self.io[0x4F] = 0;
+        self.io[0x70] = 0;
+
+        // the CGB boot rom leaves DMG games on X coordinate priority
+        self.io[0x6C] = 1;
+    }
+
+    // https://gbdev.io/pandocs/CGB_Registers.html#ff6c--opri-cgb-mode-only-object-priority-mode
+    // With OPRI bit 0 clear, overlapping sprites are drawn in OAM order instead of by X coordinate
+    pub fn oam_index_priority(&self) -> bool {
+        self.model == Model::Cgb && self.io[0x6C] & 1 == 0
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        self.spu.save_state(state);
+        self.interupts.save_state(state);
+        self.input.save_state(state);
+        self.timer.save_state(state);
+        self.serial.save_state(state);
+        self.cartridge.save_state(state);
+
+        state.write_bytes(&self.gpu_vram);
+        state.write_bytes(&self.working_ram);
+        state.write_bytes(&self.io);
+        state.write_bytes(&self.zero_page);
+        state.write_bytes(&self.sprite_table);
+        state.write_bytes(&self.sprite_palette[0]);
+        state.write_bytes(&self.sprite_palette[1]);
+        state.write_bytes(&self.bg_palette);
+
+        state.write_u16(self.dma_transfer_index);
+        state.write_u16(self.dma_transfer_base_addr);
+        state.write_u8(self.dma_queue_counter);
+        state.write_u16(self.dma_queue_val);
+        state.write_bool(self.dma_active);
+        state.write_u8(self.dma_active_clock);
+
+        state.write_bool(self.lock_vram);
+        state.write_bool(self.lock_oam);
+        state.write_bool(self.oam_scan_row.is_some());
+        state.write_u8(self.oam_scan_row.unwrap_or(0));
+        state.write_bool(self.stat_irq_state);
+        state.write_bool(self.bios_enabled);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.spu.load_state(state);
+        self.interupts.load_state(state);
+        self.input.load_state(state);
+        self.timer.load_state(state);
+        self.serial.load_state(state);
+        self.cartridge.load_state(state);
+
+        state.read_bytes(&mut self.gpu_vram);
+        self.rebuild_tileset();
+        state.read_bytes(&mut self.working_ram);
+        state.read_bytes(&mut self.io);
+        state.read_bytes(&mut self.zero_page);
+        state.read_bytes(&mut self.sprite_table);
+        state.read_bytes(&mut self.sprite_palette[0]);
+        state.read_bytes(&mut self.sprite_palette[1]);
+        state.read_bytes(&mut self.bg_palette);
+
+        self.dma_transfer_index = state.read_u16();
+        self.dma_transfer_base_addr = state.read_u16();
+        self.dma_queue_counter = state.read_u8();
+        self.dma_queue_val = state.read_u16();
+        self.dma_active = state.read_bool();
+        self.dma_active_clock = state.read_u8();
+
+        self.lock_vram = state.read_bool();
+        self.lock_oam = state.read_bool();
+        let oam_scanning = state.read_bool();
+        let oam_scan_row = state.read_u8();
+        self.oam_scan_row = if oam_scanning { Some(oam_scan_row) } else { None };
+        self.stat_irq_state = state.read_bool();
+        self.bios_enabled = state.read_bool();
+    }
+
+    // The ppu only blocks vram and oam while it's running, with the lcd
+    // off they can always be accessed whatever mode it was last in
+    fn is_vram_locked(&self) -> bool {
+        self.lock_vram && self.io[0x40] >> 7 != 0
+    }
+
+    fn is_oam_locked(&self) -> bool {
+        self.lock_oam && self.io[0x40] >> 7 != 0
+    }
+
+    #[cfg(debug_assertions)]
+    fn read_only_write(&mut self, register: &'static str, addr: u16, val: u8) {
+        if !self.trap_read_only_writes { return }
+
+        let warning = DebugWarning::ReadOnlyWrite { register, addr, val };
+        emu_warn!(target: "gameboy_rs::memory", "{}", warning);
+        self.debug_warnings.push(warning);
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn read_only_write(&mut self, _register: &'static str, _addr: u16, _val: u8) { }
+
+    // Reads and writes still go ahead (as 0xFF / ignored), the error is picked up by the next step
+    fn unhandled_access(&self, error: EmuError) {
+        if self.peeking.get() { return }
+
+        emu_warn!(target: "gameboy_rs::memory", "{}", error);
+        self.error.set(Some(error));
+    }
+
+    // https://gbdev.io/pandocs/OAM_Corruption_Bug.html
+    // On the models with a DMG cpu, a 16 bit INC or DEC of a register pointing at
+    // 0xFE00 - 0xFEFF while the ppu is scanning OAM corrupts the row of OAM it's reading.
+    // Only the INC/DEC rr corruption is emulated, not the ones from reads, writes or the stack.
+    pub fn oam_bug_write(&mut self, addr: u16) {
+        if !self.oam_bug || self.model == Model::Cgb { return }
+        if !(0xFE00..=0xFEFF).contains(&addr) { return }
+
+        // the first row is never corrupted
+        let row = match self.oam_scan_row {
+            Some(row) if row > 0 => row as usize * 8,
+            _ => return
+        };
+        let previous = row - 8;
+
+        let word = |table: &[u8; 0xA0], i: usize| u16::from_le_bytes([table[i], table[i + 1]]);
+        let a = word(&self.sprite_table, row);
+        let b = word(&self.sprite_table, previous);
+        let c = word(&self.sprite_table, previous + 4);
+
+        let corrupted = ((a ^ c) & (b ^ c)) ^ c;
+        self.sprite_table[row..row + 2].copy_from_slice(&corrupted.to_le_bytes());
+
+        // the other 3 words get copied from the row before
+        for i in 2..8 {
+            self.sprite_table[row + i] = self.sprite_table[previous + i];
+        }
+    }
+
+    fn read_unusable_oam(&self, addr: u16) -> u8 {
+        match self.unusable_oam_policy {
+            UnusableOamPolicy::Zero => if self.is_oam_locked() { 0xFF } else { 0 },
+            UnusableOamPolicy::OpenBus => 0xFF,
+            UnusableOamPolicy::CgbQuirk => {
+                let nibble = (addr as u8) >> 4;
+                (nibble << 4) | nibble
+            }
+        }
+    }
+
+    // For debuggers, the same as read_byte except unhandled addresses aren't errors
+    pub fn peek_byte(&self, addr: u16) -> u8 {
+        self.peeking.set(true);
+        let val = self.read_byte(addr);
+        self.peeking.set(false);
+        val
+    }
+
+    pub fn poke_byte(&mut self, addr: u16, val: u8) {
+        self.peeking.set(true);
+        self.write_byte(addr, val);
+        self.peeking.set(false);
+    }
+
+    // While an OAM DMA is running the cpu can't use the bus the DMA is reading from,
+    // reads from it return 0xFF. VRAM has a bus of its own, the cartridge and WRAM share
+    // the other one. The io registers and HRAM can always be reached, which is why games
+    // wait for the transfer in a routine copied to HRAM.
     pub fn read_byte(&self, addr: u16) -> u8 {
+        let dma_bus_conflict = self.dma_active && addr < 0xFE00 &&
+            Self::is_vram_bus(addr) == Self::is_vram_bus(self.dma_transfer_base_addr);
+
+        if dma_bus_conflict {
+            return 0xFF;
+        }
+
+        self.read_bus(addr)
+    }
+
+    fn is_vram_bus(addr: u16) -> bool {
+        (0x8000..0xA000).contains(&addr)
+    }
+
+    // What the DMA sees, without the DMA's own restrictions
+    fn read_bus(&self, addr: u16) -> u8 {
         match addr & 0xF000 {
             // rom_bank_0
             0x0000 | 0x1000 | 0x2000 | 0x3000 |
@@ -146,12 +488,13 @@ impl Mmu {
                     return self.bios[addr as usize];
                 }
 
-                self.cartridge.read_rom(addr)
+                let val = self.cartridge.read_rom(addr);
+                self.cheats.patch_rom_read(addr, val)
             }
 
             // vram
             0x8000 | 0x9000 => {
-                if self.lock_vram {
+                if self.is_vram_locked() {
                     return 0xFF;
                 }
 
@@ -168,7 +511,7 @@ impl Mmu {
                 self.working_ram[(addr - 0xC000) as usize]
             }
 
-            // 0xE000 to 0xFFxx is a mirror of the internal ram
+            // 0xE000 to 0xFDFF is a mirror of the internal ram, 0xFE00 up is OAM and IO
 
             0xE000 => {
                 self.working_ram[(addr - 0xE000) as usize]
@@ -184,7 +527,7 @@ impl Mmu {
 
                     0x0E00 => {
                         if addr < 0xFEA0 {
-                            if self.lock_oam || self.lock_vram {
+                            if self.is_oam_locked() || self.is_vram_locked() {
                                 return 0xFF;
                             }
 
@@ -194,9 +537,7 @@ impl Mmu {
                         }
 
                         // FEAO -> FEFF
-                        // "Empty but usable for io"?
-                        // Some just return here
-                        return 0;
+                        self.read_unusable_oam(addr)
                     },
 
                     0x0F00 => {
@@ -225,6 +566,10 @@ impl Mmu {
                             return self.zero_page[(addr - 0xFF80) as usize]
                         }
 
+                        else if addr == 0xFF01 || addr == 0xFF02 {
+                            self.serial.read(addr)
+                        }
+
                         else if addr == 0xFF03 {
                             return 0xFF;
                         }
@@ -291,7 +636,7 @@ impl Mmu {
                         }
 
                         else if addr >= 0xFF30 && addr <= 0xFF3F {
-                            return self.spu.get_sample((addr - 0xFF30) as u8);
+                            self.spu.get_sample((addr - 0xFF30) as u8, self.model == Model::Cgb)
                         }
 
                         else if addr == 0xFF20 {
@@ -323,24 +668,43 @@ impl Mmu {
                             return self.spu.get_nr52();
                         }
 
+                        // the gaps between the sound registers aren't connected to anything,
+                        // and neither are the CGB registers on the other models
+                        else if Self::is_unused_sound_register(addr) || (Self::is_cgb_register(addr) && self.model != Model::Cgb) {
+                            0xFF
+                        }
+
+                        // VRAM bank
+                        else if addr == 0xFF4F {
+                            0b1111_1110 | self.io[0x4F]
+                        }
+
+                        // WRAM bank
+                        else if addr == 0xFF70 {
+                            0b1111_1000 | self.io[0x70]
+                        }
+
                         else if addr >= 0xFF00 && addr <= 0xFF7F {
-                            return self.io[(addr - 0xFF00) as usize]
+                            let index = (addr - 0xFF00) as usize;
+                            self.io[index] | IO_READ_MASKS[index]
                         } 
                         
                         else {
-                            panic!("unhandled byte read from memory! Addr: {:#X}", addr);
+                            self.unhandled_access(EmuError::UnhandledRead { addr });
+                            0xFF
                         }
                     },
 
                     _ => {
-                        println!("Unhandled branch in read request for mem (0xFxxx): {:#X}", addr);
-                        std::process::exit(0);
+                        self.unhandled_access(EmuError::UnhandledRead { addr });
+                        0xFF
                     }
                 }
             }
 
             _ => {
-                panic!("Unhandled read at addr {:#06X}", addr);
+                self.unhandled_access(EmuError::UnhandledRead { addr });
+                0xFF
             }
         }
     }
@@ -354,9 +718,10 @@ impl Mmu {
 
             // vram
             0x8000 | 0x9000 => {
-                if self.lock_vram { return }
+                if self.is_vram_locked() { return }
 
                 self.gpu_vram[(addr - 0x8000) as usize] = val;
+                self.update_tileset(addr);
             }
 
             0xA000 | 0xB000 => {
@@ -380,16 +745,16 @@ impl Mmu {
                     },
 
                     0x0E00 => {
+                        // the DMA has OAM to itself, so the transfer's data always wins
                         if self.dma_active { return; }
 
-                        if self.lock_vram || self.lock_oam { return; }
+                        if self.is_vram_locked() || self.is_oam_locked() { return; }
 
                         if addr < 0xFEA0 {
                             self.sprite_table[(addr - 0xFE00) as usize] = val;
                         }
 
-                        // "Empty but usable for io"?
-                        // Some just return here
+                        // FEAO -> FEFF is unusable, writes do nothing
                         return;
                     },
 
@@ -402,12 +767,17 @@ impl Mmu {
                             self.zero_page[(addr - 0xFF80) as usize] = val;
                         }
 
+                        else if addr == 0xFF01 || addr == 0xFF02 {
+                            self.serial.write(addr, val);
+                        }
+
                         else if addr >= 0xFF03 && addr <= 0xFF07 {
                             self.timer.write(addr, val);
                         }
 
+                        // only the 5 interrupts exist, the upper 3 bits always read 1
                         else if addr == 0xFF0F {
-                            self.interupts.flags = val;
+                            self.interupts.flags = val & 0b0001_1111;
                         }
 
                         // LCD CONTROL
@@ -427,13 +797,23 @@ impl Mmu {
 
                         else if addr == 0xFF41 {
                             let stat = self.io[0x41];
+
+                            // Pre-CGB models briefly act as if every source was enabled while the
+                            // value is written, so a write in HBlank, VBlank or with LY = LYC can
+                            // fire a STAT interrupt. The OAM source doesn't take part.
+                            // https://gbdev.io/pandocs/STAT.html#spurious-stat-interrupts
+                            if self.model != Model::Cgb && self.io[0x40] >> 7 != 0 {
+                                self.io[0x41] = stat | 0b0101_1000;
+                                self.update_stat_irq_conditions(String::from("STAT WRITE BUG"));
+                            }
+
                             self.io[0x41] = (stat & 0b1000_0111) | (val & 0b0111_1000);
 
                             self.update_stat_irq_conditions(String::from("STAT WRITE"));
                         }
 
                         else if addr == 0xFF44 {
-                            // Do nothing, this is read only ?
+                            self.read_only_write("LY", addr, val);
                         }
 
                         else if addr == 0xFF45 {
@@ -536,7 +916,7 @@ impl Mmu {
                         }
 
                         else if addr >= 0xFF30 && addr <= 0xFF3F {
-                            self.spu.set_sample((addr - 0xFF30) as u8, val);
+                            self.spu.set_sample((addr - 0xFF30) as u8, val, self.model == Model::Cgb);
                         }
 
                         else if addr == 0xFF20 {
@@ -566,6 +946,30 @@ impl Mmu {
                             self.spu.set_nr52(val);
                         }
 
+                        else if Self::is_unused_sound_register(addr) || (Self::is_cgb_register(addr) && self.model != Model::Cgb) {
+                            // NOP
+                        }
+
+                        else if addr == 0xFF4F {
+                            self.io[0x4F] = val & 0b0000_0001;
+                        }
+
+                        else if addr == 0xFF70 {
+                            self.io[0x70] = val & 0b0000_0111;
+                        }
+
+                        else if addr == 0xFF6C {
+                            self.io[0x6C] = val & 0b0000_0001;
+                        }
+
+                        // The boot rom's last instruction writes 1 here. Once it's unmapped
+                        // there's no mapping it back in, whatever gets written after.
+                        else if addr == 0xFF50 {
+                            if val != 0 {
+                                self.bios_enabled = false;
+                            }
+                        }
+
                         else if addr >= 0xFF00 && addr <= 0xFF7F {
                             self.io[(addr - 0xFF00) as usize] = val;
                         }
@@ -575,26 +979,109 @@ impl Mmu {
                         } 
                         
                         else {
-                            panic!("unhandled byte write to memory! Addr: {:#X} Val: {:#X}", addr, val);
+                            self.unhandled_access(EmuError::UnhandledWrite { addr, val });
                         }
                     },
 
                     _ => {
-                        println!("Unhandled branch in write request for mem (0xFxxx): {:#X}, Val: {:#X}", addr, val);
-                        std::process::exit(0);
+                        self.unhandled_access(EmuError::UnhandledWrite { addr, val });
                     }
                 }
             },
 
             _ => {
-                println!("Unhandled write request for mem address: {:#X}, Val: {:#X}", addr, val);
-                std::process::exit(0);
+                self.unhandled_access(EmuError::UnhandledWrite { addr, val });
+            }
+        }
+    }
+
+    fn is_unused_sound_register(addr: u16) -> bool {
+        addr == 0xFF15 || addr == 0xFF1F || (0xFF27..=0xFF2F).contains(&addr)
+    }
+
+    // Only there in CGB mode, the other models have nothing connected to them.
+    // KEY1, VBK, the HDMA registers, the CGB palettes, OPRI and SVBK.
+    // The registers are kept but VRAM and WRAM aren't banked yet, see Notes.md.
+    fn is_cgb_register(addr: u16) -> bool {
+        addr == 0xFF4D || addr == 0xFF4F || (0xFF51..=0xFF55).contains(&addr) ||
+            (0xFF68..=0xFF6C).contains(&addr) || addr == 0xFF70
+    }
+
+    // Decodes the tile row that the vram address is in, the two bytes of a row hold
+    // the low and high bits of each of the 8 pixels. Writes to the tile maps are ignored.
+    fn update_tileset(&mut self, addr: u16) {
+        let addr = (addr & 0x1FFE) as usize;
+        if addr >= 0x1800 { return }
+
+        let tile = addr >> 4;
+        let y = (addr >> 1) & 7;
+        self.tileset[tile][y] = decode_tile_row(self.gpu_vram[addr], self.gpu_vram[addr + 1]);
+    }
+
+    fn rebuild_tileset(&mut self) {
+        for addr in (0x8000..0x9800).step_by(2) {
+            self.update_tileset(addr);
+        }
+    }
+
+    // A decoded tile, each pixel is a colour number 0-3.
+    // Tiles 0-383 cover 0x8000 - 0x97FF.
+    pub fn get_tile(&self, index: usize) -> [[u8; 8]; 8] {
+        assert!(index < 384, "Tile index out of range: {}", index);
+
+        self.tileset[index]
+    }
+
+    // Writes the tile back to vram in its 2bpp form as well, so the change persists
+    // and ends up in save states. Only the low 2 bits of each pixel are used.
+    pub fn set_tile(&mut self, index: usize, data: [[u8; 8]; 8]) {
+        assert!(index < 384, "Tile index out of range: {}", index);
+
+        for (y, row) in data.iter().enumerate() {
+            let mut low = 0;
+            let mut high = 0;
+            for (x, pixel) in row.iter().enumerate() {
+                let bit = 1 << (7 - x);
+                if pixel & 1 != 0 { low |= bit; }
+                if pixel & 2 != 0 { high |= bit; }
             }
+
+            let addr = index * 16 + y * 2;
+            self.gpu_vram[addr] = low;
+            self.gpu_vram[addr + 1] = high;
+            self.update_tileset(addr as u16);
         }
     }
 
+    // 0x8000 - 0x97FF, the 384 tiles in their 2bpp form
+    pub fn tile_data(&self) -> &[u8] {
+        &self.gpu_vram[..0x1800]
+    }
+
+    // The 32x32 tile map at 0x9800 (map 0) or 0x9C00 (map 1), a row of 32 tile numbers after another
+    pub fn tile_map(&self, map: u8) -> &[u8] {
+        assert!(map < 2, "There's no tile map {}", map);
+
+        let start = 0x1800 + map as usize * 0x400;
+        &self.gpu_vram[start..start + 0x400]
+    }
+
+    // The whole of vram, tile data and tile maps.
+    // Only the DMG's single bank exists so far.
+    pub fn dump_vram(&self) -> Vec<u8> {
+        self.gpu_vram.to_vec()
+    }
+
+    // Ignores the vram lock, anything past the end of vram is ignored
+    pub fn load_vram(&mut self, data: &[u8]) {
+        let len = data.len().min(self.gpu_vram.len());
+        self.gpu_vram[..len].copy_from_slice(&data[..len]);
+
+        self.rebuild_tileset();
+    }
+
     pub fn read_word(&self, addr: u16) -> u16 {
-        self.read_byte(addr) as u16 + ((self.read_byte(addr + 1) as u16) << 8)
+        self.read_byte(addr) as u16 + ((self.read_byte(addr.wrapping_add(1)) as u16) << 8)
     }
 
     pub fn write_word(&mut self, addr: u16, val: u16) {
@@ -602,21 +1089,35 @@ impl Mmu {
         let higher_val: u8 = ((val & 0xFF00) >> 8) as u8;
 
         self.write_byte(addr, lower_val);
-        self.write_byte(addr + 1, higher_val);
+        self.write_byte(addr.wrapping_add(1), higher_val);
     }
 
+    // Writing FF46 while a transfer is running restarts it from the new source. The old
+    // transfer carries on until the new one starts, so OAM ends up all from the new source.
     pub fn dma_queue(&mut self, val: u8) {
         self.dma_queue_val = val as u16;
         self.dma_queue_counter = 5;
     }
     
+    // A transfer running, or about to start
+    pub fn is_dma_running(&self) -> bool {
+        self.dma_active || self.dma_queue_counter > 0
+    }
+
+    // The DMA sees echo ram all the way up from 0xE000, so a source of 0xFE or 0xFF
+    // copies from 0xDE00 or 0xDF00 rather than from OAM or the io registers
+    fn dma_source_addr(addr: u16) -> u16 {
+        if addr >= 0xE000 { addr - 0x2000 } else { addr }
+    }
+
     pub fn dma_tick(&mut self) {
         // tick transfer, if active
         if self.dma_active {
             self.dma_active_clock += 1;
 
             if self.dma_active_clock == 4 { 
-                let src_val = self.read_byte(self.dma_transfer_base_addr + self.dma_transfer_index);
+                let src_addr = self.dma_transfer_base_addr.wrapping_add(self.dma_transfer_index);
+                let src_val = self.read_bus(Self::dma_source_addr(src_addr));
                 self.sprite_table[self.dma_transfer_index as usize] = src_val;
                 self.dma_transfer_index += 1;
     
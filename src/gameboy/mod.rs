@@ -1,17 +1,45 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::{Ref, RefCell}, collections::HashMap, fs, path::PathBuf, rc::Rc, time::{Instant, SystemTime, UNIX_EPOCH}};
+#[cfg(any(feature = "zip", feature = "hound"))]
+use std::path::Path;
 
 use sdl2::{audio::AudioQueue, keyboard::Keycode};
 
-use self::{cpu::Cpu, interupt::{InterruptFlag, Interupt}, mmu::Mmu, ppu::Ppu, spu::{Spu}};
+#[cfg(feature = "zip")]
+use self::rom_archive::ZipRomError;
+#[cfg(feature = "hound")]
+use self::spu::wav_recorder::WavRecorder;
+use self::{cpu::{CYCLES_PER_MACHINE_CYCLE, Cpu, disassembler::disassembly_iterator::is_illegal_opcode}, input::{Button, ButtonState, HostKey}, interupt::Interupt, mmu::Mmu, movie::MovieState, ppu::Ppu, rewind::Rewind, save_state::{StateError, StateMeta, StateReader, StateWriter}, spu::{Spu}};
 
+pub use self::{cartridge::{Cartridge, Mapper, MapperKind, info::CartridgeInfo, MemoryLayout, mbc1::Mbc1, mbc2::Mbc2, mbc3::Mbc3, mbc5::Mbc5, no_mbc::NoMbc}, cheats::CheatError, error::Error, cpu::{Flags, Registers, disassembler::{Instruction, disassembly_iterator::DisassemblyIterator}}, emulator::Emulator, interupt::{INTERRUPT_VECTORS, InterruptFlag, InterruptState}, limiter::{Clock, FrameLimiter, SystemClock}, metrics::FrameMetrics, mmu::{RamInit, UnusableOamPolicy}, model::Model, movie::InputMovie, serial::{ChannelCable, LinkCable, LinkMessage}, status::{DebugWarning, EmuError, FrameStatus, RunCodeError}, scaler::scale_nearest, ascii::ascii_art, tile::{decode_tile, decode_tile_row}};
+
+#[macro_use]
+mod logging;
 mod cpu;
 mod mmu;
 mod interupt;
 mod ppu;
 pub mod spu;
 mod timer;
-mod input;
+mod serial;
+pub mod input;
 mod cartridge;
+pub mod save_state;
+mod rewind;
+mod movie;
+mod cheats;
+mod status;
+mod error;
+mod limiter;
+mod metrics;
+mod model;
+mod emulator;
+mod tile;
+mod scaler;
+mod ascii;
+#[cfg(feature = "zip")]
+pub mod rom_archive;
+#[cfg(feature = "link-tcp")]
+pub mod link_tcp;
 
 /*
     System Clocks
@@ -23,17 +51,117 @@ mod cartridge;
     VRAM: 2,097,152 Hz
 */
 
+// What step_frame_av returns, the frame buffer and the left/right samples for it
+pub type AvFrame = (Vec<u8>, Vec<(i16, i16)>);
+
 pub struct GameBoy {
     cpu: Cpu,
     mmu: Rc<RefCell<Mmu>>,
-    ppu: Ppu
+    ppu: Ppu,
+
+    paused: bool,
+    rewind: Rewind,
+    movie: MovieState,
+    limiter: FrameLimiter,
+    serial_console: Option<String>,
+    // How many frames the next step_frame runs
+    frame_speed: u32,
+    // Clock cycles run since power on. Not saved.
+    cycle_count: u64,
+    frame_metrics: FrameMetrics,
+    // Where the save state slots are kept
+    state_dir: Option<PathBuf>,
+    // Set when the ppu finishes a frame, until took_vblank is called. Not saved.
+    vblank_taken: bool,
+
+    error: Option<EmuError>
 }
 
 impl GameBoy {
+    // Panics if the ROM can't be loaded, see try_new
     pub fn new(rom_path: &str, device: Option<Rc<RefCell<AudioQueue<f32>>>>) -> Self {
-        let cartridge = cartridge::create(rom_path);
+        Self::new_with_ram_init(rom_path, device, RamInit::default())
+    }
+
+    // Like new, but hands back what went wrong loading the ROM instead of panicking
+    pub fn try_new(rom_path: &str, device: Option<Rc<RefCell<AudioQueue<f32>>>>) -> Result<Self, Error> {
+        let cartridge = cartridge::create(rom_path)?;
+        Ok(Self::with_cartridge(cartridge, device, RamInit::default()))
+    }
+
+    // Powers up with WRAM, VRAM and OAM filled in as ram_init says
+    pub fn new_with_ram_init(
+        rom_path: &str, 
+        device: Option<Rc<RefCell<AudioQueue<f32>>>>, 
+        ram_init: RamInit
+    ) -> Self {
+        let cartridge = Self::create_cartridge(rom_path);
+        Self::with_cartridge(cartridge, device, ram_init)
+    }
+
+    // Powers up as the given model, for the hardware quirks and registers that differ between them
+    pub fn new_with_model(
+        rom_path: &str, 
+        device: Option<Rc<RefCell<AudioQueue<f32>>>>, 
+        model: Model
+    ) -> Self {
+        let cartridge = Self::create_cartridge(rom_path);
+        Self::with_cartridge_and_model(cartridge, device, RamInit::default(), model)
+    }
+
+    // Loads the named ROM from the zip, or the first .gb/.gbc file in it.
+    // The save file is kept next to the zip.
+    #[cfg(feature = "zip")]
+    pub fn from_zip(
+        zip_path: &str, 
+        entry: Option<&str>, 
+        device: Option<Rc<RefCell<AudioQueue<f32>>>>
+    ) -> Result<Self, ZipRomError> {
+        let path = Path::new(zip_path);
+        let rom = rom_archive::read_rom(path, entry)?;
+        let cartridge = cartridge::create_from_reader(&mut rom.as_slice(), path).map_err(ZipRomError::Rom)?;
+
+        Ok(Self::with_cartridge(cartridge, device, RamInit::default()))
+    }
+
+    // For a ROM that's already in memory. There's no save file, the cartridge ram
+    // starts out blank and is gone once the GameBoy is dropped.
+    pub fn from_rom(rom: Vec<u8>, device: Option<Rc<RefCell<AudioQueue<f32>>>>) -> Result<Self, Error> {
+        let cartridge = cartridge::create_from_rom(rom, None)?;
+        Ok(Self::with_cartridge(cartridge, device, RamInit::default()))
+    }
+
+    // For a cartridge put together by hand, like one with a custom mapper
+    pub fn from_cartridge(cartridge: Cartridge, device: Option<Rc<RefCell<AudioQueue<f32>>>>) -> Self {
+        Self::with_cartridge(cartridge, device, RamInit::default())
+    }
+
+    // For the constructors that can't return the error
+    fn create_cartridge(rom_path: &str) -> Cartridge {
+        match cartridge::create(rom_path) {
+            Ok(cartridge) => cartridge,
+            Err(err) => panic!("{}", err)
+        }
+    }
+
+    fn with_cartridge(
+        cartridge: Cartridge, 
+        device: Option<Rc<RefCell<AudioQueue<f32>>>>, 
+        ram_init: RamInit
+    ) -> Self {
+        Self::with_cartridge_and_model(cartridge, device, ram_init, Model::default())
+    }
+
+    fn with_cartridge_and_model(
+        cartridge: Cartridge, 
+        device: Option<Rc<RefCell<AudioQueue<f32>>>>, 
+        ram_init: RamInit,
+        model: Model
+    ) -> Self {
         let spu = Spu::new(device);
-        let mmu = Rc::new(RefCell::new(Mmu::new(cartridge, spu)));
+        let mut mmu = Mmu::new_with_model(cartridge, spu, model);
+        mmu.init_ram(ram_init);
+        let mmu = Rc::new(RefCell::new(mmu));
         
         let cpu = Cpu::new(mmu.clone());
         let ppu = Ppu::new(mmu.clone());
@@ -41,26 +169,182 @@ impl GameBoy {
         Self {
             cpu,
             mmu,
-            ppu
+            ppu,
+
+            paused: false,
+            rewind: Rewind::new(),
+            movie: MovieState::Idle,
+            limiter: FrameLimiter::new(),
+            serial_console: None,
+            frame_speed: 1,
+            cycle_count: 0,
+            frame_metrics: FrameMetrics::default(),
+            state_dir: None,
+            vblank_taken: false,
+
+            error: None
         }
     }
 
     pub fn key_down(&mut self, key: Keycode) {
+        self.host_key_down(key.into());
+    }
+
+    pub fn key_up(&mut self, key: Keycode) {
+        self.host_key_up(key.into());
+    }
+
+    pub fn set_input_mapping(&mut self, mapping: HashMap<HostKey, Button>) {
+        (*self.mmu).borrow_mut().input.set_mapping(mapping);
+    }
+
+    // Returns false if the key isn't mapped to a button
+    pub fn host_key_down(&mut self, key: HostKey) -> bool {
+        let button = (*self.mmu).borrow().input.get_mapping().get(&key).copied();
+        if button.is_none() { return false; }
+
+        self.press_button(button.unwrap());
+        true
+    }
+
+    pub fn host_key_up(&mut self, key: HostKey) -> bool {
+        let button = (*self.mmu).borrow().input.get_mapping().get(&key).copied();
+        if button.is_none() { return false; }
+
+        self.release_button(button.unwrap());
+        true
+    }
+
+    pub fn press_button(&mut self, button: Button) {
         let mut mmu = (*self.mmu).borrow_mut();
-        let sucessful_press = mmu.input.key_down(key);
-        
-        if sucessful_press {
+        mmu.input.press_button(button);
+
+        self.cpu.stopped = false;
+        mmu.interupts.request_interupt(interupt::InterruptFlag::Joypad);
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        (*self.mmu).borrow_mut().input.release_button(button);
+    }
+
+    // The buttons held down by press_button, set_button_state, a movie or the keyboard.
+    // It doesn't matter which column the game has selected in JOYP.
+    pub fn button_state(&self) -> ButtonState {
+        (*self.mmu).borrow().input.button_state()
+    }
+
+    // Sets all eight buttons at once, a set bit means pressed. From bit 0 up:
+    // Right, Left, Up, Down, A, B, Select, Start (see Button::mask).
+    // Only the buttons that weren't already held down count as a press for the interrupt.
+    pub fn set_button_state(&mut self, mask: u8) {
+        let mut mmu = (*self.mmu).borrow_mut();
+        let newly_pressed = mask & !mmu.input.get_button_mask();
+        mmu.input.set_button_mask(mask);
+
+        if newly_pressed != 0 {
             self.cpu.stopped = false;
             mmu.interupts.request_interupt(interupt::InterruptFlag::Joypad);
         }
     }
 
-    pub fn key_up(&mut self, key: Keycode) {
-        (*self.mmu).borrow_mut().input.key_up(key);
+    // Records the buttons held at the start of every step_frame from now on
+    pub fn start_recording(&mut self) {
+        self.movie = MovieState::Recording(InputMovie::new());
+    }
+
+    // Gives back an empty movie if nothing was being recorded
+    pub fn stop_recording(&mut self) -> InputMovie {
+        match std::mem::replace(&mut self.movie, MovieState::Idle) {
+            MovieState::Recording(movie) => movie,
+            other => {
+                self.movie = other;
+                InputMovie::new()
+            }
+        }
+    }
+
+    // The movie's buttons replace the live ones at the start of every step_frame
+    // until it runs out. Load the state the movie was recorded from first.
+    pub fn play_movie(&mut self, movie: InputMovie) {
+        self.movie = MovieState::Playing(movie, 0);
+    }
+
+    pub fn is_playing_movie(&self) -> bool {
+        matches!(self.movie, MovieState::Playing(_, _))
+    }
+
+    fn movie_frame(&mut self) {
+        match &mut self.movie {
+            MovieState::Idle => {}
+
+            MovieState::Recording(movie) => {
+                movie.push((*self.mmu).borrow().input.get_button_mask());
+            }
+
+            MovieState::Playing(movie, frame) => {
+                if *frame >= movie.len() {
+                    self.movie = MovieState::Idle;
+                    return;
+                }
+
+                let mask = movie.frames()[*frame];
+                *frame += 1;
+                self.set_button_state(mask);
+            }
+        }
+    }
+
+    // The game title from the cartridge header
+    pub fn get_title(&self) -> String {
+        let mmu = (*self.mmu).borrow();
+
+        (0x134..=0x143)
+            .map(|addr| mmu.peek_byte(addr))
+            .take_while(|c| *c != 0)
+            .map(|c| c as char)
+            .collect()
     }
 
     pub fn get_frame_buffer(&self) -> &[u8] {
-        &self.ppu.frame_buffer
+        self.ppu.get_output_frame()
+    }
+
+    // The frame buffer upscaled by factor (1 is just a copy), still 160 * factor pixels wide
+    pub fn framebuffer_scaled(&self, factor: u8) -> Vec<u8> {
+        scale_nearest(self.get_frame_buffer(), 160, 144, factor)
+    }
+
+    // The frame buffer as text, 80 characters across and 36 lines down, see ascii_art
+    pub fn render_ascii(&self) -> String {
+        self.render_ascii_with_ratio(2)
+    }
+
+    // Each character covers ratio pixels across and ratio * 2 down
+    pub fn render_ascii_with_ratio(&self, ratio: u8) -> String {
+        ascii_art(self.get_frame_buffer(), 160, 144, ratio)
+    }
+
+    // Only restarts the ppu, as if the lcd had just been turned on, with a blank frame buffer.
+    // Everything else, including vram and OAM, carries on as it was.
+    pub fn reset_ppu(&mut self) {
+        self.ppu.restart();
+    }
+
+    // Only silences the apu and throws away the audio waiting to be played, see Spu::restart
+    pub fn reset_apu(&mut self) {
+        (*self.mmu).borrow_mut().spu.restart();
+    }
+
+    // Averages every frame with the one before, so flickering sprites
+    // look see-through like they do on a real DMG
+    pub fn set_frame_blend(&mut self, enabled: bool) {
+        self.ppu.set_frame_blend(enabled);
+    }
+
+    // How many sprites can be drawn on one line. Real hardware stops at 10,
+    // None draws them all to get rid of the flicker some games use to get around that.
+    pub fn set_sprite_limit(&mut self, limit: Option<u8>) {
+        self.ppu.set_sprite_limit(limit);
     }
 
     pub fn get_draw_flag(&self) -> bool {
@@ -71,12 +355,799 @@ impl GameBoy {
         self.ppu.draw_flag = false;
     }
 
+    // For frontends that poll instead of being called back: true if the ppu has finished
+    // a frame since the last call. Unlike the draw flag, nothing else clears it.
+    pub fn took_vblank(&mut self) -> bool {
+        let taken = self.vblank_taken;
+        self.vblank_taken = false;
+        taken
+    }
+
+    // The audio produced since the last drain, only collected when there's no audio device.
+    // The samples are interleaved left/right at spu::SAMPLE_RATE.
+    pub fn drain_audio(&mut self) -> Vec<f32> {
+        (*self.mmu).borrow_mut().spu.drain_samples()
+    }
+
+    // Copies everything sent to the audio output into a .wav file until stop_audio_recording
+    // is called. Fast forwarded frames are muted, so they're left out of the recording too.
+    // Starting a new recording finishes off the old one.
+    #[cfg(feature = "hound")]
+    pub fn start_audio_recording(&mut self, path: &str) -> Result<(), hound::Error> {
+        let recorder = WavRecorder::new(Path::new(path))?;
+        let old_recorder = (*self.mmu).borrow_mut().spu.set_recorder(Some(recorder));
+
+        match old_recorder {
+            Some(old_recorder) => old_recorder.finish(),
+            None => Ok(())
+        }
+    }
+
+    // Does nothing if there wasn't a recording
+    #[cfg(feature = "hound")]
+    pub fn stop_audio_recording(&mut self) -> Result<(), hound::Error> {
+        let recorder = (*self.mmu).borrow_mut().spu.set_recorder(None);
+
+        match recorder {
+            Some(recorder) => recorder.finish(),
+            None => Ok(())
+        }
+    }
+
+    // How many samples are buffered, in the audio device if there is one.
+    // Left and right are counted separately.
+    pub fn audio_samples_available(&self) -> usize {
+        (*self.mmu).borrow().spu.samples_available()
+    }
+
+    // The most samples that get buffered before some are dropped, so running in turbo
+    // without draining doesn't keep using more memory. Defaults to spu::DEFAULT_AUDIO_BUFFER_CAPACITY.
+    pub fn set_audio_buffer_capacity(&mut self, capacity: usize) {
+        (*self.mmu).borrow_mut().spu.set_audio_buffer_capacity(capacity);
+    }
+
+    // Reads a byte from the memory bus, as the cpu would see it.
+    // Useful for debuggers and tests, no cycles are spent doing this. Unlike the cpu,
+    // reading an unhandled address here doesn't leave the GameBoy with an error.
+    pub fn read_byte(&self, addr: u16) -> u8 {
+        (*self.mmu).borrow().peek_byte(addr)
+    }
+
+    // Writes a byte to the memory bus, as the cpu would.
+    // Useful for debuggers and tests, no cycles are spent doing this. Unhandled
+    // addresses are ignored without leaving an error, same as read_byte.
+    pub fn write_byte(&mut self, addr: u16, val: u8) {
+        (*self.mmu).borrow_mut().poke_byte(addr, val);
+    }
+
+    // For attaching to bug reports, loading a dump gets back the exact same tiles
+    pub fn dump_vram(&self) -> Vec<u8> {
+        (*self.mmu).borrow().dump_vram()
+    }
+
+    // The tile data half of vram, 0x8000 - 0x97FF
+    pub fn tile_data(&self) -> Vec<u8> {
+        (*self.mmu).borrow().tile_data().to_vec()
+    }
+
+    // The 1024 tile numbers of tile map 0 (0x9800) or 1 (0x9C00), panics for any other map
+    pub fn tile_map(&self, map: u8) -> Vec<u8> {
+        (*self.mmu).borrow().tile_map(map).to_vec()
+    }
+
+    // The banks the cartridge has switched in
+    pub fn memory_layout(&self) -> MemoryLayout {
+        (*self.mmu).borrow().memory_layout()
+    }
+
+    // Debugging only: forces a bank in at 0x4000 - 0x7FFF until the game next writes to
+    // the mapper. A running game that expects its own bank there is likely to crash.
+    pub fn set_rom_bank(&mut self, bank: u16) {
+        (*self.mmu).borrow_mut().set_rom_bank(bank);
+    }
+
+    // Debugging only, like set_rom_bank for the cartridge ram at 0xA000 - 0xBFFF
+    pub fn set_ram_bank(&mut self, bank: u8) {
+        (*self.mmu).borrow_mut().set_ram_bank(bank);
+    }
+
+    pub fn current_rom_bank(&self) -> u16 {
+        self.memory_layout().rom_bank as u16
+    }
+
+    pub fn current_ram_bank(&self) -> u8 {
+        self.memory_layout().ram_bank as u8
+    }
+
+    // Whether the header has the logo the real boot rom checks for
+    pub fn has_nintendo_logo(&self) -> bool {
+        (*self.mmu).borrow().has_nintendo_logo()
+    }
+
+    // For rom browsers: takes the cartridge out and hands back its ram for saving, empty if it
+    // has none. Its save file is still written as usual. The cpu carries on with nothing in the slot.
+    pub fn eject_cartridge(&mut self) -> Vec<u8> {
+        let cartridge = (*self.mmu).borrow_mut().replace_cartridge(Cartridge::empty());
+        cartridge.ram().to_vec()
+    }
+
+    // Puts a new cartridge in and starts it from reset, through the boot rom. Whatever was in the
+    // slot is dropped, which writes its save file. The cheats and rewind snapshots were for the
+    // old game so they're cleared, everything else outside the cpu is left to the boot rom.
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        {
+            let mut mmu = (*self.mmu).borrow_mut();
+            mmu.replace_cartridge(cartridge);
+            mmu.bios_enabled = true;
+            mmu.cheats.clear();
+        }
+
+        let trap_stack_corruption = self.cpu.trap_stack_corruption;
+        self.cpu = Cpu::new(self.mmu.clone());
+        self.cpu.trap_stack_corruption = trap_stack_corruption;
+        self.rewind.clear();
+        self.error = None;
+    }
+
+    // An override for ROMs with the wrong cartridge type in their header, see Cartridge::force_mapper.
+    // Call it before running anything. Save states only hold the mapper's registers, so it has to
+    // be forced again before loading a state that was saved with it.
+    pub fn force_mapper(&mut self, kind: MapperKind) {
+        (*self.mmu).borrow_mut().force_mapper(kind);
+    }
+
+    // Everything the cartridge header says, for showing what's been loaded
+    pub fn cartridge_info(&self) -> CartridgeInfo {
+        (*self.mmu).borrow().cartridge_info()
+    }
+
+    pub fn load_vram(&mut self, data: &[u8]) {
+        (*self.mmu).borrow_mut().load_vram(data);
+    }
+
+    pub fn get_tile(&self, index: usize) -> [[u8; 8]; 8] {
+        (*self.mmu).borrow().get_tile(index)
+    }
+
+    // Panics if the index isn't below 384
+    pub fn set_tile(&mut self, index: usize, data: [[u8; 8]; 8]) {
+        (*self.mmu).borrow_mut().set_tile(index, data);
+    }
+
+    // Plugs in a link cable to another emulator, replacing any cable already plugged in
+    pub fn connect_link_cable(&mut self, cable: Box<dyn LinkCable>) {
+        (*self.mmu).borrow_mut().serial.connect(cable);
+    }
+
+    pub fn disconnect_link_cable(&mut self) {
+        (*self.mmu).borrow_mut().serial.disconnect();
+    }
+
+    // Test roms and homebrew often print to the serial port. With the console on, every
+    // byte the game starts sending is collected as text, with or without a link cable.
+    // Turning it off throws away what's been collected.
+    pub fn set_serial_console(&mut self, enabled: bool) {
+        self.serial_console = match enabled {
+            true => Some(self.serial_console.take().unwrap_or_default()),
+            false => None
+        };
+    }
+
+    pub fn serial_console_output(&self) -> &str {
+        match &self.serial_console {
+            Some(output) => output,
+            None => ""
+        }
+    }
+
+    pub fn clear_serial_console(&mut self) {
+        if let Some(console) = &mut self.serial_console {
+            console.clear();
+        }
+    }
+
+    // Only printable ascii and new lines are kept
+    fn serial_console_write(output: &mut String, val: u8) {
+        if val == b'\n' || val.is_ascii_graphic() || val == b' ' {
+            output.push(val as char);
+        }
+    }
+
+    pub fn set_unusable_oam_policy(&mut self, policy: UnusableOamPolicy) {
+        (*self.mmu).borrow_mut().unusable_oam_policy = policy;
+    }
+
+    // On by default, it only happens on the models with a DMG cpu. See Mmu::oam_bug_write.
+    pub fn set_oam_bug(&mut self, enabled: bool) {
+        (*self.mmu).borrow_mut().oam_bug = enabled;
+    }
+
+    // Disassembles memory from the given address onwards, as the cpu would see it.
+    // The memory can't be written to while the iterator is alive.
+    pub fn disassemble(&self, addr: u16) -> DisassemblyIterator<Ref<'_, Mmu>> {
+        DisassemblyIterator::new((*self.mmu).borrow(), addr)
+    }
+
     pub fn start_log(&mut self) {
         self.cpu.start_log = true;
     }
 
+    // Pausing freezes the whole machine between two ticks, so any in progress
+    // instruction or ppu mode just carries on from where it was once resumed.
+    // This is separate from the cpu being stopped by the STOP instruction.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // An instruction the cpu is part way through is saved too, it carries on from
+    // the same step when the state is loaded.
+    pub fn save_state(&mut self) -> Vec<u8> {
+        self.write_state()
+    }
+
+    // The state is checked before anything is loaded, so an invalid state
+    // leaves the machine as it was.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut state = StateReader::new(state)?;
+        self.read_components(&mut state);
+
+        Ok(())
+    }
+
+    fn finish_instruction(&mut self) {
+        while self.cpu.is_processing_instruction() {
+            if self.tick() { break }
+        }
+    }
+
+    fn write_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        self.write_components(&mut state);
+        state.finish()
+    }
+
+    fn write_components(&self, state: &mut StateWriter) {
+        self.cpu.save_state(state);
+        self.ppu.save_state(state);
+        (*self.mmu).borrow().save_state(state);
+    }
+
+    fn read_components(&mut self, state: &mut StateReader) {
+        self.cpu.load_state(state);
+        self.ppu.load_state(state);
+        (*self.mmu).borrow_mut().load_state(state);
+
+        // loading a state is a way out of an error
+        self.error = None;
+    }
+
+    // The directory save_state_to_slot writes to, it has to already exist
+    pub fn set_state_directory(&mut self, dir: &str) {
+        self.state_dir = Some(PathBuf::from(dir));
+    }
+
+    // Identifies the ROM a slot was saved from, it's the same for every ROM with the same title
+    pub fn rom_hash(&self) -> u64 {
+        save_state::rom_hash(&self.get_title())
+    }
+
+    // Overwrites whatever was in the slot
+    pub fn save_state_to_slot(&mut self, slot: u8) -> Result<StateMeta, StateError> {
+        if self.state_dir.is_none() { return Err(StateError::NoStateDirectory) }
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let meta = StateMeta {
+            slot,
+            rom_hash: self.rom_hash(),
+            timestamp: timestamp.as_secs(),
+            frame_count: self.ppu.frame_count
+        };
+
+        let mut state = StateWriter::new_slot();
+        meta.write(&mut state);
+        self.write_components(&mut state);
+
+        let path = save_state::slot_path(self.state_dir.as_ref().unwrap(), slot);
+        fs::write(path, state.finish())?;
+
+        Ok(meta)
+    }
+
+    // A state saved from another ROM is refused with WrongRom,
+    // like an invalid state it leaves the machine as it was.
+    pub fn load_state_from_slot(&mut self, slot: u8) -> Result<StateMeta, StateError> {
+        if self.state_dir.is_none() { return Err(StateError::NoStateDirectory) }
+
+        let path = save_state::slot_path(self.state_dir.as_ref().unwrap(), slot);
+        let data = fs::read(path)?;
+
+        let mut state = StateReader::new_slot(&data)?;
+        let meta = StateMeta::read(&mut state);
+        if meta.rom_hash != self.rom_hash() {
+            return Err(StateError::WrongRom);
+        }
+
+        self.read_components(&mut state);
+        Ok(meta)
+    }
+
+    // Every slot in the state directory, in slot order, whichever ROM they're from.
+    // Files that can't be read are left out.
+    pub fn list_states(&self) -> Vec<StateMeta> {
+        if self.state_dir.is_none() { return Vec::new() }
+        let dir = self.state_dir.as_ref().unwrap();
+
+        (0..=u8::MAX)
+            .filter_map(|slot| fs::read(save_state::slot_path(dir, slot)).ok())
+            .filter_map(|data| {
+                let mut state = StateReader::new_slot(&data).ok()?;
+                Some(StateMeta::read(&mut state))
+            })
+            .collect()
+    }
+
+    // Keeps snapshots for rewind_frame going back the given number of seconds. Off by default,
+    // as a snapshot every few frames adds up. Changing it throws away the snapshots kept so far.
+    pub fn set_rewind(&mut self, enabled: bool, seconds: u32) {
+        self.rewind.set_enabled(enabled, seconds);
+    }
+
+    // Goes back to the latest rewind snapshot, which is at most
+    // REWIND_FRAMES_PER_SNAPSHOT frames ago, calling it again keeps going further back.
+    // Returns false if there's nothing left to rewind to, or rewinding is turned off.
+    pub fn rewind_frame(&mut self) -> bool {
+        let snapshot = match self.rewind.pop() {
+            Some(snapshot) => snapshot,
+            None => return false
+        };
+
+        self.load_state(&snapshot).unwrap();
+        true
+    }
+
+    // Runs until the ppu has a full frame ready to be drawn.
+    // Stops early if the cpu gets stopped or something goes wrong.
+    pub fn step_frame(&mut self) -> Result<FrameStatus, EmuError> {
+        if let Some(error) = self.error { return Err(error) }
+        if self.paused { return Ok(FrameStatus::Paused) }
+
+        let speed = self.frame_speed;
+        self.frame_speed = 1;
+
+        let muted = speed > 1;
+        (*self.mmu).borrow_mut().spu.set_muted(muted);
+        let stopped = self.run_frames(speed);
+        (*self.mmu).borrow_mut().spu.set_muted(false);
+
+        if stopped {
+            return self.status_after_stop();
+        }
+
+        self.limiter.frame();
+        Ok(FrameStatus::Completed)
+    }
+
+    // For frontends that draw on VBlank: runs until the ppu next enters VBlank, even if the last
+    // call stopped part way through a frame, and returns the frame it just finished.
+    // There's no frame limiting or fast forwarding. If the cpu gets stopped or something
+    // goes wrong it returns early with whatever is in the frame buffer, get_error says what.
+    pub fn run_to_vblank(&mut self) -> &[u8] {
+        if self.error.is_none() && !self.paused {
+            self.run_frames(1);
+        }
+
+        self.get_frame_buffer()
+    }
+
+    // For recording video: runs a frame like run_to_vblank and returns it along with the audio
+    // for exactly the cycles that ran, as left/right pairs at spu::SAMPLE_RATE. drain_audio
+    // can't be used for this as its samples come in blocks, and not at all while the sound is off.
+    // Nothing is fast forwarded or frame limited.
+    pub fn step_frame_av(&mut self) -> Result<AvFrame, EmuError> {
+        if let Some(error) = self.error { return Err(error) }
+        if self.paused { return Ok((self.get_frame_buffer().to_vec(), Vec::new())) }
+
+        (*self.mmu).borrow_mut().spu.start_av_capture();
+        let stopped = self.run_frames(1);
+        let samples = (*self.mmu).borrow_mut().spu.finish_av_capture();
+
+        if stopped {
+            self.status_after_stop()?;
+        }
+
+        Ok((self.get_frame_buffer().to_vec(), samples))
+    }
+
+    // True while the cpu is halted or spinning on a jump to itself, like a jr -2 waiting
+    // for an interrupt. Loops that poll memory or registers don't count, they could be
+    // waiting on something other than an interrupt.
+    pub fn is_idle(&self) -> bool {
+        self.cpu.halted || self.cpu.idle
+    }
+
+    // Fast forwards through an idle loop: runs until the cpu takes an interrupt or does
+    // anything else, or until the ppu finishes a frame so the frontend can draw it.
+    // Everything but the cpu still runs cycle by cycle, so nothing is missed. The cpu is
+    // left out of the trips round a jump to itself, so they don't count towards the instruction
+    // count. A halted cpu already does next to nothing. Finishes the trip round the loop it's
+    // on when the frame ends, so it can run a few cycles past the end of the frame.
+    // Returns how many clock cycles it ran for, 0 if the cpu wasn't idle.
+    pub fn skip_idle(&mut self) -> u64 {
+        if self.error.is_some() || self.paused { return 0 }
+
+        let start_totals = self.metric_totals();
+        let start_time = Instant::now();
+
+        let frame_count = self.ppu.frame_count;
+        let mut cycles = 0;
+
+        while self.is_idle() && self.ppu.frame_count == frame_count {
+            let loop_cycles = self.cpu.idle_loop_cycles().filter(|_| !self.is_interrupt_pending());
+            if let Some(loop_cycles) = loop_cycles {
+                cycles += self.skip_idle_loop(loop_cycles);
+                continue;
+            }
+
+            if self.tick() { break }
+            cycles += 1;
+        }
+
+        self.frame_metrics = FrameMetrics::between(&start_totals, &self.metric_totals(), start_time.elapsed());
+        cycles
+    }
+
+    // One trip round a jump to itself, without the cpu. An interrupt could only be taken while the
+    // jump is being fetched. If one comes up then, the cpu is caught up to where it would have
+    // been and left for tick to take it as normal.
+    // Returns how many clock cycles it ran for.
+    fn skip_idle_loop(&mut self, loop_cycles: u64) -> u64 {
+        for cycle in 0..loop_cycles {
+            if cycle < CYCLES_PER_MACHINE_CYCLE as u64 && self.is_interrupt_pending() {
+                for _ in 0..cycle {
+                    self.cpu.tick();
+                }
+
+                return cycle;
+            }
+
+            self.cycle_count += 1;
+            self.tick_components();
+        }
+
+        loop_cycles
+    }
+
+    // An interrupt the cpu would take, if it's in a state to take one
+    fn is_interrupt_pending(&self) -> bool {
+        let mmu = (*self.mmu).borrow();
+        mmu.interupts.is_master_enabled() && mmu.interupts.get_interupt_state().is_some()
+    }
+
+    // Returns true if the cpu got stopped or something went wrong
+    fn run_frames(&mut self, frames: u32) -> bool {
+        let start_totals = self.metric_totals();
+        let start_time = Instant::now();
+
+        let mut stopped = false;
+        'frames: for _ in 0..frames {
+            self.movie_frame();
+            self.clear_draw_flag();
+
+            while !self.get_draw_flag() {
+                if self.tick() {
+                    stopped = true;
+                    break 'frames;
+                }
+            }
+        }
+
+        self.frame_metrics = FrameMetrics::between(&start_totals, &self.metric_totals(), start_time.elapsed());
+        stopped
+    }
+
+    // Counts from the last call to step_frame, step_frame_av, run_to_vblank or skip_idle
+    pub fn frame_metrics(&self) -> FrameMetrics {
+        self.frame_metrics
+    }
+
+    fn metric_totals(&self) -> FrameMetrics {
+        FrameMetrics {
+            cycles: self.cycle_count,
+            instructions: self.cpu.instruction_count,
+            ppu_dots: self.ppu.dot_count,
+            ..FrameMetrics::default()
+        }
+    }
+
+    // For fast forwarding while a key is held: the next call to step_frame runs this
+    // many frames instead of 1, and only the last one is left in the frame buffer.
+    // The audio from those frames is thrown away so the sound doesn't fall behind.
+    // It only lasts for that one call, so call this before every step_frame it should apply to.
+    pub fn set_frame_speed(&mut self, speed: u32) {
+        self.frame_speed = speed.max(1);
+    }
+
+    // Has step_frame sleep to keep to the given frame rate, for when there's no
+    // audio device to keep time with. 0 turns it off, which is the default.
+    pub fn set_target_fps(&mut self, fps: f64) {
+        self.limiter.set_target_fps(fps);
+    }
+
+    // How many frames step_frame has been completing a second recently
+    pub fn current_fps(&self) -> f64 {
+        self.limiter.current_fps()
+    }
+
+    // The limiter uses the system clock unless it's given another
+    pub fn set_limiter_clock(&mut self, clock: Box<dyn Clock>) {
+        self.limiter.set_clock(clock);
+    }
+
+    // Runs for the given number of clock cycles.
+    // Stops early if the cpu gets stopped or something goes wrong.
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<FrameStatus, EmuError> {
+        if let Some(error) = self.error { return Err(error) }
+        if self.paused { return Ok(FrameStatus::Paused) }
+
+        for _ in 0..cycles {
+            if self.tick() {
+                return self.status_after_stop();
+            }
+        }
+
+        Ok(FrameStatus::Completed)
+    }
+
+    // Runs until the next instruction has fully completed, or finishes the current one
+    // if ticking stopped part way through it. Returns the instruction in the same form
+    // as disassemble. An interrupt dispatch counts as an instruction, it's returned as
+    // the "Interupt Service Routine" with the address of the handler it jumped to.
+    pub fn step_instruction(&mut self) -> Result<(u16, Instruction, Vec<u8>), EmuError> {
+        if let Some(error) = self.error { return Err(error) }
+
+        let mut started = self.cpu.is_processing_instruction();
+        loop {
+            let stopped = self.tick();
+            if let Some(error) = self.error { return Err(error) }
+
+            if self.cpu.is_processing_instruction() {
+                started = true;
+            }
+            else if started || stopped {
+                break;
+            }
+        }
+
+        match self.cpu.instruction_addr {
+            Some(addr) => Ok(self.disassemble(addr).next().unwrap()),
+            None => Ok((self.cpu.pc, Interupt::create_interupt_instruction(), Vec::new()))
+        }
+    }
+
+    fn status_after_stop(&self) -> Result<FrameStatus, EmuError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(FrameStatus::Stopped)
+        }
+    }
+
+    pub fn get_error(&self) -> Option<EmuError> {
+        self.error
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.cpu.registers()
+    }
+
+    // The Z, N, H and C flags from F
+    pub fn flags(&self) -> Flags {
+        self.cpu.flags()
+    }
+
+    pub fn interrupt_state(&self) -> InterruptState {
+        self.cpu.interrupt_state()
+    }
+
+    // The timer's internal 16 bit counter, FF04 only shows the upper 8 bits.
+    // It goes up once per dot, and any write to FF04 zeroes all of it.
+    pub fn div_counter(&self) -> u16 {
+        self.mmu.borrow().timer.get_div()
+    }
+
+    // Requests an interrupt as if the hardware had, for testing interrupt handlers
+    pub fn force_interrupt(&mut self, flag: InterruptFlag) {
+        self.cpu.force_interrupt(flag);
+    }
+
+    // For testing handlers: finishes the current instruction then jumps straight into
+    // the interrupt's handler, whether or not it's enabled. Returns the new PC.
+    pub fn dispatch_interrupt(&mut self, flag: InterruptFlag) -> u16 {
+        self.finish_instruction();
+        self.cpu.dispatch_interrupt(flag)
+    }
+
+    // Runs a single opcode straight away, see Cpu::execute_opcode. Any instruction
+    // the cpu is in the middle of is finished first. The ppu and timers don't move.
+    pub fn execute_opcode(&mut self, opcode: u8, op8: u8, op16: u16) -> Result<u32, EmuError> {
+        self.finish_instruction();
+        self.cpu.execute_opcode(opcode, op8, op16)
+    }
+
+    // See Cpu::cycles_for_opcode
+    pub fn cycles_for_opcode(opcode: u8, cb_opcode: u8, branch_taken: bool) -> Option<u32> {
+        Cpu::cycles_for_opcode(opcode, cb_opcode, branch_taken)
+    }
+
+    // For cpu tests without building a rom: copies the code to addr, which should be
+    // somewhere writable like wram, jumps to it and runs until the cpu reaches the sentinel.
+    // The sentinel has to be one of the illegal opcodes, it works as a breakpoint and the pc
+    // is left on it. Any other error stops the run, as does running max_instructions without
+    // getting to the sentinel.
+    pub fn run_code(&mut self, addr: u16, code: &[u8], sentinel: u8, max_instructions: u64) -> Result<Registers, RunCodeError> {
+        if !is_illegal_opcode(sentinel) {
+            return Err(RunCodeError::InvalidSentinel(sentinel));
+        }
+
+        self.finish_instruction();
+        for (i, byte) in code.iter().enumerate() {
+            self.write_byte(addr.wrapping_add(i as u16), *byte);
+        }
+        self.set_pc(addr);
+
+        for _ in 0..max_instructions {
+            match self.step_instruction() {
+                Ok(_) => { }
+                Err(EmuError::IllegalOpcode { opcode, .. }) if opcode == sentinel => {
+                    self.error = None;
+                    return Ok(self.registers());
+                }
+                Err(error) => return Err(error.into())
+            }
+        }
+
+        Err(RunCodeError::Timeout { instructions: max_instructions })
+    }
+
+    // Advanced: puts the cpu into any state, for setting up tests or cheats.
+    // Nothing a real GameBoy could do is checked for, and the registers change straight away
+    // even if the cpu is in the middle of an instruction, so this can break accuracy.
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.cpu.set_registers(registers);
+    }
+
+    // Starts the cartridge straight away instead of running the boot rom, with the registers
+    // left as the given model's boot rom would leave them. The model's quirks are used from then on.
+    // Call this before running anything.
+    pub fn skip_boot_rom(&mut self, model: Model) {
+        let header_checksum = self.read_byte(0x14D);
+        self.set_model(model);
+        (*self.mmu).borrow_mut().skip_boot_rom();
+        self.set_registers(model.post_boot_registers(header_checksum));
+    }
+
+    // The console being emulated, for the hardware quirks that only some models have
+    pub fn set_model(&mut self, model: Model) {
+        (*self.mmu).borrow_mut().model = model;
+    }
+
+    pub fn model(&self) -> Model {
+        (*self.mmu).borrow().model
+    }
+
+    fn update_registers(&mut self, update: impl FnOnce(&mut Registers)) {
+        let mut registers = self.registers();
+        update(&mut registers);
+        self.set_registers(registers);
+    }
+
+    // Advanced, see set_registers
+    pub fn set_pc(&mut self, val: u16) {
+        self.update_registers(|registers| registers.pc = val);
+    }
+
+    pub fn set_sp(&mut self, val: u16) {
+        self.update_registers(|registers| registers.sp = val);
+    }
+
+    pub fn set_a(&mut self, val: u8) {
+        self.update_registers(|registers| registers.a = val);
+    }
+
+    pub fn set_f(&mut self, val: u8) {
+        self.update_registers(|registers| registers.f = val);
+    }
+
+    pub fn set_flags(&mut self, flags: Flags) {
+        self.cpu.set_flags(flags);
+    }
+
+    pub fn set_b(&mut self, val: u8) {
+        self.update_registers(|registers| registers.b = val);
+    }
+
+    pub fn set_c(&mut self, val: u8) {
+        self.update_registers(|registers| registers.c = val);
+    }
+
+    pub fn set_d(&mut self, val: u8) {
+        self.update_registers(|registers| registers.d = val);
+    }
+
+    pub fn set_e(&mut self, val: u8) {
+        self.update_registers(|registers| registers.e = val);
+    }
+
+    pub fn set_h(&mut self, val: u8) {
+        self.update_registers(|registers| registers.h = val);
+    }
+
+    pub fn set_l(&mut self, val: u8) {
+        self.update_registers(|registers| registers.l = val);
+    }
+
+    // Takes Game Genie codes (ABC-DEF or ABC-DEF-GHI) and GameShark codes (01VVAAAA)
+    pub fn add_cheat(&mut self, code: &str) -> Result<(), CheatError> {
+        (*self.mmu).borrow_mut().cheats.add(code)
+    }
+
+    // Returns false if the code wasn't active
+    pub fn remove_cheat(&mut self, code: &str) -> bool {
+        (*self.mmu).borrow_mut().cheats.remove(code)
+    }
+
+    pub fn clear_cheats(&mut self) {
+        (*self.mmu).borrow_mut().cheats.clear();
+    }
+
+    fn apply_game_shark_cheats(&mut self) {
+        let mut mmu = (*self.mmu).borrow_mut();
+        for (addr, val) in mmu.cheats.ram_writes() {
+            mmu.write_byte(addr, val);
+        }
+    }
+
+    // Warnings are only checked for in debug builds, so this is always empty in release
+    pub fn take_debug_warnings(&mut self) -> Vec<DebugWarning> {
+        let mut warnings = std::mem::take(&mut self.cpu.debug_warnings);
+        warnings.append(&mut (*self.mmu).borrow_mut().debug_warnings);
+        warnings
+    }
+
+    // Stops with EmuError::StackCorruption when something is pushed with SP in ROM.
+    // Off by default, as some test ROMs put the stack there on purpose.
+    pub fn set_trap_stack_corruption(&mut self, enabled: bool) {
+        self.cpu.trap_stack_corruption = enabled;
+    }
+
+    // Reports writes to read only registers like LY as debug warnings, instead of silently
+    // ignoring them. Off by default, and does nothing in release builds.
+    pub fn set_trap_read_only_writes(&mut self, enabled: bool) {
+        (*self.mmu).borrow_mut().trap_read_only_writes = enabled;
+    }
+
+    // Runs one cycle of the 4MHz master clock. Everything is stepped once per cycle, so
+    // the ppu moves on one dot at a time even part way through a cpu instruction.
+    // Returns true if the cpu has been stopped, or the GameBoy has
+    // been frozen by an error.
     pub fn tick(&mut self) -> bool {
-        if self.cpu.stopped { return true }
+        if self.cpu.stopped || self.error.is_some() { return true }
+
+        // left until the next tick, so rewinding straight after the frame
+        // that flagged it goes back to the previous snapshot
+        if self.rewind.snapshot_pending {
+            let snapshot = self.write_state();
+            self.rewind.push(snapshot);
+        }
+
+        self.cycle_count += 1;
 
         {
             let mut mmu = (*self.mmu).borrow_mut();
@@ -84,17 +1155,54 @@ impl GameBoy {
         }
 
         self.cpu.tick();
-        self.ppu.tick();
-        
-        let mut mmu = (*self.mmu).borrow_mut();
-        mmu.dma_tick();
-        mmu.spu.tick();
-        
-        let request_timer_interupt = mmu.timer.tick();
-        if request_timer_interupt {
-            mmu.interupts.request_interupt(InterruptFlag::Timer)
+        self.tick_components();
+
+        if self.cpu.error.is_some() {
+            self.error = self.cpu.error.take();
         }
 
+        if self.error.is_some() { return true }
+
         self.cpu.stopped
     }
+
+    // Everything but the cpu, for one clock cycle
+    fn tick_components(&mut self) {
+        let frame_count = self.ppu.frame_count;
+        self.ppu.tick();
+        if self.ppu.frame_count != frame_count {
+            self.vblank_taken = true;
+            self.rewind.frame();
+            self.apply_game_shark_cheats();
+        }
+        
+        {
+            let mut mmu = (*self.mmu).borrow_mut();
+            mmu.dma_tick();
+            mmu.spu.tick();
+            
+            let request_timer_interupt = mmu.timer.tick();
+            if request_timer_interupt {
+                mmu.interupts.request_interupt(InterruptFlag::Timer)
+            }
+
+            let div = mmu.timer.get_div();
+            if mmu.serial.tick(div) {
+                mmu.interupts.request_interupt(InterruptFlag::Serial)
+            }
+
+            if let (Some(sent), Some(console)) = (mmu.serial.take_sent_byte(), &mut self.serial_console) {
+                Self::serial_console_write(console, sent);
+            }
+
+            if mmu.timer.take_apu_clock() {
+                mmu.spu.clock_frame_sequencer();
+            }
+
+            let mmu_error = mmu.error.take();
+            if mmu_error.is_some() {
+                self.error = mmu_error;
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use super::cpu::Registers;
+
+// https://gbdev.io/pandocs/Power_Up_Sequence.html#cpu-registers
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Model {
+    // The very first DMG boot rom
+    Dmg0,
+    #[default]
+    Dmg,
+    // Game Boy Pocket
+    Mgb,
+    Sgb,
+    Sgb2,
+    Cgb
+}
+
+// What each model's boot rom leaves in the registers, in the order
+// A, F, B, C, D, E, H, L
+const POST_BOOT_REGISTERS: [(Model, [u8; 8]); 6] = [
+    (Model::Dmg0, [0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03]),
+    (Model::Dmg,  [0x01, 0x80, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D]),
+    (Model::Mgb,  [0xFF, 0x80, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D]),
+    (Model::Sgb,  [0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60]),
+    (Model::Sgb2, [0xFF, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60]),
+    (Model::Cgb,  [0x11, 0x80, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D])
+];
+
+impl Model {
+    // The DMG and MGB boot roms finish with a comparison against the header checksum,
+    // which leaves the H and C flags set unless the checksum is 0
+    pub fn post_boot_registers(&self, header_checksum: u8) -> Registers {
+        let (_, regs) = POST_BOOT_REGISTERS.iter()
+            .find(|(model, _)| model == self)
+            .unwrap();
+
+        let mut f = regs[1];
+        if (*self == Model::Dmg || *self == Model::Mgb) && header_checksum != 0 {
+            f |= 0b0011_0000;
+        }
+
+        Registers {
+            a: regs[0],
+            f,
+            b: regs[2],
+            c: regs[3],
+            d: regs[4],
+            e: regs[5],
+            h: regs[6],
+            l: regs[7],
+
+            sp: 0xFFFE,
+            pc: 0x0100
+        }
+    }
+}
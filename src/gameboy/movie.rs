@@ -0,0 +1,66 @@
+// An input movie is the button mask (see input::Button::mask) held down for each
+// frame, in order. Played back from the same starting state it reproduces a run exactly.
+
+const MAGIC: [u8; 4] = *b"GBRM";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputMovie {
+    frames: Vec<u8>
+}
+
+impl Default for InputMovie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputMovie {
+    pub fn new() -> Self {
+        Self {
+            frames: Vec::new()
+        }
+    }
+
+    pub fn from_frames(frames: Vec<u8>) -> Self {
+        Self {
+            frames
+        }
+    }
+
+    pub fn frames(&self) -> &[u8] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn push(&mut self, mask: u8) {
+        self.frames.push(mask);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + self.frames.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.frames);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < MAGIC.len() || bytes[0..4] != MAGIC {
+            return None;
+        }
+
+        Some(Self::from_frames(bytes[4..].to_vec()))
+    }
+}
+
+pub(super) enum MovieState {
+    Idle,
+    Recording(InputMovie),
+    Playing(InputMovie, usize)
+}
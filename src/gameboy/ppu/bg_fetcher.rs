@@ -1,6 +1,6 @@
-use std::{cell::{Ref, RefCell}, collections::VecDeque, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
-use crate::gameboy::{mmu::Mmu, ppu::{LcdControlFlag, Ppu}};
+use crate::gameboy::{mmu::Mmu, ppu::LcdControlFlag, save_state::{StateReader, StateWriter}};
 
 
 pub enum FetchMode {
@@ -54,9 +54,38 @@ impl BgFetcher {
         self.high_data = 0;
     }
 
-    fn get_adjusted_tile_index(mmu: &Ref<Mmu>, addr: u16, signed_tile_index: bool) -> u16 {
+    pub fn save_state(&self, state: &mut StateWriter) {
+        let window = match self.mode {
+            FetchMode::Background => false,
+            FetchMode::Window => true
+        };
+        state.write_bool(window);
+        state.write_u8(self.cycle);
+        state.write_u16(self.tile_counter);
+        state.write_u16(self.tile_data_addr);
+        state.write_u16(self.tile_num);
+        state.write_bool(self.reset_on_first_step_3);
+        state.write_u8(self.low_data);
+        state.write_u8(self.high_data);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.mode = match state.read_bool() {
+            false => FetchMode::Background,
+            true => FetchMode::Window
+        };
+        self.cycle = state.read_u8();
+        self.tile_counter = state.read_u16();
+        self.tile_data_addr = state.read_u16();
+        self.tile_num = state.read_u16();
+        self.reset_on_first_step_3 = state.read_bool();
+        self.low_data = state.read_u8();
+        self.high_data = state.read_u8();
+    }
+
+    fn get_adjusted_tile_index(tile_num: u8, signed_tile_index: bool) -> u16 {
         if signed_tile_index {
-            let tile = mmu.gpu_vram[(addr - 0x8000) as usize] as i8 as i16;
+            let tile = tile_num as i8 as i16;
             if tile >= 0 {
                 tile as u16 + 256
             }
@@ -65,21 +94,22 @@ impl BgFetcher {
             }
         }
         else {
-            mmu.gpu_vram[(addr - 0x8000) as usize] as u16
+            tile_num as u16
         }
     }
 
-    fn get_bg_map_start_addr(ldlc_flags: u8) -> u16 {
+    // 0 for the map at 0x9800, 1 for 0x9C00
+    fn get_bg_map(ldlc_flags: u8) -> u8 {
         match (ldlc_flags & LcdControlFlag::BGTileMapAddress as u8) != 0 {
-            true => 0x9C00,
-            false => 0x9800
+            true => 1,
+            false => 0
         }
     }
 
-    fn get_window_map_start_addr(ldlc_flags: u8) -> u16 {
+    fn get_window_map(ldlc_flags: u8) -> u8 {
         match (ldlc_flags & LcdControlFlag::WindowTileMapAddress as u8) != 0 {
-            true => 0x9C00,
-            false => 0x9800
+            true => 1,
+            false => 0
         }
     }
    
@@ -100,27 +130,24 @@ impl BgFetcher {
                 
                 let signed_tile_addressing: bool = ldlc_flags & LcdControlFlag::BGAndWindowTileData as u8 == 0;
 
-                let map_addr = match self.mode {
+                let (map, map_offset) = match self.mode {
                     FetchMode::Background => {
                         let tile_y = scan_line.wrapping_add(scroll_y) / 8;
                         let base_tile_map_offset = tile_y as u16 * 32;
 
-                        Self::get_bg_map_start_addr(ldlc_flags) + base_tile_map_offset +
-                            (scroll_x.wrapping_add(self.tile_counter as u8 * 8) / 8) as u16
+                        (Self::get_bg_map(ldlc_flags), base_tile_map_offset +
+                            (scroll_x.wrapping_add(self.tile_counter as u8 * 8) / 8) as u16)
                     }
 
                     FetchMode::Window => {
                         let base_tile_map_offset = (window_line_counter as u16 / 8) * 32;
-                        
-                        Self::get_window_map_start_addr(ldlc_flags) + 
-                            self.tile_counter + base_tile_map_offset
+
+                        (Self::get_window_map(ldlc_flags), self.tile_counter + base_tile_map_offset)
                     }
                 };
-                    
 
                 self.tile_num = Self::get_adjusted_tile_index(
-                    &mmu, 
-                    map_addr,
+                    mmu.tile_map(map)[map_offset as usize],
                     signed_tile_addressing
                 );
             }
@@ -143,7 +170,7 @@ impl BgFetcher {
                 };
 
                 self.tile_data_addr = 0x8000 + (self.tile_num * 16) + (offset as u16);
-                self.low_data = mmu.gpu_vram[(self.tile_data_addr - 0x8000) as usize];
+                self.low_data = mmu.tile_data()[(self.tile_data_addr - 0x8000) as usize];
             }
 
             6 => {
@@ -155,7 +182,7 @@ impl BgFetcher {
                 }
 
                 let mmu = (*self.mmu).borrow();
-                self.high_data = mmu.gpu_vram[(self.tile_data_addr + 1 - 0x8000) as usize];
+                self.high_data = mmu.tile_data()[(self.tile_data_addr + 1 - 0x8000) as usize];
             }
 
             8..=u8::MAX => {
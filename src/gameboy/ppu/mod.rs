@@ -1,7 +1,7 @@
 use std::{borrow::Borrow, cell::{RefCell}, cmp::Ordering, collections::VecDeque, rc::Rc};
 use self::{bg_fetcher::{FetchMode, BgFetcher}, sprite_fetcher::SpriteFetcher};
 
-use super::{interupt::InterruptFlag, mmu::Mmu};
+use super::{interupt::InterruptFlag, mmu::Mmu, save_state::{StateReader, StateWriter}};
 
 mod bg_fetcher;
 mod sprite_fetcher;
@@ -11,6 +11,15 @@ pub struct Ppu {
     mode: PpuMode,
     pub frame_buffer: [u8; 160 * 144],
 
+    // Frame blending averages each frame with the one before it, like the
+    // DMG's slow LCD does. Only the output is blended, so none of this is saved.
+    frame_blend: bool,
+    previous_frame: [u8; 160 * 144],
+    blended_frame: [u8; 160 * 144],
+
+    // How many sprites mode 2 picks per line, None for all of them. Not saved.
+    sprite_limit: Option<u8>,
+
     fifo_sprite_buffer: VecDeque<Sprite>,
     fifo_sprite_buffer_peek: Option<Sprite>,
 
@@ -26,6 +35,9 @@ pub struct Ppu {
     fifo_wy_ly_equal: bool,
     fifo_current_x: usize,
     fifo_sprite_fetch: bool,
+    fifo_sprite_wait: u8,
+    fifo_sprites_fetched: u8,
+    fifo_sprite_tile: Option<u16>,
     reset: bool,
 
     mode_clock_cycles: u64,
@@ -35,6 +47,9 @@ pub struct Ppu {
     wy_ly_equality_latch: bool,
 
     pub draw_flag: bool,
+    pub frame_count: u64,
+    // Every dot drawn with the lcd on since power on, for performance metrics. Not saved.
+    pub dot_count: u64,
 
     ly_153_early: bool,
 
@@ -49,13 +64,60 @@ pub struct Sprite {
     sprite_palette: usize,
     xflip: bool,
     yflip: bool,
-    belowbg: bool
+    belowbg: bool,
+    // where it is in OAM, for CGB OAM order priority
+    oam_index: u8
 }
 
 pub struct FifoPixel {
     sprite_palette: usize,
     sprite_color_bit: u8,
-    belowbg: bool
+    belowbg: bool,
+    oam_index: u8
+}
+
+impl Sprite {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.y);
+        state.write_u8(self.x);
+        state.write_u16(self.tile_num);
+        state.write_u8(self.sprite_palette as u8);
+        state.write_bool(self.xflip);
+        state.write_bool(self.yflip);
+        state.write_bool(self.belowbg);
+        state.write_u8(self.oam_index);
+    }
+
+    fn load_state(state: &mut StateReader) -> Self {
+        Self {
+            y: state.read_u8(),
+            x: state.read_u8(),
+            tile_num: state.read_u16(),
+            sprite_palette: (state.read_u8() & 1) as usize,
+            xflip: state.read_bool(),
+            yflip: state.read_bool(),
+            belowbg: state.read_bool(),
+            oam_index: state.read_u8()
+        }
+    }
+}
+
+impl FifoPixel {
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.sprite_palette as u8);
+        state.write_u8(self.sprite_color_bit);
+        state.write_bool(self.belowbg);
+        state.write_u8(self.oam_index);
+    }
+
+    fn load_state(state: &mut StateReader) -> Self {
+        Self {
+            sprite_palette: (state.read_u8() & 1) as usize,
+            sprite_color_bit: state.read_u8(),
+            belowbg: state.read_bool(),
+            oam_index: state.read_u8()
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -123,6 +185,12 @@ impl Ppu {
             mode: PpuMode::OAM,
             frame_buffer: [0; 160 * 144],
 
+            frame_blend: false,
+            previous_frame: [0; 160 * 144],
+            blended_frame: [0; 160 * 144],
+
+            sprite_limit: Some(Self::MAX_SPRITES_PER_LINE as u8),
+
             fifo_sprite_buffer: VecDeque::new(),
             fifo_sprite_buffer_peek: None,
 
@@ -138,6 +206,9 @@ impl Ppu {
             fifo_current_x: 0,
             fifo_wy_ly_equal: false,
             fifo_sprite_fetch: false,
+            fifo_sprite_wait: 0,
+            fifo_sprites_fetched: 0,
+            fifo_sprite_tile: None,
             reset: false,
 
             mode_clock_cycles: 0,
@@ -147,6 +218,8 @@ impl Ppu {
             wy_ly_equality_latch: false,
 
             draw_flag: false,
+            frame_count: 0,
+            dot_count: 0,
 
             ly_153_early: false,
 
@@ -154,6 +227,145 @@ impl Ppu {
         }
     }
 
+    // Starts again as if the lcd had just been turned on, with a blank frame.
+    // The sprite limit, frame blending and the frame count are kept.
+    pub fn restart(&mut self) {
+        self.frame_buffer = [Self::LCD_OFF_COLOR; 160 * 144];
+        self.previous_frame = [Self::LCD_OFF_COLOR; 160 * 144];
+        self.blended_frame = [Self::LCD_OFF_COLOR; 160 * 144];
+
+        self.fifo_sprite_buffer.clear();
+        self.fifo_sprite_buffer_peek = None;
+        self.window_internal_line_counter = 0;
+        self.bg_fifo.clear();
+        self.sprite_fifo.clear();
+        self.bg_fetcher.reset();
+        self.sprite_fetcher.reset();
+
+        self.fifo_scx_skipped = 0;
+        self.fifo_wx_skipped = 0;
+        self.fifo_current_x = 0;
+        self.fifo_wy_ly_equal = false;
+        self.fifo_sprite_fetch = false;
+        self.fifo_sprite_wait = 0;
+        self.fifo_sprites_fetched = 0;
+        self.fifo_sprite_tile = None;
+
+        self.mode_clock_cycles = 0;
+        self.line_clock_cycles = 0;
+        self.frame_clock_cycles = 0;
+        self.wy_ly_equality_latch = false;
+        self.ly_153_early = false;
+
+        // the same as the lcd being off, the next tick with it on starts line 0
+        self.mode = PpuMode::HBlank;
+        self.reset = true;
+
+        let mut mmu = self.mmu.borrow_mut();
+        mmu.io[0x44] = 0;
+        mmu.io[0x41] &= 0b1111_1100;
+        mmu.lock_oam = false;
+        mmu.lock_vram = false;
+        mmu.oam_scan_row = None;
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.mode as u8);
+        state.write_bytes(&self.frame_buffer);
+
+        state.write_u8(self.fifo_sprite_buffer.len() as u8);
+        for sprite in &self.fifo_sprite_buffer {
+            sprite.save_state(state);
+        }
+        state.write_bool(self.fifo_sprite_buffer_peek.is_some());
+        if let Some(sprite) = &self.fifo_sprite_buffer_peek {
+            sprite.save_state(state);
+        }
+
+        state.write_u8(self.window_internal_line_counter);
+        state.write_u8(self.bg_fifo.len() as u8);
+        for pixel in &self.bg_fifo {
+            state.write_u8(*pixel);
+        }
+        state.write_u8(self.sprite_fifo.len() as u8);
+        for pixel in &self.sprite_fifo {
+            pixel.save_state(state);
+        }
+
+        self.bg_fetcher.save_state(state);
+        self.sprite_fetcher.save_state(state);
+
+        state.write_u8(self.fifo_scx_skipped);
+        state.write_u8(self.fifo_wx_skipped);
+        state.write_bool(self.fifo_wy_ly_equal);
+        state.write_u8(self.fifo_current_x as u8);
+        state.write_bool(self.fifo_sprite_fetch);
+        state.write_u8(self.fifo_sprite_wait);
+        state.write_u8(self.fifo_sprites_fetched);
+        state.write_bool(self.fifo_sprite_tile.is_some());
+        state.write_u16(self.fifo_sprite_tile.unwrap_or(0));
+        state.write_bool(self.reset);
+
+        state.write_u64(self.mode_clock_cycles);
+        state.write_u64(self.line_clock_cycles);
+        state.write_u64(self.frame_clock_cycles);
+
+        state.write_bool(self.wy_ly_equality_latch);
+        state.write_bool(self.draw_flag);
+        state.write_u64(self.frame_count);
+        state.write_bool(self.ly_153_early);
+        state.write_bool(self.power_on_line_0);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.mode = PpuMode::from_u8(state.read_u8() & 3);
+        state.read_bytes(&mut self.frame_buffer);
+
+        self.fifo_sprite_buffer.clear();
+        for _ in 0..state.read_u8() {
+            self.fifo_sprite_buffer.push_back(Sprite::load_state(state));
+        }
+        self.fifo_sprite_buffer_peek = match state.read_bool() {
+            true => Some(Sprite::load_state(state)),
+            false => None
+        };
+
+        self.window_internal_line_counter = state.read_u8();
+        self.bg_fifo.clear();
+        for _ in 0..state.read_u8() {
+            self.bg_fifo.push_back(state.read_u8());
+        }
+        self.sprite_fifo.clear();
+        for _ in 0..state.read_u8() {
+            self.sprite_fifo.push_back(FifoPixel::load_state(state));
+        }
+
+        self.bg_fetcher.load_state(state);
+        self.sprite_fetcher.load_state(state);
+
+        self.fifo_scx_skipped = state.read_u8();
+        self.fifo_wx_skipped = state.read_u8();
+        self.fifo_wy_ly_equal = state.read_bool();
+        self.fifo_current_x = state.read_u8() as usize;
+        self.fifo_sprite_fetch = state.read_bool();
+        self.fifo_sprite_wait = state.read_u8();
+        self.fifo_sprites_fetched = state.read_u8();
+        let has_sprite_tile = state.read_bool();
+        let sprite_tile = state.read_u16();
+        self.fifo_sprite_tile = if has_sprite_tile { Some(sprite_tile) } else { None };
+        self.reset = state.read_bool();
+
+        self.mode_clock_cycles = state.read_u64();
+        self.line_clock_cycles = state.read_u64();
+        self.frame_clock_cycles = state.read_u64();
+
+        self.wy_ly_equality_latch = state.read_bool();
+        self.draw_flag = state.read_bool();
+        self.frame_count = state.read_u64();
+        self.ly_153_early = state.read_bool();
+        self.power_on_line_0 = state.read_bool();
+    }
+
     fn get_scan_line(&self) -> u8 {
         (*self.mmu).borrow().io[0x44]
     }
@@ -194,6 +406,46 @@ impl Ppu {
     }
 
     const STAT_CHANGE_OFFSET: u64 = 4;
+    const MAX_SPRITES_PER_LINE: usize = 10;
+    const DOTS_PER_LINE: u64 = 456;
+    const DOTS_PER_FRAME: u64 = Self::DOTS_PER_LINE * 154;
+    // A turned off lcd shows white, the same as colour 0 in the default palette
+    const LCD_OFF_COLOR: u8 = 255;
+
+    fn finish_frame(&mut self) {
+        if self.frame_blend {
+            for i in 0..self.frame_buffer.len() {
+                let sum = self.frame_buffer[i] as u16 + self.previous_frame[i] as u16;
+                self.blended_frame[i] = (sum / 2) as u8;
+            }
+        }
+
+        self.previous_frame = self.frame_buffer;
+
+        self.draw_flag = true;
+        self.frame_count += 1;
+    }
+
+    pub fn set_frame_blend(&mut self, enabled: bool) {
+        // nothing to blend with until the next frame
+        if enabled && !self.frame_blend {
+            self.blended_frame = self.frame_buffer;
+        }
+
+        self.frame_blend = enabled;
+    }
+
+    pub fn set_sprite_limit(&mut self, limit: Option<u8>) {
+        self.sprite_limit = limit;
+    }
+
+    // The frame to show, blended with the previous one if frame blending is on
+    pub fn get_output_frame(&self) -> &[u8] {
+        match self.frame_blend {
+            true => &self.blended_frame,
+            false => &self.frame_buffer
+        }
+    }
 
     pub fn tick(&mut self) {
         {
@@ -203,16 +455,24 @@ impl Ppu {
                 self.line_clock_cycles = 0;
                 self.frame_clock_cycles = 0;
                 self.mode = PpuMode::HBlank;
-                self.frame_buffer = [220; 160 * 144];
+                self.frame_buffer = [Self::LCD_OFF_COLOR; 160 * 144];
                 self.reset = true;
+                mmu.oam_scan_row = None;
                 mmu.io[0x44] = 0; // set ly to 0
                 mmu.io[0x41] = mmu.io[0x41] & 0b11111100;
-                return;
             }
+        }
 
-            if mmu.io[0x40] & LcdControlFlag::LCDDisplayEnable as u8 == 0 && self.reset {
-                return;
+        // Nothing is drawn and there's no VBlank interrupt with the lcd off, but the blank
+        // frame is still handed out once a frame's worth of cycles so frontends keep going
+        if (*self.mmu).borrow().io[0x40] & LcdControlFlag::LCDDisplayEnable as u8 == 0 && self.reset {
+            self.frame_clock_cycles += 1;
+            if self.frame_clock_cycles == Self::DOTS_PER_FRAME {
+                self.frame_clock_cycles = 0;
+                self.finish_frame();
             }
+
+            return;
         }
 
         if self.reset {
@@ -239,6 +499,7 @@ impl Ppu {
             self.check_ly_eq_lyc();
         }
 
+        self.dot_count += 1;
         self.mode_clock_cycles += 1;
         self.line_clock_cycles += 1;
         self.frame_clock_cycles += 1;
@@ -261,7 +522,7 @@ impl Ppu {
                     mmu.lock_vram = false;
                 }
 
-                if self.line_clock_cycles == 456 {
+                if self.line_clock_cycles == Self::DOTS_PER_LINE {
                     self.power_on_line_0 = false;
                     self.mode_clock_cycles = 0;
                     self.line_clock_cycles = 0;
@@ -275,7 +536,7 @@ impl Ppu {
                         self.mode = PpuMode::VBlank;
 
                         // notify safe draw
-                        self.draw_flag = true;
+                        self.finish_frame();
                     }
                     else {
                         self.mode = PpuMode::OAM;
@@ -301,14 +562,15 @@ impl Ppu {
 
                 let scan_line = self.get_scan_line();
                 
-                // 153 only lasts 4 cycles
+                // 153 only lasts 4 cycles, for the rest of the line LY reads 0 and is
+                // compared against LYC as 0, so LYC=0 matches before the frame starts
                 if scan_line == 153 && self.line_clock_cycles == 4 {
                     self.set_scan_line(0);
                     self.check_ly_eq_lyc();
                     self.ly_153_early = true;
                 }
 
-                if self.line_clock_cycles == 456 {
+                if self.line_clock_cycles == Self::DOTS_PER_LINE {
                     self.line_clock_cycles = 0;
 
                     
@@ -334,6 +596,9 @@ impl Ppu {
             
             // 2
             PpuMode::OAM => {
+                // one row of OAM (2 sprites) is read every machine cycle
+                self.mmu.borrow_mut().oam_scan_row = Some(((self.mode_clock_cycles - 1) / 4) as u8);
+
                 if self.mode_clock_cycles == 1 {
                     // handle wy_ly latch
                     let mut mmu = (*self.mmu).borrow_mut();
@@ -355,55 +620,11 @@ impl Ppu {
                 }
 
                 if self.mode_clock_cycles == 80 {
+                    self.mmu.borrow_mut().oam_scan_row = None;
                     self.fifo_sprite_buffer.clear();
                     self.fifo_sprite_buffer_peek = None;
 
-                    let mut sprites = Vec::new();
-
-                    {
-                        let mmu = (*self.mmu).borrow();
-                        let ldlc_flags = mmu.io[0x40];
-                        let sprite_size = if ldlc_flags & LcdControlFlag::OBJSize as u8 != 0 
-                            {16} else {8};
-                        let scan_line = mmu.io[0x44];
-
-                        let mut i = 0;
-                        while i < 40 && sprites.len() < 10 {
-                            let sprite_addr = (i as usize) * 4;
-                    
-                            let sprite_y = mmu.sprite_table[sprite_addr];
-                            let sprite_x = mmu.sprite_table[sprite_addr + 1];
-                            let tile_num = 
-                                (mmu.sprite_table[sprite_addr + 2] & 
-                                (if sprite_size == 16 { 0xFE } else { 0xFF })) as u16;
-
-                            let flags = mmu.sprite_table[sprite_addr + 3];
-                            let sprite_palette: usize = if flags & (1 << 4) != 0 {1} else {0};
-                            let xflip: bool = flags & (1 << 5) != 0;
-                            let yflip: bool = flags & (1 << 6) != 0;
-                            let belowbg: bool = flags & (1 << 7) != 0;
-
-                            let sprite = Sprite {
-                                x: sprite_x,
-                                y: sprite_y,
-                                tile_num,
-                                sprite_palette,
-                                xflip,
-                                yflip,
-                                belowbg
-                            };
-
-                            let cond1 = sprite_x > 0;
-                            let cond2 = scan_line + 16 >= sprite_y;
-                            let cond3 = scan_line as u16 + 16 < sprite_y as u16 + sprite_size;
-                            
-                            if cond1 && cond2 && cond3 {
-                                sprites.push(sprite);
-                            }
-
-                            i += 1;
-                        }
-                    }
+                    let mut sprites = self.scan_oam();
 
                     // sort sprite by x pos (via a stable sort)
                     sprites.sort_by(|a, b| {
@@ -432,6 +653,9 @@ impl Ppu {
                     self.sprite_fifo.clear();
                     self.fifo_wy_ly_equal = false;
                     self.fifo_sprite_fetch = false;
+                    self.fifo_sprite_wait = 0;
+                    self.fifo_sprites_fetched = 0;
+                    self.fifo_sprite_tile = None;
 
                     self.mode_clock_cycles = 0;
                     self.mode = PpuMode::VRAM;
@@ -461,6 +685,59 @@ impl Ppu {
         self.update_stat_irq_conditions(String::from(""));
     }
 
+    // Mode 2 goes through OAM in order, picking the first 10 sprites that are on this line.
+    // Any more than that aren't drawn and don't add to the length of mode 3, wherever they are.
+    // Lifting the sprite limit draws the rest too.
+    fn scan_oam(&self) -> Vec<Sprite> {
+        let limit = self.sprite_limit.map(|l| l as usize).unwrap_or(40);
+        let mut sprites = Vec::with_capacity(limit);
+
+        let mmu = (*self.mmu).borrow();
+        let ldlc_flags = mmu.io[0x40];
+        let sprite_size = if ldlc_flags & LcdControlFlag::OBJSize as u8 != 0 
+            {16} else {8};
+        let scan_line = mmu.io[0x44];
+
+        let mut i = 0;
+        while i < 40 && sprites.len() < limit {
+            let sprite_addr = (i as usize) * 4;
+    
+            let sprite_y = mmu.sprite_table[sprite_addr];
+            let sprite_x = mmu.sprite_table[sprite_addr + 1];
+            let tile_num = 
+                (mmu.sprite_table[sprite_addr + 2] & 
+                (if sprite_size == 16 { 0xFE } else { 0xFF })) as u16;
+
+            let flags = mmu.sprite_table[sprite_addr + 3];
+            let sprite_palette: usize = if flags & (1 << 4) != 0 {1} else {0};
+            let xflip: bool = flags & (1 << 5) != 0;
+            let yflip: bool = flags & (1 << 6) != 0;
+            let belowbg: bool = flags & (1 << 7) != 0;
+
+            let sprite = Sprite {
+                x: sprite_x,
+                y: sprite_y,
+                tile_num,
+                sprite_palette,
+                xflip,
+                yflip,
+                belowbg,
+                oam_index: i as u8
+            };
+
+            let cond2 = scan_line + 16 >= sprite_y;
+            let cond3 = scan_line as u16 + 16 < sprite_y as u16 + sprite_size;
+            
+            if cond2 && cond3 {
+                sprites.push(sprite);
+            }
+
+            i += 1;
+        }
+
+        sprites
+    }
+
     fn power_on_line_0_tick(&mut self) -> bool {
         match self.line_clock_cycles {
             83 => {
@@ -478,7 +755,7 @@ impl Ppu {
             }
 
 
-            456 => {
+            Self::DOTS_PER_LINE => {
                 self.power_on_line_0 = false;
                 self.mode_clock_cycles = 0;
                 self.line_clock_cycles = 0;
@@ -502,10 +779,17 @@ impl Ppu {
     fn fifo_tick(&mut self) -> bool {
         // sprite fifo handling
         if self.fifo_sprite_fetch {
+            if self.fifo_sprite_wait > 0 {
+                self.fifo_sprite_wait -= 1;
+                return false;
+            }
+
+            let oam_index_priority = (*self.mmu).borrow().oam_index_priority();
             let sprite = self.fifo_sprite_buffer_peek.as_ref().unwrap();
-            self.sprite_fetcher.tick(&mut self.sprite_fifo, &sprite);
+            self.sprite_fetcher.tick(&mut self.sprite_fifo, sprite, oam_index_priority);
             if self.sprite_fetcher.cycle == 6 {
                 self.fifo_sprite_fetch = false;
+                self.fifo_sprites_fetched += 1;
                 self.fifo_sprite_buffer_peek = self.fifo_sprite_buffer.pop_front();
             } else {
                 return false;
@@ -518,6 +802,24 @@ impl Ppu {
         let scan_line = mmu.io[0x44];
         let scroll_x: u8 = mmu.io[0x43];
 
+        // Sprites past the hardware limit are fetched all at once, so lifting
+        // the limit never makes mode 3 longer than it could be on a real game boy
+        while ldlc_flags & LcdControlFlag::OBJEnable as u8 != 0
+            && self.fifo_sprites_fetched as usize >= Self::MAX_SPRITES_PER_LINE
+            && self.fifo_sprite_buffer_peek.is_some()
+        {
+            let sprite = self.fifo_sprite_buffer_peek.as_ref().unwrap();
+            if sprite.x as usize > self.fifo_current_x + 8 { break }
+
+            self.sprite_fetcher.cycle = 0;
+            while self.sprite_fetcher.cycle < 6 {
+                self.sprite_fetcher.tick(&mut self.sprite_fifo, sprite, mmu.oam_index_priority());
+            }
+
+            self.fifo_sprites_fetched += 1;
+            self.fifo_sprite_buffer_peek = self.fifo_sprite_buffer.pop_front();
+        }
+
         // check sprite
         if ldlc_flags & LcdControlFlag::OBJEnable as u8 != 0  && self.fifo_sprite_buffer_peek.is_some() {
             let candidate_sprite = self.fifo_sprite_buffer_peek.as_ref().unwrap();
@@ -525,7 +827,17 @@ impl Ppu {
             // needs to be a while??
             // many sprites can be on the same x pos
             if candidate_sprite.x as usize <= self.fifo_current_x + 8 {
-                self.bg_fetcher.cycle = 1; // reset bg fetcher
+                // https://gbdev.io/pandocs/Rendering.html#obj-penalty-algorithm
+                // The first sprite in a bg tile has to wait for that tile to be fetched,
+                // the closer the sprite is to the right edge of the tile the less it waits.
+                let tile = (candidate_sprite.x as u16 + scroll_x as u16) / 8;
+                self.fifo_sprite_wait = 0;
+
+                if self.fifo_sprite_tile != Some(tile) {
+                    let tile_offset = candidate_sprite.x.wrapping_add(scroll_x) & 7;
+                    self.fifo_sprite_wait = 5 - tile_offset.min(5);
+                    self.fifo_sprite_tile = Some(tile);
+                }
 
                 // pause pixel pushing and bg fetcher
                 // and start sprite fetcher
@@ -584,12 +896,15 @@ impl Ppu {
             color_bit = 0;
         }
 
+        // the palettes are looked up as each pixel is pushed, so games that change
+        // BGP part way through a frame or a line get the change from that pixel on
         let mut color = mmu.bg_palette[color_bit as usize];
 
         let sprite_pixel = self.sprite_fifo.pop_front();
         if sprite_pixel.is_some() {
             let sprite_pixel = sprite_pixel.unwrap();
 
+            // colour 0 is transparent whatever OBP maps it to, so this is checked before the palette
             let skip = (sprite_pixel.belowbg && color_bit != 0) || sprite_pixel.sprite_color_bit == 0;
 
             if !skip {
@@ -1,6 +1,6 @@
 use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
-use crate::gameboy::mmu::Mmu;
+use crate::gameboy::{mmu::Mmu, save_state::{StateReader, StateWriter}};
 
 use super::{FifoPixel, LcdControlFlag, Sprite};
 
@@ -33,7 +33,23 @@ impl SpriteFetcher {
         self.data_high = 0;
     }
 
-    pub fn tick(&mut self, sprite_fifo: &mut VecDeque<FifoPixel>, sprite: &Sprite) {
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.cycle);
+        state.write_u16(self.tile_addr);
+        state.write_u8(self.data_low);
+        state.write_u8(self.data_high);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.cycle = state.read_u8();
+        self.tile_addr = state.read_u16();
+        self.data_low = state.read_u8();
+        self.data_high = state.read_u8();
+    }
+
+    // With oam_index_priority (CGB OPRI) a sprite's pixels also replace the pixels of any sprite
+    // later in OAM, rather than only the transparent ones
+    pub fn tick(&mut self, sprite_fifo: &mut VecDeque<FifoPixel>, sprite: &Sprite, oam_index_priority: bool) {
         self.cycle += 1;
 
         match self.cycle {
@@ -47,18 +63,18 @@ impl SpriteFetcher {
 
                 let tile_y: u16 = if sprite.yflip {
                     let base = if sprite_size == 16 { 30 } else { 14 };
-                    base - ((scan_line - (sprite.y - 16)) as u16 * 2)
+                    base - (scan_line.wrapping_sub(sprite.y.wrapping_sub(16))) as u16 * 2
                 } else {
                     (scan_line.wrapping_sub(sprite.y.wrapping_sub(16))) as u16 * 2
                 };
 
                 self.tile_addr = 0x8000 + (sprite.tile_num * 16) + tile_y;
-                self.data_low = mmu.gpu_vram[(self.tile_addr - 0x8000) as usize];
+                self.data_low = mmu.tile_data()[(self.tile_addr - 0x8000) as usize];
             }
 
             4 => {
                 let mmu = (*self.mmu).borrow();
-                self.data_high = mmu.gpu_vram[(self.tile_addr + 1 - 0x8000) as usize];
+                self.data_high = mmu.tile_data()[(self.tile_addr + 1 - 0x8000) as usize];
             }
             
 
@@ -93,11 +109,15 @@ impl SpriteFetcher {
                     let px_data = FifoPixel {
                         belowbg: sprite.belowbg,
                         sprite_color_bit: colnr,
-                        sprite_palette: sprite.sprite_palette 
+                        sprite_palette: sprite.sprite_palette,
+                        oam_index: sprite.oam_index
                     };
 
                     if (x as usize) < fifo_len {
-                        if fifo_buffer[x as usize].sprite_color_bit == 0 {
+                        let existing = &fifo_buffer[x as usize];
+                        let higher_priority = oam_index_priority && colnr != 0 && sprite.oam_index < existing.oam_index;
+
+                        if existing.sprite_color_bit == 0 || higher_priority {
                             fifo_buffer[x as usize] = px_data;
                         }
                     } else {
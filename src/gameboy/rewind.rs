@@ -0,0 +1,135 @@
+use std::{collections::VecDeque, convert::TryInto};
+
+// A snapshot is taken every few frames rather than every frame to keep the
+// memory used by the buffer down, rewinding jumps back a few frames at a time.
+const REWIND_FRAMES_PER_SNAPSHOT: u32 = 4;
+
+// Only the newest snapshot is kept whole. Each older one is kept as the bytes that changed
+// going back from the snapshot after it, which for a few frames is usually a small part of the
+// state, mostly the frame buffer and whatever the game touched in RAM.
+pub struct Rewind {
+    enabled: bool,
+    max_snapshots: usize,
+
+    newest: Option<Vec<u8>>,
+    // oldest at the front, the back one goes back from newest
+    deltas: VecDeque<Vec<u8>>,
+    frames_since_snapshot: u32,
+    pub snapshot_pending: bool
+}
+
+impl Rewind {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            max_snapshots: 0,
+
+            newest: None,
+            deltas: VecDeque::new(),
+            frames_since_snapshot: 0,
+            snapshot_pending: false
+        }
+    }
+
+    // Turning it off, or changing how far back it goes, throws away what's been kept so far
+    pub fn set_enabled(&mut self, enabled: bool, seconds: u32) {
+        self.clear();
+        self.enabled = enabled;
+        self.max_snapshots = (60 * seconds / REWIND_FRAMES_PER_SNAPSHOT) as usize;
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.frames_since_snapshot = 0;
+        self.snapshot_pending = false;
+    }
+
+    // Called once a frame, flags that a snapshot should be taken at the start of the next tick.
+    pub fn frame(&mut self) {
+        if !self.enabled || self.max_snapshots == 0 { return }
+
+        self.frames_since_snapshot += 1;
+
+        if self.frames_since_snapshot >= REWIND_FRAMES_PER_SNAPSHOT {
+            self.frames_since_snapshot = 0;
+            self.snapshot_pending = true;
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if let Some(newest) = self.newest.take() {
+            self.deltas.push_back(diff(&snapshot, &newest));
+
+            if self.deltas.len() >= self.max_snapshots {
+                self.deltas.pop_front();
+            }
+        }
+
+        self.newest = Some(snapshot);
+        self.snapshot_pending = false;
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.frames_since_snapshot = 0;
+        self.snapshot_pending = false;
+
+        let snapshot = self.newest.take()?;
+        self.newest = self.deltas.pop_back().map(|delta| patch(&snapshot, &delta));
+
+        Some(snapshot)
+    }
+}
+
+// What turns `from` into `to`: to's length, then runs of (bytes to skip: u16, bytes changed: u16,
+// the changed bytes). States can change length, past the end of the shorter one counts as 0s.
+fn diff(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let byte = |data: &[u8], i: usize| data.get(i).copied().unwrap_or(0);
+    let len = from.len().max(to.len());
+
+    let mut delta = Vec::new();
+    delta.extend_from_slice(&(to.len() as u32).to_le_bytes());
+
+    let mut i = 0;
+    while i < len {
+        let skip_start = i;
+        while i < len && i - skip_start < 0xFFFF && byte(from, i) == byte(to, i) {
+            i += 1;
+        }
+
+        let changed_start = i;
+        while i < len && i - changed_start < 0xFFFF && byte(from, i) != byte(to, i) {
+            i += 1;
+        }
+
+        delta.extend_from_slice(&((changed_start - skip_start) as u16).to_le_bytes());
+        delta.extend_from_slice(&((i - changed_start) as u16).to_le_bytes());
+        delta.extend((changed_start..i).map(|j| byte(to, j)));
+    }
+
+    delta
+}
+
+fn patch(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let read_u16 = |i: usize| u16::from_le_bytes(delta[i..i + 2].try_into().unwrap()) as usize;
+    let len = u32::from_le_bytes(delta[0..4].try_into().unwrap()) as usize;
+
+    let mut to = from.to_vec();
+    to.resize(from.len().max(len), 0);
+
+    let mut i = 4;
+    let mut pos = 0;
+    while i < delta.len() {
+        let skip = read_u16(i);
+        let changed = read_u16(i + 2);
+        i += 4;
+        pos += skip;
+
+        to[pos..pos + changed].copy_from_slice(&delta[i..i + changed]);
+        i += changed;
+        pos += changed;
+    }
+
+    to.truncate(len);
+    to
+}
@@ -0,0 +1,72 @@
+use std::{error, fmt, fs::File, io::{self, Read}, path::Path};
+
+use zip::{ZipArchive, result::ZipError};
+
+use super::error::Error;
+
+#[derive(Debug)]
+pub enum ZipRomError {
+    Io(io::Error),
+    Zip(ZipError),
+    NoRomEntry,
+    // The ROM was found but couldn't be loaded
+    Rom(Error)
+}
+
+impl fmt::Display for ZipRomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZipRomError::Io(err) => write!(f, "Unable to read the zip file: {}", err),
+            ZipRomError::Zip(err) => write!(f, "Invalid zip file: {}", err),
+            ZipRomError::NoRomEntry => write!(f, "No .gb or .gbc ROM found in the zip file"),
+            ZipRomError::Rom(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl error::Error for ZipRomError {}
+
+impl From<io::Error> for ZipRomError {
+    fn from(err: io::Error) -> Self {
+        ZipRomError::Io(err)
+    }
+}
+
+impl From<ZipError> for ZipRomError {
+    fn from(err: ZipError) -> Self {
+        ZipRomError::Zip(err)
+    }
+}
+
+// Reads the named entry, or the first .gb/.gbc entry if no name is given
+pub fn read_rom(zip_path: &Path, entry: Option<&str>) -> Result<Vec<u8>, ZipRomError> {
+    let mut archive = ZipArchive::new(File::open(zip_path)?)?;
+
+    let mut rom_index = None;
+    for i in 0..archive.len() {
+        let name = archive.by_index(i)?.name().to_owned();
+
+        let is_rom = match entry {
+            Some(entry) => name == entry,
+            None => {
+                let name = name.to_lowercase();
+                name.ends_with(".gb") || name.ends_with(".gbc")
+            }
+        };
+
+        if is_rom {
+            rom_index = Some(i);
+            break;
+        }
+    }
+
+    let rom_index = match rom_index {
+        Some(i) => i,
+        None => return Err(ZipRomError::NoRomEntry)
+    };
+
+    let mut rom = Vec::new();
+    archive.by_index(rom_index)?.read_to_end(&mut rom)?;
+
+    Ok(rom)
+}
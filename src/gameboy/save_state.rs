@@ -0,0 +1,240 @@
+// Save states are a flat little endian stream of bytes.
+// Every component writes its fields in order and then reads them back
+// in the same order, so a component's save_state and load_state need to be kept in sync.
+
+use std::{error, fmt, io, path::{Path, PathBuf}};
+
+// Slot files have a StateMeta in front of the machine state, so they get their own magic
+// to keep them from being loaded as plain states, and the other way round
+const MAGIC: [u8; 4] = *b"GBRS";
+const SLOT_MAGIC: [u8; 4] = *b"GBSL";
+// Goes up whenever what gets written changes, older states can't be loaded
+const VERSION: u8 = 2;
+
+// magic + version + length of the data after the header
+const HEADER_LENGTH: usize = 4 + 1 + 4;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StateError {
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    InvalidLength,
+
+    // For the slots
+    NoStateDirectory,
+    WrongRom,
+    Io(io::ErrorKind)
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidHeader => 
+                write!(f, "Not a save state, the header is missing"),
+            StateError::UnsupportedVersion(version) => 
+                write!(f, "Save state version {} isn't supported, expected version {}", version, VERSION),
+            StateError::InvalidLength => 
+                write!(f, "Save state is the wrong length, it's been cut short or added to"),
+            StateError::NoStateDirectory => 
+                write!(f, "No directory has been set for the save state slots"),
+            StateError::WrongRom => 
+                write!(f, "The save state is for a different ROM"),
+            StateError::Io(kind) => 
+                write!(f, "Unable to read or write the save state slot: {:?}", kind)
+        }
+    }
+}
+
+impl error::Error for StateError {}
+
+impl From<io::Error> for StateError {
+    fn from(err: io::Error) -> Self {
+        StateError::Io(err.kind())
+    }
+}
+
+// What's written in front of the machine state in a slot's file,
+// enough to list the slots without loading any of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateMeta {
+    pub slot: u8,
+    // See rom_hash
+    pub rom_hash: u64,
+    // Seconds since the unix epoch
+    pub timestamp: u64,
+    // Frames the ppu had drawn since power on
+    pub frame_count: u64
+}
+
+impl StateMeta {
+    pub fn write(&self, state: &mut StateWriter) {
+        state.write_u8(self.slot);
+        state.write_u64(self.rom_hash);
+        state.write_u64(self.timestamp);
+        state.write_u64(self.frame_count);
+    }
+
+    pub fn read(state: &mut StateReader) -> Self {
+        Self {
+            slot: state.read_u8(),
+            rom_hash: state.read_u64(),
+            timestamp: state.read_u64(),
+            frame_count: state.read_u64()
+        }
+    }
+}
+
+// FNV-1a of the title from the cartridge header. It needs to come out the same
+// on every build, so it can't use std's hasher.
+pub fn rom_hash(title: &str) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+
+    for byte in title.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+
+    hash
+}
+
+pub fn slot_path(dir: &Path, slot: u8) -> PathBuf {
+    dir.join(format!("slot{}.state", slot))
+}
+
+pub struct StateWriter {
+    data: Vec<u8>
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::with_magic(MAGIC)
+    }
+
+    // For a slot file, see SLOT_MAGIC
+    pub fn new_slot() -> Self {
+        Self::with_magic(SLOT_MAGIC)
+    }
+
+    fn with_magic(magic: [u8; 4]) -> Self {
+        let mut data = Vec::with_capacity(0x10000);
+        data.extend_from_slice(&magic);
+        data.push(VERSION);
+        data.extend_from_slice(&[0; 4]); // length, filled in when finished
+
+        Self {
+            data
+        }
+    }
+
+    pub fn write_u8(&mut self, val: u8) {
+        self.data.push(val);
+    }
+
+    pub fn write_bool(&mut self, val: bool) {
+        self.data.push(val as u8);
+    }
+
+    pub fn write_u16(&mut self, val: u16) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, val: u64) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let length = (self.data.len() - HEADER_LENGTH) as u32;
+        self.data[5..HEADER_LENGTH].copy_from_slice(&length.to_le_bytes());
+        self.data
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> StateReader<'a> {
+    // Checks the header before anything is read, so a bad state gets
+    // rejected before any of the machine has been overwritten.
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        Self::with_magic(data, MAGIC)
+    }
+
+    // For a slot file, see SLOT_MAGIC
+    pub fn new_slot(data: &'a [u8]) -> Result<Self, StateError> {
+        Self::with_magic(data, SLOT_MAGIC)
+    }
+
+    fn with_magic(data: &'a [u8], magic: [u8; 4]) -> Result<Self, StateError> {
+        if data.len() < HEADER_LENGTH || data[0..4] != magic {
+            return Err(StateError::InvalidHeader);
+        }
+
+        if data[4] != VERSION {
+            return Err(StateError::UnsupportedVersion(data[4]));
+        }
+
+        let mut length = [0; 4];
+        length.copy_from_slice(&data[5..HEADER_LENGTH]);
+        if u32::from_le_bytes(length) as usize != data.len() - HEADER_LENGTH {
+            return Err(StateError::InvalidLength);
+        }
+
+        Ok(Self {
+            data,
+            pos: HEADER_LENGTH
+        })
+    }
+
+    // Reading past the end gives back zeroes rather than panicking
+    pub fn read_bytes(&mut self, bytes: &mut [u8]) {
+        for byte in bytes.iter_mut() {
+            *byte = self.read_u8();
+        }
+    }
+
+    pub fn read_u8(&mut self) -> u8 {
+        if self.pos >= self.data.len() { return 0 }
+
+        let val = self.data[self.pos];
+        self.pos += 1;
+        val
+    }
+
+    pub fn read_bool(&mut self) -> bool {
+        self.read_u8() != 0
+    }
+
+    pub fn read_u16(&mut self) -> u16 {
+        let mut bytes = [0; 2];
+        self.read_bytes(&mut bytes);
+        u16::from_le_bytes(bytes)
+    }
+
+    pub fn read_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.read_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn read_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.read_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+}
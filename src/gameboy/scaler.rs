@@ -0,0 +1,26 @@
+use std::iter;
+
+// Nearest neighbour upscaling for frontends that draw at 2x, 3x etc. Every pixel becomes a
+// factor x factor block. Works on one byte per pixel, like the frame buffer.
+pub fn scale_nearest(pixels: &[u8], width: usize, height: usize, factor: u8) -> Vec<u8> {
+    assert!(factor > 0, "Can't scale by 0");
+    assert_eq!(pixels.len(), width * height);
+
+    if factor == 1 { return pixels.to_vec() }
+
+    let factor = factor as usize;
+    let mut scaled = Vec::with_capacity(pixels.len() * factor * factor);
+
+    for row in pixels.chunks(width) {
+        let mut line = Vec::with_capacity(width * factor);
+        for &pixel in row {
+            line.extend(iter::repeat_n(pixel, factor));
+        }
+
+        for _ in 0..factor {
+            scaled.extend_from_slice(&line);
+        }
+    }
+
+    scaled
+}
@@ -0,0 +1,269 @@
+use std::{sync::mpsc::{self, Receiver, Sender}, time::{Duration, Instant}};
+
+use super::save_state::{StateReader, StateWriter};
+
+// https://gbdev.io/pandocs/Serial_Data_Transfer_(Link_Cable).html
+// The side using its internal clock drives the transfer, 8 bits at 8192Hz.
+// The side using an external clock only shifts when the other side clocks it.
+// The internal clock shifts a bit on every falling edge of bit 8 of the full DIV counter.
+const CLOCK_DIV_BIT: u16 = 8;
+
+// How often the cable gets checked for transfers started by the other side
+const POLL_CYCLES: u16 = 64;
+
+// How long the clocking side keeps its transfer going waiting for the other side's byte,
+// before treating the cable as unplugged
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+// What arrives from the other end of the cable. Replies carry the id of the transfer they answer,
+// so one that turns up after its transfer gave up waiting isn't taken as the answer to the next.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LinkMessage {
+    // The other side started a transfer with its internal clock, sending this byte
+    Transfer { id: u8, val: u8 },
+    // The other side's byte, in answer to our transfer
+    Reply { id: u8, val: u8 }
+}
+
+pub trait LinkCable {
+    // Returns false if the other side has gone
+    fn send(&mut self, message: LinkMessage) -> bool;
+
+    // A message from the other side if there is one. Never waits for one to arrive.
+    fn receive(&mut self) -> Option<LinkMessage>;
+}
+
+// Links two emulators in the same process, which need to be running on separate threads
+pub struct ChannelCable {
+    sender: Sender<LinkMessage>,
+    receiver: Receiver<LinkMessage>
+}
+
+impl ChannelCable {
+    pub fn pair() -> (Self, Self) {
+        let (sender_a, receiver_b) = mpsc::channel();
+        let (sender_b, receiver_a) = mpsc::channel();
+
+        let a = Self {
+            sender: sender_a,
+            receiver: receiver_a
+        };
+
+        let b = Self {
+            sender: sender_b,
+            receiver: receiver_b
+        };
+
+        (a, b)
+    }
+}
+
+impl LinkCable for ChannelCable {
+    fn send(&mut self, message: LinkMessage) -> bool {
+        self.sender.send(message).is_ok()
+    }
+
+    fn receive(&mut self) -> Option<LinkMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+
+    bits_shifted: u8,
+    prev_clock_bit: bool,
+    poll_cycles: u16,
+
+    // The byte sent by the last transfer this side started, until it's taken
+    sent: Option<u8>,
+    // Counts the transfers this side has started
+    transfer_id: u8,
+    // Once all 8 bits are out, when to stop waiting for the other side's byte. Not saved,
+    // a state loaded part way through a transfer gives up on the reply straight away.
+    reply_deadline: Option<Instant>,
+
+    cable: Option<Box<dyn LinkCable>>
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self {
+            sb: 0,
+            sc: 0,
+
+            bits_shifted: 0,
+            prev_clock_bit: false,
+            poll_cycles: 0,
+
+            sent: None,
+            transfer_id: 0,
+            reply_deadline: None,
+
+            cable: None
+        }
+    }
+
+    pub fn connect(&mut self, cable: Box<dyn LinkCable>) {
+        self.cable = Some(cable);
+    }
+
+    pub fn disconnect(&mut self) {
+        self.cable = None;
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.sb);
+        state.write_u8(self.sc);
+        state.write_u8(self.bits_shifted);
+        state.write_bool(self.prev_clock_bit);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.sb = state.read_u8();
+        self.sc = state.read_u8();
+        self.bits_shifted = state.read_u8();
+        self.prev_clock_bit = state.read_bool();
+    }
+
+    fn is_transferring(&self) -> bool {
+        self.sc & 0b1000_0000 != 0
+    }
+
+    fn is_internal_clock(&self) -> bool {
+        self.sc & 0b0000_0001 != 0
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF01 => self.sb,
+            0xFF02 => self.sc | 0b0111_1110,
+
+            _ => unreachable!()
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0xFF01 => self.sb = val,
+            0xFF02 => {
+                self.sc = val & 0b1000_0001;
+
+                if self.is_transferring() && self.is_internal_clock() {
+                    self.bits_shifted = 0;
+                    self.sent = Some(self.sb);
+                    self.transfer_id = self.transfer_id.wrapping_add(1);
+                    self.reply_deadline = None;
+
+                    if let Some(cable) = self.cable.as_mut() {
+                        let sent = cable.send(LinkMessage::Transfer { id: self.transfer_id, val: self.sb });
+                        if !sent { self.cable = None; }
+                    }
+                }
+            }
+
+            _ => unreachable!()
+        }
+    }
+
+    // For watching what a game sends, whether or not anything is connected
+    pub fn take_sent_byte(&mut self) -> Option<u8> {
+        self.sent.take()
+    }
+
+    // Takes the timer's full DIV counter.
+    // Returns true if a transfer finished and the serial interrupt should be requested
+    pub fn tick(&mut self, div: u16) -> bool {
+        let clock_bit = (div >> CLOCK_DIV_BIT) & 1 == 1;
+        let falling_edge = self.prev_clock_bit && !clock_bit;
+        self.prev_clock_bit = clock_bit;
+
+        if self.is_transferring() && self.is_internal_clock() {
+            if self.bits_shifted == 8 {
+                return self.poll_reply();
+            }
+
+            if !falling_edge { return false }
+
+            self.bits_shifted += 1;
+            if self.bits_shifted == 8 {
+                self.reply_deadline = Some(Instant::now() + REPLY_TIMEOUT);
+                return self.poll_reply();
+            }
+
+            return false;
+        }
+
+        self.poll_cycles += 1;
+        if self.poll_cycles < POLL_CYCLES { return false }
+        self.poll_cycles = 0;
+
+        self.poll_cable()
+    }
+
+    // Checked every tick once all 8 bits are out, the transfer keeps going until the other side's
+    // byte arrives so the emulator never has to wait on it.
+    // Returns true once the transfer has finished.
+    fn poll_reply(&mut self) -> bool {
+        let val = match self.receive_reply() {
+            Some(val) => val,
+            None => return false
+        };
+
+        self.sb = val;
+        self.sc &= 0b0111_1111;
+        self.reply_deadline = None;
+        true
+    }
+
+    // With nothing connected the bits shifted in are all 1s
+    fn receive_reply(&mut self) -> Option<u8> {
+        let cable = match self.cable.as_mut() {
+            Some(cable) => cable,
+            None => return Some(0xFF)
+        };
+
+        while let Some(message) = cable.receive() {
+            match message {
+                LinkMessage::Reply { id, val } if id == self.transfer_id => return Some(val),
+
+                // the answer to a transfer that gave up waiting for it
+                LinkMessage::Reply { .. } => {}
+
+                // both sides are clocking, nothing gets shifted in on this side
+                LinkMessage::Transfer { id, .. } => {
+                    cable.send(LinkMessage::Reply { id, val: 0xFF });
+                }
+            }
+        }
+
+        match self.reply_deadline {
+            Some(deadline) if Instant::now() < deadline => None,
+            _ => Some(0xFF)
+        }
+    }
+
+    // Replies that turn up here are for transfers that already gave up on them
+    fn poll_cable(&mut self) -> bool {
+        if self.cable.is_none() { return false }
+
+        let is_transferring = self.is_transferring();
+        let cable = self.cable.as_mut().unwrap();
+        let (id, val) = match cable.receive() {
+            Some(LinkMessage::Transfer { id, val }) => (id, val),
+            _ => return false
+        };
+
+        // the other side still clocks its bits through, this side just isn't listening
+        if !is_transferring {
+            cable.send(LinkMessage::Reply { id, val: 0xFF });
+            return false;
+        }
+
+        cable.send(LinkMessage::Reply { id, val: self.sb });
+        self.sb = val;
+        self.sc &= 0b0111_1111;
+        true
+    }
+}
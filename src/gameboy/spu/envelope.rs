@@ -1,3 +1,5 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
 use super::MAX_VOLUME;
 
 
@@ -5,7 +7,7 @@ use super::MAX_VOLUME;
 pub struct Envelope {
     pub volume: u8,
     direction: EnvelopeDirection,
-    step_duration: u32,
+    period: u8,
     counter: u32
 }
 
@@ -17,21 +19,22 @@ impl Envelope {
         } else { 
             EnvelopeDirection::Decrease 
         };
-        let length = (val & 7) as u32;
+        let period = val & 7;
 
         Self {
             volume,
             direction,
-            step_duration: length * 0x10000,
+            period,
             counter: 0
         }
     }
 
+    // Clocked by the frame sequencer at 64Hz
     pub fn tick(&mut self) {
-        if self.step_duration == 0 { return }
+        if self.period == 0 { return }
 
         self.counter += 1;
-        self.counter %= self.step_duration;
+        self.counter %= self.period as u32;
 
         if self.counter == 0 {
             match self.direction {
@@ -54,14 +57,24 @@ impl Envelope {
     }
 
     pub fn into_u8(&self) -> u8 {
-        let length = (self.step_duration / 0x10000) as u8;
-
-        (self.volume << 4) | ((self.direction as u8) << 3) | length
+        (self.volume << 4) | ((self.direction as u8) << 3) | self.period
     }
 
     pub fn enabled(&self) -> bool {
         self.direction != EnvelopeDirection::Decrease || self.volume != 0
     }
+
+    // the register value holds everything apart from the counter
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.into_u8());
+        state.write_u32(self.counter);
+    }
+
+    pub fn load_state(state: &mut StateReader) -> Self {
+        let mut envelope = Self::new(state.read_u8());
+        envelope.counter = state.read_u32();
+        envelope
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
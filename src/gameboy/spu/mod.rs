@@ -1,19 +1,29 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
 use sdl2::audio::AudioQueue;
 
-use self::{envelope::Envelope, sampled_wave::SampledWave, square_wave::{Duty, SquareWave, Sweep}, white_noise_wave::{WhiteNoiseGenerator, WhiteNoiseWave}};
+use super::save_state::{StateReader, StateWriter};
+
+#[cfg(feature = "hound")]
+use self::wav_recorder::WavRecorder;
+use self::{envelope::Envelope, sampled_wave::SampledWave, square_wave::{Duty, SquareWave, Sweep}, white_noise_wave::WhiteNoiseWave};
 
 mod white_noise_wave;
 mod sampled_wave;
 mod square_wave;
 mod envelope;
+#[cfg(feature = "hound")]
+pub mod wav_recorder;
 
 pub const MAX_VOLUME: Sample = (1 << 4) - 1;
 pub const MAX_SAMPLE: Sample = MAX_VOLUME * 4 * 2; // 4 PCM streams, 2 channels 
 pub const SAMPLES_PER_BUFFER: usize = 1024;
 pub const SAMPLE_RATE: u32 = 48000;
 pub const CLOCKS_PER_SAMPLE: u64 = 87;
+// The master clock the spu is ticked at
+pub const CLOCK_RATE: u64 = 4_194_304;
+// One second of interleaved left/right samples
+pub const DEFAULT_AUDIO_BUFFER_CAPACITY: usize = SAMPLE_RATE as usize * 2;
 
 pub type Sample = u8;
 pub type SampleBuffer = [Sample; SAMPLES_PER_BUFFER];
@@ -24,6 +34,7 @@ pub struct Spu {
     buffer_pos: usize,
 
     enabled: bool,
+    frame_sequencer_step: u8,
 
     channel_1: SquareWave,
     channel_2: SquareWave,
@@ -32,7 +43,21 @@ pub struct Spu {
 
     mixer: Mixer,
 
-    device: Option<Rc<RefCell<AudioQueue<f32>>>>
+    device: Option<Rc<RefCell<AudioQueue<f32>>>>,
+    // Without a device the samples wait here until the frontend drains them
+    queued_samples: VecDeque<f32>,
+    audio_buffer_capacity: usize,
+
+    // The channels keep running but nothing is sent out
+    muted: bool,
+
+    // Samples for step_frame_av, taken at exactly SAMPLE_RATE against the master clock
+    // rather than every CLOCKS_PER_SAMPLE. Only for recordings, so not saved.
+    av_capture: Option<Vec<(i16, i16)>>,
+    av_clock: u64,
+
+    #[cfg(feature = "hound")]
+    recorder: Option<WavRecorder>
 }
 
 impl Spu {
@@ -43,6 +68,7 @@ impl Spu {
             buffer_pos: 0,
 
             enabled: false,
+            frame_sequencer_step: 0,
 
             channel_1: SquareWave::new(),
             channel_2: SquareWave::new(),
@@ -51,11 +77,25 @@ impl Spu {
 
             mixer: Mixer::new(),
 
-            device
+            device,
+            queued_samples: VecDeque::new(),
+            audio_buffer_capacity: DEFAULT_AUDIO_BUFFER_CAPACITY,
+
+            muted: false,
+
+            av_capture: None,
+            av_clock: 0,
+
+            #[cfg(feature = "hound")]
+            recorder: None
         }
     }
 
     pub fn tick(&mut self) {
+        if self.av_capture.is_some() {
+            self.capture_av_sample();
+        }
+
         if !self.enabled { return }
 
         self.channel_1.tick();
@@ -71,6 +111,33 @@ impl Spu {
         self.sample_clock -= 1;
     }
 
+    // Clocked on the falling edge of DIV bit 4, which happens at 512Hz.
+    // Lengths are clocked on every other step, the sweep on steps 2 and 6
+    // and the envelopes on step 7.
+    pub fn clock_frame_sequencer(&mut self) {
+        if !self.enabled { return }
+
+        let step = self.frame_sequencer_step;
+        self.frame_sequencer_step = (step + 1) % 8;
+
+        if step.is_multiple_of(2) {
+            self.channel_1.clock_length();
+            self.channel_2.clock_length();
+            self.channel_3.clock_length();
+            self.channel_4.clock_length();
+        }
+
+        if step == 2 || step == 6 {
+            self.channel_1.clock_sweep();
+        }
+
+        if step == 7 {
+            self.channel_1.clock_envelope();
+            self.channel_2.clock_envelope();
+            self.channel_4.clock_envelope();
+        }
+    }
+
     pub fn sample(&mut self) {
         let (left_sample, right_sample) = self.mixer.sample_and_mix(
             &self.channel_1, 
@@ -90,15 +157,151 @@ impl Spu {
         }
     }
 
+    // Goes up by SAMPLE_RATE every cycle, so the same number of cycles always gives the
+    // same number of samples. The sound being off is captured as silence.
+    fn capture_av_sample(&mut self) {
+        self.av_clock += SAMPLE_RATE as u64;
+        if self.av_clock < CLOCK_RATE { return }
+        self.av_clock -= CLOCK_RATE;
+
+        let (left, right) = match self.enabled {
+            true => self.mixer.sample_and_mix(
+                &self.channel_1,
+                &self.channel_2,
+                &self.channel_3,
+                &self.channel_4
+            ),
+            false => (0.0, 0.0)
+        };
+
+        // the same level the audio device gets
+        let to_i16 = |sample: f32| ((sample / 6.0).clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        self.av_capture.as_mut().unwrap().push((to_i16(left), to_i16(right)));
+    }
+
+    pub fn start_av_capture(&mut self) {
+        self.av_capture = Some(Vec::new());
+    }
+
+    // The left/right pairs since start_av_capture
+    pub fn finish_av_capture(&mut self) -> Vec<(i16, i16)> {
+        self.av_capture.take().unwrap_or_default()
+    }
+
     pub fn send_sample(&mut self) {
+        if self.muted { return }
+
         let mut buffer = [0.0; SAMPLES_PER_BUFFER];
 
         for i in 0..SAMPLES_PER_BUFFER {
             buffer[i] = self.buffer[i] / 6.0;
         }
 
-        if self.device.is_some() {
-            (*self.device.as_ref().unwrap()).borrow().queue(&buffer);
+        // recorded before anything gets dropped for a full device
+        #[cfg(feature = "hound")]
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(&buffer);
+        }
+
+        if let Some(device) = &self.device {
+            // the device can't have its oldest samples taken back out, so when
+            // it's full (like when running in turbo) the new ones get dropped instead
+            if self.samples_available() + buffer.len() <= self.audio_buffer_capacity {
+                (*device).borrow().queue(&buffer);
+            }
+        }
+        else {
+            self.queued_samples.extend(buffer.iter());
+            self.drop_oldest_samples();
+        }
+    }
+
+    #[cfg(feature = "hound")]
+    pub fn set_recorder(&mut self, recorder: Option<WavRecorder>) -> Option<WavRecorder> {
+        std::mem::replace(&mut self.recorder, recorder)
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    // Interleaved left/right samples
+    pub fn drain_samples(&mut self) -> Vec<f32> {
+        self.queued_samples.drain(..).collect()
+    }
+
+    // Samples waiting to be played, left and right are counted separately
+    pub fn samples_available(&self) -> usize {
+        if let Some(device) = &self.device {
+            let bytes = (*device).borrow().size() as usize;
+            return bytes / std::mem::size_of::<f32>();
+        }
+
+        self.queued_samples.len()
+    }
+
+    // Silences every channel and throws away the samples waiting to be played, to get rid of a
+    // stuck note. Unlike turning the sound off with NR52 it stays on, and NR50, NR51 and wave ram are kept.
+    pub fn restart(&mut self) {
+        self.frame_sequencer_step = 0;
+        self.channel_1 = SquareWave::new();
+        self.channel_2 = SquareWave::new();
+        self.channel_3 = SampledWave::new(Some(self.channel_3.samples));
+        self.channel_4 = WhiteNoiseWave::new();
+
+        self.sample_clock = CLOCKS_PER_SAMPLE;
+        self.buffer_pos = 0;
+        self.queued_samples.clear();
+
+        if let Some(device) = &self.device {
+            (*device).borrow().clear();
+        }
+    }
+
+    // What the boot rom leaves the registers as, for starting without it.
+    // https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+    // Channel 1 played the boot sound and is left on, but its envelope has run down to 0
+    // so it's silent. The other channels' DACs are off, so their triggers do nothing.
+    pub fn skip_boot_rom(&mut self) {
+        self.set_nr52(0x80);
+
+        self.set_nr10(0x80);
+        self.set_nr11(0xBF);
+        self.set_nr12(0xF3);
+        self.set_nr13(0xC1);
+        self.set_nr14(0x87);
+        self.channel_1.envelope.volume = 0;
+
+        self.set_nr21(0x3F);
+        self.set_nr22(0x00);
+        self.set_nr24(0xBF);
+
+        self.set_nr30(0x7F);
+        self.set_nr31(0xFF);
+        self.set_nr32(0x9F);
+        self.set_nr34(0xBF);
+
+        self.set_nr41(0xFF);
+        self.set_nr42(0x00);
+        self.set_nr43(0x00);
+        self.set_nr44(0xBF);
+
+        self.set_nr50(0x77);
+        self.set_nr51(0xF3);
+    }
+
+    // Rounded down to a whole number of left/right pairs so they stay interleaved
+    pub fn set_audio_buffer_capacity(&mut self, capacity: usize) {
+        self.audio_buffer_capacity = capacity & !1;
+
+        self.drop_oldest_samples();
+    }
+
+    // When the frontend isn't keeping up
+    fn drop_oldest_samples(&mut self) {
+        if self.queued_samples.len() > self.audio_buffer_capacity {
+            let excess = self.queued_samples.len() - self.audio_buffer_capacity;
+            self.queued_samples.drain(..excess);
         }
     }
 
@@ -371,18 +574,25 @@ impl Spu {
         }
     }
 
-    pub fn get_sample(&self, index: u8) -> u8 {
-        let index = (index * 2) as usize;
+    // Reads as 0xFF when the DMG blocks the access, see SampledWave::wave_ram_index
+    pub fn get_sample(&self, index: u8, cgb: bool) -> u8 {
+        let index = match self.channel_3.wave_ram_index(index, cgb) {
+            Some(index) => index * 2,
+            None => return 0xFF
+        };
         let sample_high = self.channel_3.samples[index];
         let sample_low = self.channel_3.samples[index + 1];
 
         (sample_high << 4) | sample_low
     }
 
-    pub fn set_sample(&mut self, index: u8, sample: u8) {
+    pub fn set_sample(&mut self, index: u8, sample: u8, cgb: bool) {
         // uaffected by master enable/disable
 
-        let index = (index * 2) as usize;
+        let index = match self.channel_3.wave_ram_index(index, cgb) {
+            Some(index) => index * 2,
+            None => return
+        };
         let sample_high = (sample >> 4) as Sample;
         let sample_low = (sample & 0b0000_1111) as Sample;
 
@@ -419,7 +629,7 @@ impl Spu {
     pub fn set_nr43(&mut self, val: u8) {
         if !self.enabled { return }
 
-        self.channel_4.white_noise_generator = WhiteNoiseGenerator::new(val);
+        self.channel_4.white_noise_generator.set_val(val);
     }
 
     pub fn get_nr44(&self) -> u8 {
@@ -443,7 +653,39 @@ impl Spu {
         }
     }
 
+    // The sample buffer isn't saved, audio carries on from an empty buffer
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u64(self.sample_clock);
+        state.write_bool(self.enabled);
+        state.write_u8(self.frame_sequencer_step);
+
+        self.channel_1.save_state(state);
+        self.channel_2.save_state(state);
+        self.channel_3.save_state(state);
+        self.channel_4.save_state(state);
+
+        state.write_u8(self.mixer.channel_output_flags);
+        state.write_u8(self.mixer.channel_vol_flags);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.sample_clock = state.read_u64();
+        self.buffer_pos = 0;
+        self.enabled = state.read_bool();
+        self.frame_sequencer_step = state.read_u8() % 8;
+
+        self.channel_1.load_state(state);
+        self.channel_2.load_state(state);
+        self.channel_3.load_state(state);
+        self.channel_4.load_state(state);
+
+        self.mixer.channel_output_flags = state.read_u8();
+        self.mixer.channel_vol_flags = state.read_u8();
+    }
+
+    // Powering the apu back on starts the frame sequencer from step 0
     fn reset(&mut self) {
+        self.frame_sequencer_step = 0;
         self.channel_1 = SquareWave::new();
         self.channel_2 = SquareWave::new();
         self.channel_3 = SampledWave::new(Some(self.channel_3.samples));
@@ -459,6 +701,15 @@ pub enum Mode {
     Counter = 1
 }
 
+impl From<u8> for Mode {
+    fn from(val: u8) -> Self {
+        match val & 1 {
+            0 => Self::Consecutive,
+            _ => Self::Counter
+        }
+    }
+}
+
 pub struct Mixer {
     channel_output_flags: u8,
     channel_vol_flags: u8,
@@ -1,18 +1,25 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
 use super::{Mode, Sample};
 
+// https://gbdev.gg8.se/wiki/articles/Gameboy_sound_hardware#Obscure_Behavior
+// How long after the channel reads wave ram the DMG still lets the cpu at it, one 2MHz apu clock
+const DMG_WAVE_RAM_ACCESS_CYCLES: u8 = 2;
 
 pub(super) struct SampledWave {
     pub enabled: bool,
     running: bool,
     
-    pub remaining: u32,
+    length: u16,
     pub output_level: u8,
     pub frequency: u16,
     cycle: u16,
     pub mode: Mode,
 
     pub samples: [Sample; 32],
-    sample_index: usize
+    sample_index: usize,
+    // Clock cycles since the channel last read a sample from wave ram
+    cycles_since_read: u8
 }
 
 impl SampledWave {
@@ -26,38 +33,43 @@ impl SampledWave {
             enabled: false,
             running: false,
 
-            remaining: 0x100 * 0x4000,
+            length: 0,
             output_level: 0,
             frequency: 0,
             cycle: 0,
             mode: Mode::Consecutive,
             samples,
-            sample_index: 0
+            sample_index: 0,
+            cycles_since_read: DMG_WAVE_RAM_ACCESS_CYCLES
         }
     }
 
     pub fn tick(&mut self) {
-        if self.mode == Mode::Counter {
-            if self.remaining == 0 {
-                self.enabled = false;
-                self.remaining = 0x100 * 0x4000;
-                return;
-            } 
-                
-            self.remaining -= 1;
-        }
-
         if !self.running { return }
 
         if self.cycle == 0 {
             self.cycle = 2 * (0x800 - self.frequency);
 
             self.sample_index = (self.sample_index + 1) % 32;
+            self.cycles_since_read = 0;
+        }
+        else {
+            self.cycles_since_read = self.cycles_since_read.saturating_add(1);
         }
 
         self.cycle -= 1;
     }
 
+    // Clocked by the frame sequencer at 256Hz
+    pub fn clock_length(&mut self) {
+        if self.mode != Mode::Counter || self.length == 0 { return }
+
+        self.length -= 1;
+        if self.length == 0 {
+            self.enabled = false;
+        }
+    }
+
     pub fn sample(&self) -> Sample {
         if !self.running { return 0 }
 
@@ -73,7 +85,7 @@ impl SampledWave {
     }
 
     pub fn set_length(&mut self, length: u8) {
-        self.remaining = (0x100 - (length as u32)) * 0x4000;
+        self.length = 0x100 - (length as u16);
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -85,11 +97,54 @@ impl SampledWave {
     }
 
     pub fn start(&mut self) {
+        if self.length == 0 {
+            self.length = 0x100;
+        }
+
         self.running = self.enabled;
     }
 
+    // Which byte of wave ram an access to index really goes to. While the channel is playing
+    // it's the byte the channel is reading, whatever the index. The CGB always lets the access
+    // through, the DMG only does straight after the channel has read it, otherwise it's None.
+    pub fn wave_ram_index(&self, index: u8, cgb: bool) -> Option<usize> {
+        if !self.running { return Some(index as usize) }
+
+        if !cgb && self.cycles_since_read >= DMG_WAVE_RAM_ACCESS_CYCLES {
+            return None;
+        }
+
+        Some(self.sample_index / 2)
+    }
+
     pub fn is_dac_enabled(&self) -> bool {
         // self.enabled or self.running?
         self.running
     }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.enabled);
+        state.write_bool(self.running);
+        state.write_u16(self.length);
+        state.write_u8(self.output_level);
+        state.write_u16(self.frequency);
+        state.write_u16(self.cycle);
+        state.write_u8(self.mode as u8);
+        state.write_bytes(&self.samples);
+        state.write_u8(self.sample_index as u8);
+        state.write_u8(self.cycles_since_read);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.enabled = state.read_bool();
+        self.running = state.read_bool();
+        self.length = state.read_u16();
+        self.output_level = state.read_u8() & 3;
+        self.frequency = state.read_u16();
+        self.cycle = state.read_u16();
+        self.mode = Mode::from(state.read_u8());
+        state.read_bytes(&mut self.samples);
+        self.sample_index = (state.read_u8() % 32) as usize;
+        self.cycles_since_read = state.read_u8();
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
 use super::{Mode, Sample, envelope::Envelope};
 
 
@@ -9,7 +11,7 @@ pub struct SquareWave {
     pub mode: Mode,
 
     pub enabled: bool,
-    remaining: u32,
+    length: u16,
     counter: u16,
     phase: u8,
     pub sweep: Sweep
@@ -25,7 +27,7 @@ impl SquareWave {
             mode: Mode::Consecutive,
 
             enabled: false,
-            remaining: 64 * 0x4000,
+            length: 0,
             counter: 0,
             phase: 0,
             sweep: Sweep::new(0)
@@ -33,28 +35,8 @@ impl SquareWave {
     }
 
     pub fn tick(&mut self) {
-        if self.mode == Mode::Counter {
-            if self.remaining == 0 {
-                self.enabled = false;
-                self.remaining = 64 * 0x4000;
-                return;
-            } 
-                
-            self.remaining -= 1;
-        }
-
         if !self.enabled { return }
 
-        self.envelope.tick();
-
-        self.freq = match self.sweep.tick(self.freq) {
-            Some(freq) => freq,
-            None => {
-                self.enabled = false;
-                return
-            }
-        };
-
         if self.counter == 0 {
             self.counter = 4 * (0x800 - self.freq);
             self.phase = (self.phase + 1) % 8;
@@ -63,6 +45,33 @@ impl SquareWave {
         self.counter -= 1;
     }
 
+    // Clocked by the frame sequencer at 256Hz
+    pub fn clock_length(&mut self) {
+        if self.mode != Mode::Counter || self.length == 0 { return }
+
+        self.length -= 1;
+        if self.length == 0 {
+            self.enabled = false;
+        }
+    }
+
+    // Clocked by the frame sequencer at 64Hz
+    pub fn clock_envelope(&mut self) {
+        if !self.enabled { return }
+
+        self.envelope.tick();
+    }
+
+    // Clocked by the frame sequencer at 128Hz
+    pub fn clock_sweep(&mut self) {
+        if !self.enabled { return }
+
+        match self.sweep.tick(self.freq) {
+            Some(freq) => self.freq = freq,
+            None => self.enabled = false
+        }
+    }
+
     pub fn sample(&self) -> Sample {
         if !self.enabled { return 0 }
         
@@ -76,7 +85,7 @@ impl SquareWave {
     pub fn set_length(&mut self, length: u8) {
         assert!(length < 64);
 
-        self.remaining = (64 - (length as u32)) * 0x4000;
+        self.length = 64 - (length as u16);
     }
 
     pub fn set_envelope(&mut self, envelope: Envelope) {
@@ -92,9 +101,39 @@ impl SquareWave {
     }
 
     pub fn start(&mut self) {
+        if self.length == 0 {
+            self.length = 64;
+        }
+
         self.envelope = self.start_envelope;
         self.enabled = self.envelope.enabled();
     }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.duty as u8);
+        self.envelope.save_state(state);
+        self.start_envelope.save_state(state);
+        state.write_u16(self.freq);
+        state.write_u8(self.mode as u8);
+        state.write_bool(self.enabled);
+        state.write_u16(self.length);
+        state.write_u16(self.counter);
+        state.write_u8(self.phase);
+        self.sweep.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.duty = Duty::from(state.read_u8() & 3);
+        self.envelope = Envelope::load_state(state);
+        self.start_envelope = Envelope::load_state(state);
+        self.freq = state.read_u16();
+        self.mode = Mode::from(state.read_u8());
+        self.enabled = state.read_bool();
+        self.length = state.read_u16();
+        self.counter = state.read_u16();
+        self.phase = state.read_u8();
+        self.sweep = Sweep::load_state(state);
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -129,7 +168,7 @@ impl Duty {
 }
 
 pub struct Sweep {
-    duration: u32,
+    period: u8,
     direction: SweepDirection,
     sweep_shift: u8,
     counter: u32
@@ -143,10 +182,10 @@ impl Sweep {
         } else {
             SweepDirection::Decrease
         };
-        let duration = ((val & 0b0111_0000) >> 4) as u32 * 0x8000;
+        let period = (val & 0b0111_0000) >> 4;
 
         Self {
-            duration,
+            period,
             direction,
             sweep_shift: shift,
             counter: 0
@@ -154,17 +193,26 @@ impl Sweep {
     }
 
     pub fn into_u8(&self) -> u8 {
-        let duration = (self.duration / 0x8000) as u8;
+        (1 << 7) | (self.period << 4) | ((self.direction as u8) << 3) | self.sweep_shift
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.into_u8());
+        state.write_u32(self.counter);
+    }
 
-        (1 << 7) | (duration << 4) | ((self.direction as u8) << 3) | self.sweep_shift
+    fn load_state(state: &mut StateReader) -> Self {
+        let mut sweep = Self::new(state.read_u8());
+        sweep.counter = state.read_u32();
+        sweep
     }
 
     pub fn tick(&mut self, freq: u16) -> Option<u16> {
-        if self.duration == 0 {
+        if self.period == 0 {
             return Some(freq);
         }
 
-        self.counter = (self.counter + 1) % self.duration;
+        self.counter = (self.counter + 1) % self.period as u32;
 
         if self.counter != 0 {
             return Some(freq);
@@ -0,0 +1,49 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use super::SAMPLE_RATE;
+
+// Writes out the same interleaved left/right samples the audio device gets,
+// as 32 bit float stereo at SAMPLE_RATE
+pub struct WavRecorder {
+    writer: WavWriter<BufWriter<File>>,
+    // Once a write fails nothing else is written, the error comes back from finish
+    error: Option<hound::Error>
+}
+
+impl WavRecorder {
+    pub fn new(path: &Path) -> Result<Self, hound::Error> {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float
+        };
+
+        Ok(Self {
+            writer: WavWriter::create(path, spec)?,
+            error: None
+        })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) {
+        if self.error.is_some() { return }
+
+        for sample in samples {
+            if let Err(err) = self.writer.write_sample(*sample) {
+                self.error = Some(err);
+                return;
+            }
+        }
+    }
+
+    // Fills in the header, the file isn't a valid .wav until this has been called
+    pub fn finish(self) -> Result<(), hound::Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        self.writer.finalize()
+    }
+}
@@ -1,3 +1,5 @@
+use crate::gameboy::save_state::{StateReader, StateWriter};
+
 use super::{Mode, Sample, envelope::Envelope};
 
 
@@ -7,7 +9,7 @@ pub(super) struct WhiteNoiseWave {
     pub start_envelope: Envelope,
     pub envelope: Envelope,
     pub mode: Mode,
-    remaining: u32
+    length: u16
 }
 
 impl WhiteNoiseWave {
@@ -18,25 +20,31 @@ impl WhiteNoiseWave {
             start_envelope: Envelope::new(0),
             envelope: Envelope::new(0),
             mode: Mode::Consecutive,
-            remaining: 64 * 0x4000
+            length: 0
         }
     }
 
     pub fn tick(&mut self) {
-        if self.mode == Mode::Counter {
-            if self.remaining == 0 {
-                self.enabled = false;
-                self.remaining = 64 * 0x4000;
-                return;
-            } 
-                
-            self.remaining -= 1;
+        if !self.enabled { return }
+
+        self.white_noise_generator.tick();
+    }
+
+    // Clocked by the frame sequencer at 256Hz
+    pub fn clock_length(&mut self) {
+        if self.mode != Mode::Counter || self.length == 0 { return }
+
+        self.length -= 1;
+        if self.length == 0 {
+            self.enabled = false;
         }
+    }
 
+    // Clocked by the frame sequencer at 64Hz
+    pub fn clock_envelope(&mut self) {
         if !self.enabled { return }
 
         self.envelope.tick();
-        self.white_noise_generator.tick();
     }
 
     pub fn sample(&self) -> Sample {
@@ -49,6 +57,11 @@ impl WhiteNoiseWave {
     }
 
     pub fn start(&mut self) {
+        if self.length == 0 {
+            self.length = 64;
+        }
+
+        self.white_noise_generator.reset();
         self.envelope = self.start_envelope;
         self.enabled = self.envelope.enabled();
     }
@@ -56,7 +69,7 @@ impl WhiteNoiseWave {
     pub fn set_length(&mut self, length: u8) {
         assert!(length < 64);
 
-        self.remaining = (64 - (length as u32)) * 0x4000;
+        self.length = 64 - (length as u16);
     }
 
     pub fn set_envelope(&mut self, envelope: Envelope) {
@@ -70,8 +83,29 @@ impl WhiteNoiseWave {
     pub fn is_dac_enabled(&self) -> bool {
         self.envelope.enabled()
     }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.enabled);
+        self.white_noise_generator.save_state(state);
+        self.start_envelope.save_state(state);
+        self.envelope.save_state(state);
+        state.write_u8(self.mode as u8);
+        state.write_u16(self.length);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.enabled = state.read_bool();
+        self.white_noise_generator = WhiteNoiseGenerator::load_state(state);
+        self.start_envelope = Envelope::load_state(state);
+        self.envelope = Envelope::load_state(state);
+        self.mode = Mode::from(state.read_u8());
+        self.length = state.read_u16();
+    }
 }
 
+// The LFSR is reset to all ones when the channel gets triggered
+const LFSR_RESET: u16 = 0x7FFF;
+
 #[derive(Clone, Copy)]
 enum CounterWidth {
     Width15 = 0,
@@ -90,27 +124,34 @@ pub(super) struct WhiteNoiseGenerator {
 
 impl WhiteNoiseGenerator {
     pub fn new(val: u8) -> Self {
-        let dividing_ratio = val & 0b0000_0111;
-        let shift_clock = (val & 0b1111_0000) >> 4;
-        let counter_width = match val & 0b0000_1000 == 0 {
-            true => CounterWidth::Width15,
-            false => CounterWidth::Width7
+        let mut generator = Self {
+            dividing_ratio: 0,
+            shift_clock: 0,
+            counter_width: CounterWidth::Width15,
+            val: 0,
+            noise: LFSR_RESET,
+            
+            cycles: 0
         };
 
-        let noise = match counter_width {
-            CounterWidth::Width15 => (1 << 15) - 1,
-            CounterWidth::Width7 => (1 << 7) - 1,
+        generator.set_val(val);
+        generator
+    }
+
+    // Writing NR43 changes the clock and width but leaves the LFSR where it is
+    pub fn set_val(&mut self, val: u8) {
+        self.dividing_ratio = val & 0b0000_0111;
+        self.shift_clock = (val & 0b1111_0000) >> 4;
+        self.counter_width = match val & 0b0000_1000 == 0 {
+            true => CounterWidth::Width15,
+            false => CounterWidth::Width7
         };
+        self.val = val;
+    }
 
-        Self {
-            dividing_ratio,
-            shift_clock,
-            counter_width,
-            val,
-            noise,
-            
-            cycles: 0
-        }
+    pub fn reset(&mut self) {
+        self.noise = LFSR_RESET;
+        self.cycles = 0;
     }
 
     pub fn tick(&mut self) {
@@ -125,15 +166,30 @@ impl WhiteNoiseGenerator {
             let shift = self.noise >> 1;
             let carry = (self.noise ^ shift) & 1;
 
-            self.noise = match self.counter_width {
-                CounterWidth::Width15 => shift | (carry << 14),
-                CounterWidth::Width7 => shift | (carry << 6),
-            };
+            // the feedback always goes into bit 14, in 7 bit mode it goes into bit 6 as well
+            self.noise = shift | (carry << 14);
+            if let CounterWidth::Width7 = self.counter_width {
+                self.noise = (self.noise & !(1 << 6)) | (carry << 6);
+            }
         }
     }
 
     pub fn is_output_high(&self) -> bool {
         !self.noise & 1 == 1
     }
+
+    // the register value holds the ratio, shift and width
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.val);
+        state.write_u16(self.noise);
+        state.write_u32(self.cycles);
+    }
+
+    fn load_state(state: &mut StateReader) -> Self {
+        let mut generator = Self::new(state.read_u8());
+        generator.noise = state.read_u16();
+        generator.cycles = state.read_u32();
+        generator
+    }
 }
 
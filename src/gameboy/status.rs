@@ -0,0 +1,98 @@
+use std::{error, fmt};
+
+// What happened during a call to step_frame or run_cycles
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameStatus {
+    // The frame is ready to be drawn, or all of the cycles were run
+    Completed,
+    // The cpu ran a STOP instruction and is waiting for a button press
+    Stopped,
+    // Nothing was run as the GameBoy is paused
+    Paused
+}
+
+// Something went wrong that the game can't recover from.
+// Once one of these happens the GameBoy stays frozen at the point it happened.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EmuError {
+    // Real hardware locks up when it tries to run one of these
+    IllegalOpcode { opcode: u8, addr: u16 },
+    UnhandledRead { addr: u16 },
+    UnhandledWrite { addr: u16, val: u8 },
+    // The stack pointer has ended up in ROM, so anything pushed is lost.
+    // Only reported with set_trap_stack_corruption turned on.
+    StackCorruption { sp: u16, pc: u16 }
+}
+
+impl fmt::Display for EmuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmuError::IllegalOpcode { opcode, addr } => 
+                write!(f, "Illegal opcode {:#04X} at {:#06X}", opcode, addr),
+            EmuError::UnhandledRead { addr } => 
+                write!(f, "Unhandled read from {:#06X}", addr),
+            EmuError::UnhandledWrite { addr, val } => 
+                write!(f, "Unhandled write of {:#04X} to {:#06X}", val, addr),
+            EmuError::StackCorruption { sp, pc } => 
+                write!(f, "Stack pushed into ROM, SP: {:#06X} PC: {:#06X}", sp, pc)
+        }
+    }
+}
+
+impl error::Error for EmuError {}
+
+// Why GameBoy::run_code didn't make it to its sentinel
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunCodeError {
+    // Only the illegal opcodes can be used as sentinels
+    InvalidSentinel(u8),
+    // The instruction limit was used up first, the cpu is left where it got to
+    Timeout { instructions: u64 },
+    Emu(EmuError)
+}
+
+impl fmt::Display for RunCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunCodeError::InvalidSentinel(opcode) =>
+                write!(f, "Sentinel {:#04X} isn't an illegal opcode", opcode),
+            RunCodeError::Timeout { instructions } =>
+                write!(f, "Sentinel not reached after {} instructions", instructions),
+            RunCodeError::Emu(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl error::Error for RunCodeError {}
+
+impl From<EmuError> for RunCodeError {
+    fn from(err: EmuError) -> Self {
+        RunCodeError::Emu(err)
+    }
+}
+
+// Things that usually mean the game has crashed, but that emulation can carry on from.
+// Only checked for in debug builds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DebugWarning {
+    // The stack has been pushed to or popped from outside of WRAM and HRAM
+    StackOutOfRange { sp: u16, pc: u16 },
+    // An opcode was fetched from the unusable OAM region, the io registers or IE
+    WildPc { pc: u16 },
+    // A read only register was written to, which does nothing.
+    // Only reported with set_trap_read_only_writes turned on.
+    ReadOnlyWrite { register: &'static str, addr: u16, val: u8 }
+}
+
+impl fmt::Display for DebugWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugWarning::StackOutOfRange { sp, pc } => 
+                write!(f, "Stack outside of ram, SP: {:#06X} PC: {:#06X}", sp, pc),
+            DebugWarning::WildPc { pc } => 
+                write!(f, "Executing from unmapped memory, PC: {:#06X}", pc),
+            DebugWarning::ReadOnlyWrite { register, addr, val } => 
+                write!(f, "Ignored write of {:#04X} to read only {} ({:#06X})", val, register, addr)
+        }
+    }
+}
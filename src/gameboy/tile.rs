@@ -0,0 +1,28 @@
+// https://gbdev.io/pandocs/Tile_Data.html
+// Tiles are 8x8 at 2 bits per pixel. Each row is 2 bytes, the first has the low bit
+// of every pixel and the second the high bit, with the leftmost pixel in bit 7.
+
+// The colour number, 0-3, of each pixel in the row
+pub fn decode_tile_row(low: u8, high: u8) -> [u8; 8] {
+    let mut row = [0; 8];
+
+    for (x, pixel) in row.iter_mut().enumerate() {
+        let bit = 1 << (7 - x);
+        *pixel =
+            (if low & bit != 0 {1} else {0}) |
+            (if high & bit != 0 {2} else {0});
+    }
+
+    row
+}
+
+// A whole tile in the same 16 byte form it takes in vram, indexed [y][x]
+pub fn decode_tile(bytes: &[u8; 16]) -> [[u8; 8]; 8] {
+    let mut tile = [[0; 8]; 8];
+
+    for y in 0..8 {
+        tile[y] = decode_tile_row(bytes[y * 2], bytes[y * 2 + 1]);
+    }
+
+    tile
+}
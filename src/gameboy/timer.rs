@@ -1,6 +1,12 @@
 // TODO:
 // "Additionally, this (DIV) register is reset when executing the stop instruction, and only begins ticking again once stop mode ends."
 
+use super::save_state::{StateReader, StateWriter};
+
+// The apu frame sequencer gets clocked on the falling edge of DIV bit 4,
+// which is bit 12 of the full counter
+const APU_DIV_BIT: u16 = 12;
+
 // Impl based on the cycle accurate docs diagram for obscure timer behaviour
 // also found here: https://gbdev.gg8.se/wiki/articles/Timer_Obscure_Behaviour
 
@@ -12,7 +18,9 @@ pub struct Timer {
 
     tac_freq_bits: [u8; 4],
     tima_overflown: bool,
-    ticks_since_tima_overflown: u8
+    ticks_since_tima_overflown: u8,
+
+    apu_clock_pending: bool
 }
 
 impl Timer {
@@ -25,10 +33,34 @@ impl Timer {
 
             tac_freq_bits: [9, 3, 5, 7],
             tima_overflown: false,
-            ticks_since_tima_overflown: 0
+            ticks_since_tima_overflown: 0,
+
+            apu_clock_pending: false
         }
     }
 
+    // The whole 16 bit counter is saved, not just DIV. TIMA ticks on a falling edge
+    // of one of its lower bits, so it would drift after a load otherwise.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u16(self.div);
+        state.write_u8(self.tima);
+        state.write_u8(self.tma);
+        state.write_u8(self.tac);
+        state.write_bool(self.tima_overflown);
+        state.write_u8(self.ticks_since_tima_overflown);
+        state.write_bool(self.apu_clock_pending);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) {
+        self.div = state.read_u16();
+        self.tima = state.read_u8();
+        self.tma = state.read_u8();
+        self.tac = state.read_u8();
+        self.tima_overflown = state.read_bool();
+        self.ticks_since_tima_overflown = state.read_u8();
+        self.apu_clock_pending = state.read_bool();
+    }
+
     fn is_timer_enabled(&self) -> bool {
         self.tac & 0b0000_0100 != 0
     } 
@@ -43,6 +75,8 @@ impl Timer {
             self.incr_tima();
         }
 
+        self.check_apu_falling_edge(prev_div);
+
         if self.tima_overflown {
             self.ticks_since_tima_overflown += 1;
         }
@@ -74,6 +108,24 @@ impl Timer {
         return prev_bit == 1 && bit == 0;
     }
 
+    fn check_apu_falling_edge(&mut self, prev_div: u16) {
+        if (prev_div >> APU_DIV_BIT) & 1 == 1 && (self.div >> APU_DIV_BIT) & 1 == 0 {
+            self.apu_clock_pending = true;
+        }
+    }
+
+    // The full 16 bit counter, DIV is the upper 8 bits
+    pub fn get_div(&self) -> u16 {
+        self.div
+    }
+
+    // Returns true once for every falling edge of DIV bit 4 since the last call
+    pub fn take_apu_clock(&mut self) -> bool {
+        let pending = self.apu_clock_pending;
+        self.apu_clock_pending = false;
+        pending
+    }
+
     fn incr_tima(&mut self) {
         self.tima = self.tima.wrapping_add(1);
         
@@ -105,6 +157,9 @@ impl Timer {
                 if self.tac & (1 << 2) != 0 && falling_edge_occured {
                     self.incr_tima()
                 }
+
+                // resetting DIV while bit 4 is set clocks the frame sequencer an extra time
+                self.check_apu_falling_edge(prev_div);
             }
             0xFF05 => {
                 if self.ticks_since_tima_overflown < 5 {
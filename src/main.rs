@@ -6,19 +6,24 @@ extern crate imgui_sdl2;
 extern crate gl;
 extern crate imgui_opengl_renderer;
 
-use std::{cell::RefCell, collections::VecDeque, ffi::c_void, process, rc::Rc, time::Duration};
+use std::{cell::RefCell, collections::VecDeque, ffi::c_void, path::Path, process, rc::Rc, time::Duration};
 
 use gameboy_rs::{gameboy::{GameBoy, spu::{ SAMPLES_PER_BUFFER}}};
 use gl::types::GLuint;
 use imgui::{MenuItem, im_str};
 use nfd2::Response;
-use sdl2::{audio::{AudioSpecDesired, AudioStatus}, pixels::PixelFormatEnum, surface::Surface, video::Window};
+use sdl2::{audio::{AudioQueue, AudioSpecDesired, AudioStatus}, messagebox::{MessageBoxFlag, show_simple_message_box}, pixels::PixelFormatEnum, surface::Surface, video::Window};
 
 const SCALE: u32 = 2;
 const WIDTH: u32 = 160;
 const HEIGHT: u32 = 144;
 const MENU_BAR_HEIGHT: u32 = 19;
 
+#[cfg(feature = "zip")]
+const ROM_FILE_FILTER: &str = "gb,gbc,zip";
+#[cfg(not(feature = "zip"))]
+const ROM_FILE_FILTER: &str = "gb";
+
 fn main() {
     let mut gb: Option<GameBoy> = None;
 
@@ -74,6 +79,7 @@ fn main() {
 
     let timer = sdl.timer().unwrap();
     let mut turbo = false;
+    let mut rewinding = false;
 
     let mut elapsed_ns: u64 = 0;
     const FRAMERATE_UPDATE_NS: u64 = 1000000;
@@ -103,6 +109,9 @@ fn main() {
                                 (*audio_device).borrow().pause();
                                 comutative_speed.clear();
                             },
+                            sdl2::keyboard::Keycode::Backspace => {
+                                rewinding = true;
+                            },
                             _ => {
                                 if gb.is_some() && !paused {
                                     gb.as_mut().unwrap().key_down(keycode)
@@ -131,6 +140,9 @@ fn main() {
                                 ad.resume();
                                 comutative_speed.clear();
                             },
+                            sdl2::keyboard::Keycode::Backspace => {
+                                rewinding = false;
+                            },
                             _ => {
                                 if gb.is_some() && !paused {
                                     gb.as_mut().unwrap().key_up(keycode)
@@ -156,15 +168,31 @@ fn main() {
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
 
-        if gb.is_some() && !paused {
+        if gb.is_some() && !paused && rewinding {
+            let gb = gb.as_mut().unwrap();
+            gb.rewind_frame();
+
+            render_gb(gb, fb_id, tex_id);
+            std::thread::sleep(Duration::from_millis(16));
+        }
+
+        else if gb.is_some() && !paused {
             let gb = gb.as_mut().unwrap();
-            let mut stopped: bool = false;
-            while !gb.get_draw_flag() && !stopped {
-                stopped = gb.tick();
+
+            if let Err(err) = gb.step_frame() {
+                paused = true;
+                gb.pause();
+                (*audio_device).borrow().pause();
+
+                show_simple_message_box(
+                    MessageBoxFlag::ERROR, 
+                    "Emulation error", 
+                    &err.to_string(), 
+                    &window
+                ).ok();
             }
 
             render_gb(gb, fb_id, tex_id);
-            gb.clear_draw_flag();
         }
 
         else if gb.is_some() && paused {
@@ -186,18 +214,21 @@ fn main() {
                         if MenuItem::new(im_str!("Load ROM")).build(&ui) {
                             (*audio_device).borrow().pause();
 
-                            match nfd2::open_file_dialog(Some("gb"), None).expect("Hmm?") {
+                            match nfd2::open_file_dialog(Some(ROM_FILE_FILTER), None).expect("Hmm?") {
                                 Response::Okay(file_path) => {
-                                    let _gb = GameBoy::new(
-                                        file_path.to_str().unwrap(), 
-                                        Some(audio_device.clone())
-                                    );
-                                    gb = Some(_gb);
-
-                                    let ad = (*audio_device).borrow();
-                                    ad.clear();
-                                    // the gameboy will resume the audio
-                                    paused = false;
+                                    let _gb = load_rom(&file_path, audio_device.clone(), &window);
+
+                                    if _gb.is_some() {
+                                        gb = _gb;
+                                        gb.as_mut().unwrap().set_rewind(true, 30);
+
+                                        let ad = (*audio_device).borrow();
+                                        ad.clear();
+                                        // the gameboy will resume the audio
+                                        paused = false;
+                                    } else if gb.is_some() && !paused {
+                                        (*audio_device).borrow().resume();
+                                    }
                                 },
                                 
                                 Response::OkayMultiple(files) => println!("Files {:?}", files),
@@ -216,6 +247,11 @@ fn main() {
                                 } else {
                                     (*audio_device).borrow().resume();
                                 }
+
+                                if gb.is_some() {
+                                    let gb = gb.as_mut().unwrap();
+                                    if paused { gb.pause() } else { gb.resume() }
+                                }
                             }
                         }
 
@@ -266,6 +302,44 @@ fn main() {
     }
 }
 
+fn load_rom(file_path: &Path, audio_device: Rc<RefCell<AudioQueue<f32>>>, window: &Window) -> Option<GameBoy> {
+    #[cfg(feature = "zip")]
+    {
+        let is_zip = match file_path.extension() {
+            Some(ext) => ext.to_ascii_lowercase() == "zip",
+            None => false
+        };
+
+        if is_zip {
+            return match GameBoy::from_zip(file_path.to_str().unwrap(), None, Some(audio_device)) {
+                Ok(gb) => Some(gb),
+                Err(err) => {
+                    show_load_error(&err, window);
+                    None
+                }
+            };
+        }
+    }
+
+    match GameBoy::try_new(file_path.to_str().unwrap(), Some(audio_device)) {
+        Ok(gb) => Some(gb),
+        Err(err) => {
+            show_load_error(&err, window);
+            None
+        }
+    }
+}
+
+// Like a rom that isn't a GameBoy rom, or is for the CGB only
+fn show_load_error(err: &dyn std::fmt::Display, window: &Window) {
+    show_simple_message_box(
+        MessageBoxFlag::ERROR,
+        "Unable to load ROM",
+        &err.to_string(),
+        window
+    ).ok();
+}
+
 fn init_gl_state(tex_id: &mut u32, fb_id: &mut u32) {
     unsafe {
         gl::GenTextures(1, tex_id);
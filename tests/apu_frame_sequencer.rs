@@ -0,0 +1,86 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// The frame sequencer is clocked when DIV bit 4 (bit 12 of the full counter)
+// goes from 1 to 0, 8192 cycles after DIV was reset.
+const SEQUENCER_PERIOD: u64 = 8192;
+
+const DIV: u16 = 0xFF04;
+const NR21: u16 = 0xFF16;
+const NR22: u16 = 0xFF17;
+const NR24: u16 = 0xFF19;
+const NR52: u16 = 0xFF26;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    gb
+}
+
+// Resets DIV and the frame sequencer, then starts channel 2 with a length
+// that runs out on the first length clock
+fn start_channel_2(gb: &mut GameBoy) {
+    gb.write_byte(DIV, 0);
+    gb.run_cycles(1).unwrap();
+
+    // powering the apu on starts the sequencer from step 0, which clocks the lengths
+    gb.write_byte(NR52, 0x00);
+    gb.write_byte(NR52, 0x80);
+
+    gb.write_byte(NR22, 0xF0);
+    gb.write_byte(NR21, 63);
+    gb.write_byte(NR24, 0xC0); // trigger with the length enabled
+
+    assert!(channel_2_on(gb));
+}
+
+fn channel_2_on(gb: &GameBoy) -> bool {
+    gb.read_byte(NR52) & 0b0000_0010 != 0
+}
+
+#[test]
+fn length_clocked_by_div() {
+    let mut gb = create_gameboy();
+    start_channel_2(&mut gb);
+
+    gb.run_cycles(SEQUENCER_PERIOD - 200).unwrap();
+    assert!(channel_2_on(&gb));
+
+    gb.run_cycles(200).unwrap();
+    assert!(!channel_2_on(&gb));
+}
+
+#[test]
+fn div_reset_with_bit_4_set_clocks_sequencer() {
+    let mut gb = create_gameboy();
+    start_channel_2(&mut gb);
+
+    // DIV bit 4 is set halfway through the period
+    gb.run_cycles(SEQUENCER_PERIOD / 2 + 100).unwrap();
+    assert!(channel_2_on(&gb));
+
+    gb.write_byte(DIV, 0);
+    gb.run_cycles(1).unwrap();
+    assert!(!channel_2_on(&gb));
+}
+
+#[test]
+fn div_reset_with_bit_4_clear_delays_sequencer() {
+    let mut gb = create_gameboy();
+    start_channel_2(&mut gb);
+
+    gb.run_cycles(SEQUENCER_PERIOD / 2 - 100).unwrap();
+    gb.write_byte(DIV, 0);
+
+    // the clock that was due has been pushed back
+    gb.run_cycles(SEQUENCER_PERIOD / 2 + 200).unwrap();
+    assert!(channel_2_on(&gb));
+
+    gb.run_cycles(SEQUENCER_PERIOD / 2).unwrap();
+    assert!(!channel_2_on(&gb));
+}
@@ -0,0 +1,45 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{booted_gameboy, new_gameboy, rom_with_program, BOOT_FRAMES, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NR52: u16 = 0xFF26;
+
+fn apu_registers(gb: &GameBoy) -> Vec<u8> {
+    (0xFF10..=0xFF26).map(|addr| gb.read_byte(addr)).collect()
+}
+
+#[test]
+fn apu_is_off_at_power_on() {
+    let gb = new_gameboy(rom_with_program(&SPIN));
+
+    // powered off with no channels active, only the unused bits read back
+    assert_eq!(gb.read_byte(NR52), 0x70);
+}
+
+#[test]
+fn skip_boot_rom_leaves_the_apu_as_the_boot_rom_does() {
+    let mut booted = new_gameboy(rom_with_program(&SPIN));
+    for _ in 0..BOOT_FRAMES {
+        booted.step_frame().unwrap();
+    }
+
+    let skipped = booted_gameboy(Model::Dmg);
+
+    // on, with only channel 1 left on from the boot sound
+    assert_eq!(skipped.read_byte(NR52), 0xF1);
+    assert_eq!(apu_registers(&skipped), apu_registers(&booted));
+}
+
+#[test]
+fn skip_boot_rom_is_silent() {
+    let mut gb = booted_gameboy(Model::Dmg);
+
+    gb.step_frame().unwrap();
+
+    let samples = gb.drain_audio();
+    assert!(!samples.is_empty());
+    assert!(samples.windows(2).all(|pair| pair[0] == pair[1]));
+}
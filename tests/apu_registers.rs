@@ -0,0 +1,76 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NR52: u16 = 0xFF26;
+
+// The bits of FF10 - FF2F that always read back as 1, whatever was written.
+// https://gbdev.gg8.se/wiki/articles/Gameboy_sound_hardware#Register_Reading
+const READ_MASKS: [u8; 0x20] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10 - NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // unused, NR21 - NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30 - NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // unused, NR41 - NR44
+    0x00, 0x00, 0x70,             // NR50 - NR52
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF // unused
+];
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    // power cycle the apu so no channels are left on from the boot sound
+    gb.write_byte(NR52, 0x00);
+    gb.write_byte(NR52, 0x80);
+
+    gb
+}
+
+#[test]
+fn registers_read_back_with_masks() {
+    let mut gb = create_gameboy();
+
+    for val in [0x00, 0xFF] {
+        for (i, mask) in READ_MASKS.iter().enumerate() {
+            let addr = 0xFF10 + i as u16;
+            if addr == NR52 { continue }
+
+            gb.write_byte(addr, val);
+            assert_eq!(gb.read_byte(addr), val | mask, "{:#06X} after writing {:#04X}", addr, val);
+        }
+
+        // writing 0xFF triggered every channel, power cycle to turn them off again
+        gb.write_byte(NR52, 0x00);
+        gb.write_byte(NR52, 0x80);
+    }
+}
+
+#[test]
+fn nr52_reads_power_and_channel_status() {
+    let mut gb = create_gameboy();
+
+    // the low bits are read only
+    gb.write_byte(NR52, 0xFF);
+    assert_eq!(gb.read_byte(NR52), 0xF0);
+
+    gb.write_byte(NR52, 0x00);
+    assert_eq!(gb.read_byte(NR52), 0x70);
+}
+
+#[test]
+fn registers_read_masks_while_powered_off() {
+    let mut gb = create_gameboy();
+    gb.write_byte(NR52, 0x00);
+
+    // writes are ignored and everything reads as just its mask
+    for (i, mask) in READ_MASKS.iter().enumerate() {
+        let addr = 0xFF10 + i as u16;
+        if addr == NR52 { continue }
+
+        gb.write_byte(addr, 0xFF);
+        assert_eq!(gb.read_byte(addr), *mask, "{:#06X}", addr);
+    }
+}
@@ -0,0 +1,60 @@
+use gameboy_rs::gameboy::{Model, ascii_art};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+
+#[test]
+fn ascii_art_shades() {
+    // white, light grey, dark grey and black columns, 2 pixels each
+    let pixels: Vec<u8> = (0..8 * 4)
+        .map(|i| [255, 192, 96, 0][(i % 8) / 2])
+        .collect();
+
+    assert_eq!(ascii_art(&pixels, 8, 4, 2), " .+#\n");
+    assert_eq!(ascii_art(&pixels, 8, 4, 1), "  ..++##\n  ..++##\n");
+}
+
+#[test]
+fn ascii_art_partial_blocks() {
+    // the last column and row only have some of their pixels
+    let pixels = vec![0; 5 * 5];
+    assert_eq!(ascii_art(&pixels, 5, 5, 2), "###\n###\n");
+}
+
+#[test]
+fn render_ascii_shows_the_screen() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.write_byte(LCDC, 0);
+
+    // tile 1 is solid colour 3, the left half of the screen is made of it
+    for i in 0..16 {
+        gb.write_byte(0x8000 + i, 0x00);
+        gb.write_byte(0x8010 + i, 0xFF);
+    }
+    for y in 0..32 {
+        for x in 0..32 {
+            gb.write_byte(0x9800 + y * 32 + x, if x < 10 { 1 } else { 0 });
+        }
+    }
+
+    gb.write_byte(LCDC, 0x91);
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+
+    let art = gb.render_ascii();
+    let lines: Vec<&str> = art.lines().collect();
+    assert_eq!(lines.len(), 36);
+
+    let expected = "#".repeat(40) + &" ".repeat(40);
+    for line in lines {
+        assert_eq!(line, expected);
+    }
+
+    let art = gb.render_ascii_with_ratio(8);
+    assert_eq!(art.lines().count(), 9);
+    assert!(art.lines().all(|line| line == "##########          "));
+}
@@ -0,0 +1,58 @@
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const CAPACITY: usize = 4000;
+
+#[test]
+fn samples_available_counts_undrained_samples() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    assert_eq!(gb.audio_samples_available(), 0);
+
+    // the boot rom has the lcd off for the first few frames while it clears vram,
+    // and only turns the sound on after that
+    for _ in 0..8 {
+        gb.step_frame().unwrap();
+    }
+    let available = gb.audio_samples_available();
+    assert!(available > 0);
+    assert_eq!(gb.drain_audio().len(), available);
+    assert_eq!(gb.audio_samples_available(), 0);
+}
+
+#[test]
+fn overfilled_buffer_keeps_the_newest_samples() {
+    // the boot rom's sound plays on both, only the one with a small buffer drops any
+    let mut capped = new_gameboy(rom_with_program(&SPIN));
+    let mut uncapped = new_gameboy(rom_with_program(&SPIN));
+    capped.set_audio_buffer_capacity(CAPACITY);
+
+    for _ in 0..BOOT_FRAMES / 4 {
+        capped.step_frame().unwrap();
+        uncapped.step_frame().unwrap();
+
+        assert!(capped.audio_samples_available() <= CAPACITY);
+    }
+
+    assert_eq!(capped.audio_samples_available(), CAPACITY);
+
+    let capped_audio = capped.drain_audio();
+    let uncapped_audio = uncapped.drain_audio();
+    assert!(uncapped_audio.len() > CAPACITY);
+    assert!(capped_audio[..] == uncapped_audio[uncapped_audio.len() - CAPACITY..]);
+}
+
+#[test]
+fn shrinking_capacity_drops_buffered_samples() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    for _ in 0..10 {
+        gb.step_frame().unwrap();
+    }
+    assert!(gb.audio_samples_available() > 101);
+
+    // an odd capacity would split a left/right pair
+    gb.set_audio_buffer_capacity(101);
+    assert_eq!(gb.audio_samples_available(), 100);
+}
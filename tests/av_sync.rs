@@ -0,0 +1,65 @@
+use gameboy_rs::gameboy::spu::{CLOCK_RATE, SAMPLE_RATE};
+use common::{new_gameboy, rom_with_program, CYCLES_PER_SCREEN_DRAW, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn samples_for_cycles(cycles: u64) -> usize {
+    (cycles * SAMPLE_RATE as u64 / CLOCK_RATE) as usize
+}
+
+#[test]
+fn samples_match_cycles_run() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    // the lcd is on by now, so every frame is a whole screen draw
+    for _ in 0..10 {
+        gb.step_frame().unwrap();
+    }
+
+    let mut total_cycles = 0;
+    let mut total_samples = 0;
+    for _ in 0..60 {
+        let (frame, samples) = gb.step_frame_av().unwrap();
+        assert_eq!(frame.len(), 160 * 144);
+        assert_eq!(gb.frame_metrics().cycles, CYCLES_PER_SCREEN_DRAW);
+
+        total_cycles += gb.frame_metrics().cycles;
+        total_samples += samples.len();
+
+        // the fraction of a sample left over carries on into the next frame
+        assert_eq!(total_samples, samples_for_cycles(total_cycles));
+    }
+}
+
+#[test]
+fn sound_off_is_silence() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+
+    // the boot rom doesn't turn the sound on until it's cleared vram
+    let (_, samples) = gb.step_frame_av().unwrap();
+    assert_eq!(samples.len(), samples_for_cycles(gb.frame_metrics().cycles));
+    assert!(samples.iter().all(|s| *s == (0, 0)));
+}
+
+#[test]
+fn boot_sound_is_captured() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+
+    let mut heard = false;
+    for _ in 0..120 {
+        let (_, samples) = gb.step_frame_av().unwrap();
+        heard |= samples.iter().any(|s| *s != (0, 0));
+    }
+    assert!(heard);
+}
+
+#[test]
+fn same_input_gives_same_audio() {
+    let mut a = new_gameboy(rom_with_program(&SPIN));
+    let mut b = new_gameboy(rom_with_program(&SPIN));
+
+    for _ in 0..100 {
+        assert_eq!(a.step_frame_av().unwrap(), b.step_frame_av().unwrap());
+    }
+}
@@ -0,0 +1,71 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, load_gameboy};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NUM_ROM_BANKS: usize = 4;
+
+// An MBC1 rom with 4 banks, code goes at the start of bank 0's program and wherever
+// it's put in the switchable banks
+fn create_gameboy(program: &[u8], banks: &[(usize, u16, &[u8])]) -> GameBoy {
+    let mut rom = vec![0; 0x4000 * NUM_ROM_BANKS];
+    rom[0x147] = 0x01; // MBC1
+    rom[0x148] = 0x01; // 4 banks
+    rom[0x100..0x100 + program.len()].copy_from_slice(program);
+
+    for (bank, addr, code) in banks {
+        let start = bank * 0x4000 + (*addr as usize - 0x4000);
+        rom[start..start + code.len()].copy_from_slice(code);
+    }
+
+    let mut gb = load_gameboy(rom);
+    boot(&mut gb);
+
+    gb
+}
+
+#[test]
+fn calls_into_switched_bank() {
+    let gb = create_gameboy(&[
+        0x3E, 0x02,             // ld a, 2
+        0xEA, 0x00, 0x20,       // ld (0x2000), a
+        0xCD, 0x00, 0x40,       // call 0x4000
+        0x48,                   // ld c, b
+        0x3E, 0x03,             // ld a, 3
+        0xEA, 0x00, 0x20,       // ld (0x2000), a
+        0xCD, 0x00, 0x40,       // call 0x4000
+        0x50,                   // ld d, b
+        0x18, 0xFE              // jr -2
+    ], &[
+        (1, 0x4000, &[0x06, 0x11, 0xC9]), // ld b, 0x11; ret
+        (2, 0x4000, &[0x06, 0x22, 0xC9]), // ld b, 0x22; ret
+        (3, 0x4000, &[0x06, 0x33, 0xC9])  // ld b, 0x33; ret
+    ]);
+
+    let registers = gb.registers();
+    assert_eq!(registers.c, 0x22);
+    assert_eq!(registers.d, 0x33);
+}
+
+// Code that switches out its own bank carries on from the same address in the new one
+#[test]
+fn switching_own_bank_fetches_from_new_bank() {
+    let switch_to_bank_2: &[u8] = &[
+        0x3E, 0x02,             // ld a, 2
+        0xEA, 0x00, 0x20        // ld (0x2000), a
+    ];
+
+    let gb = create_gameboy(&[
+        0xC3, 0x00, 0x40        // jp 0x4000
+    ], &[
+        (1, 0x4000, switch_to_bank_2),
+        (1, 0x4005, &[0x1E, 0x11, 0x18, 0xFE]), // ld e, 0x11; jr -2
+        (2, 0x4005, &[0x1E, 0x22, 0x18, 0xFE])  // ld e, 0x22; jr -2
+    ]);
+
+    let registers = gb.registers();
+    assert_eq!(registers.e, 0x22);
+    assert!(registers.pc >= 0x4007 && registers.pc <= 0x4009);
+}
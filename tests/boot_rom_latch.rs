@@ -0,0 +1,65 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const BOOT: u16 = 0xFF50;
+
+// The boot rom's first instruction, ld sp, 0xFFFE
+const BOOT_ROM_START: [u8; 3] = [0x31, 0xFE, 0xFF];
+const CART_START: [u8; 3] = [0xAB, 0xCD, 0xEF];
+
+fn create_gameboy() -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x00..0x03].copy_from_slice(&CART_START);
+    rom[0x100..0x102].copy_from_slice(&[0x18, 0xFE]); // jr -2
+
+    new_gameboy(rom)
+}
+
+fn read_start(gb: &GameBoy) -> [u8; 3] {
+    [gb.read_byte(0x00), gb.read_byte(0x01), gb.read_byte(0x02)]
+}
+
+#[test]
+fn writing_ff50_unmaps_boot_rom_for_good() {
+    let mut gb = create_gameboy();
+    assert_eq!(read_start(&gb), BOOT_ROM_START);
+
+    // 0 doesn't unmap it
+    gb.write_byte(BOOT, 0);
+    assert_eq!(read_start(&gb), BOOT_ROM_START);
+
+    gb.write_byte(BOOT, 1);
+    assert_eq!(read_start(&gb), CART_START);
+
+    gb.write_byte(BOOT, 0);
+    assert_eq!(read_start(&gb), CART_START);
+    assert_eq!(gb.read_byte(BOOT), 0xFF);
+}
+
+#[test]
+fn boot_rom_unmaps_itself() {
+    let mut gb = create_gameboy();
+
+    boot(&mut gb);
+
+    assert_eq!(read_start(&gb), CART_START);
+}
+
+#[test]
+fn latch_is_saved() {
+    let mut gb = create_gameboy();
+    let mapped = gb.save_state();
+
+    gb.write_byte(BOOT, 1);
+    let unmapped = gb.save_state();
+
+    gb.load_state(&mapped).unwrap();
+    assert_eq!(read_start(&gb), BOOT_ROM_START);
+
+    gb.load_state(&unmapped).unwrap();
+    assert_eq!(read_start(&gb), CART_START);
+}
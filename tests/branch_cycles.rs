@@ -0,0 +1,118 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const MARKER: u16 = 0xC000;
+const START_FLAG: u16 = 0xC001;
+
+// The M-cycles between the two marker writes that aren't the branch:
+// ld a, 0x02 and ld (MARKER), a
+const MARKER_OVERHEAD: u64 = 2 + 4;
+
+const CONDITION_TRUE: [u8; 2] = [0xF6, 0x01]; // or 0x01, clears Z
+const CONDITION_FALSE: [u8; 2] = [0xAF, 0x00]; // xor a, nop, sets Z
+
+// Waits for the test to set START_FLAG, then writes 1 to MARKER,
+// runs the branch and writes 2 to MARKER.
+// The branch always ends up at the instruction right after it.
+fn create_gameboy(condition: [u8; 2], branch: &[u8]) -> GameBoy {
+    let after_branch = 0x118 + branch.len() as u16;
+    let [low, high] = after_branch.to_le_bytes();
+
+    let mut program = vec![
+        0xAF,                       // 0x100: xor a
+        0xEA, 0x01, 0xC0,           // 0x101: ld (START_FLAG), a
+        0xEA, 0x00, 0xC0,           // 0x104: ld (MARKER), a
+        0xFA, 0x01, 0xC0,           // 0x107: ld a, (START_FLAG)
+        0xB7,                       // 0x10A: or a
+        0x28, 0xFA,                 // 0x10B: jr z, 0x107
+        0x21, low, high,            // 0x10D: ld hl, after_branch
+        0xE5,                       // 0x110: push hl, for ret
+        condition[0], condition[1], // 0x111
+        0x3E, 0x01,                 // 0x113: ld a, 0x01
+        0xEA, 0x00, 0xC0,           // 0x115: ld (MARKER), a
+    ];
+    program.extend_from_slice(branch); // 0x118
+    program.extend_from_slice(&[
+        0x3E, 0x02,                 // ld a, 0x02
+        0xEA, 0x00, 0xC0,           // ld (MARKER), a
+        0x18, 0xFE                  // jr -2
+    ]);
+
+    let mut rom = vec![0; 0x8000];
+    rom[0x38] = 0xC9; // ret, for rst 0x38
+    rom[0x100..0x100 + program.len()].copy_from_slice(&program);
+
+    new_gameboy(rom)
+}
+
+// Returns how many M-cycles the branch took
+fn time_branch(condition: [u8; 2], branch: &[u8]) -> u64 {
+    let mut gb = create_gameboy(condition, branch);
+    boot(&mut gb);
+
+    gb.write_byte(START_FLAG, 1);
+
+    while gb.read_byte(MARKER) != 1 {
+        gb.tick();
+    }
+
+    let mut cycles = 0;
+    while gb.read_byte(MARKER) != 2 {
+        gb.tick();
+        cycles += 1;
+    }
+
+    assert_eq!(cycles % 4, 0);
+    cycles / 4 - MARKER_OVERHEAD
+}
+
+#[test]
+fn jr_cc_cycles() {
+    let branch = [0x20, 0x00]; // jr nz, +0
+    assert_eq!(time_branch(CONDITION_TRUE, &branch), 3);
+    assert_eq!(time_branch(CONDITION_FALSE, &branch), 2);
+}
+
+#[test]
+fn jp_cc_cycles() {
+    let branch = [0xC2, 0x1B, 0x01]; // jp nz, 0x011B
+    assert_eq!(time_branch(CONDITION_TRUE, &branch), 4);
+    assert_eq!(time_branch(CONDITION_FALSE, &branch), 3);
+}
+
+#[test]
+fn call_cc_cycles() {
+    let branch = [0xC4, 0x1B, 0x01]; // call nz, 0x011B
+    assert_eq!(time_branch(CONDITION_TRUE, &branch), 6);
+    assert_eq!(time_branch(CONDITION_FALSE, &branch), 3);
+}
+
+#[test]
+fn ret_cc_cycles() {
+    let branch = [0xC0]; // ret nz
+    assert_eq!(time_branch(CONDITION_TRUE, &branch), 5);
+    assert_eq!(time_branch(CONDITION_FALSE, &branch), 2);
+}
+
+#[test]
+fn call_cycles() {
+    let branch = [0xCD, 0x1B, 0x01]; // call 0x011B
+    assert_eq!(time_branch(CONDITION_TRUE, &branch), 6);
+}
+
+#[test]
+fn ret_cycles() {
+    assert_eq!(time_branch(CONDITION_TRUE, &[0xC9]), 4);
+    assert_eq!(time_branch(CONDITION_TRUE, &[0xD9]), 4);
+}
+
+#[test]
+fn rst_cycles() {
+    // rst 0x38, which rets straight back
+    let rst_and_ret = time_branch(CONDITION_TRUE, &[0xFF]);
+    assert_eq!(rst_and_ret - 4, 4);
+}
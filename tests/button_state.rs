@@ -0,0 +1,48 @@
+use gameboy_rs::gameboy::input::{Button, ButtonState};
+use common::{new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+#[test]
+fn reports_pressed_buttons() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    assert_eq!(gb.button_state(), ButtonState::default());
+
+    gb.press_button(Button::A);
+    gb.press_button(Button::Left);
+    assert_eq!(gb.button_state(), ButtonState { a: true, left: true, ..ButtonState::default() });
+
+    gb.release_button(Button::A);
+    gb.press_button(Button::Start);
+    assert_eq!(gb.button_state(), ButtonState { start: true, left: true, ..ButtonState::default() });
+}
+
+#[test]
+fn ignores_joyp_column() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.press_button(Button::B);
+    gb.press_button(Button::Down);
+    let expected = ButtonState { b: true, down: true, ..ButtonState::default() };
+
+    // directions, actions, both and neither
+    for select in [0x20, 0x10, 0x00, 0x30] {
+        gb.write_byte(0xFF00, select);
+        assert_eq!(gb.button_state(), expected, "JOYP select {:#04X}", select);
+    }
+}
+
+#[test]
+fn follows_button_mask() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+
+    gb.set_button_state(Button::Up.mask() | Button::Select.mask());
+    assert_eq!(gb.button_state(), ButtonState { up: true, select: true, ..ButtonState::default() });
+
+    gb.set_button_state(0xFF);
+    assert_eq!(gb.button_state(), ButtonState {
+        up: true, down: true, left: true, right: true,
+        start: true, select: true, a: true, b: true
+    });
+}
@@ -0,0 +1,73 @@
+use gameboy_rs::gameboy::{CartridgeInfo, GameBoy};
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(header: &[(usize, &[u8])]) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    for (addr, bytes) in header {
+        rom[*addr..*addr + bytes.len()].copy_from_slice(bytes);
+    }
+
+    load_gameboy(rom)
+}
+
+#[test]
+fn dmg_header() {
+    let gb = create_gameboy(&[
+        (0x134, b"TETRIS"),
+        (0x146, &[0x00, 0x00, 0x00, 0x00, 0x00]), // no sgb, rom only, 32KB, no ram, Japan
+        (0x14D, &[0x0A, 0x16, 0xBF])
+    ]);
+
+    assert_eq!(gb.cartridge_info(), CartridgeInfo {
+        title: String::from("TETRIS"),
+        manufacturer_code: String::new(),
+        cgb_flag: 0x00,
+        sgb_flag: false,
+        cartridge_type: 0x00,
+        cartridge_type_name: "ROM ONLY",
+        rom_size: 0x8000,
+        ram_size: 0,
+        destination_code: 0x00,
+        header_checksum: 0x0A,
+        global_checksum: 0x16BF
+    });
+}
+
+#[test]
+fn cgb_header() {
+    let gb = create_gameboy(&[
+        (0x134, b"POKEMON YELAPSE"),
+        (0x143, &[0x80]),
+        (0x146, &[0x03, 0x1B, 0x00, 0x03, 0x01]), // sgb, MBC5+RAM+BATTERY, 32KB, 32KB ram, overseas
+        (0x14D, &[0x04, 0x04, 0x7C])
+    ]);
+
+    let info = gb.cartridge_info();
+    // the CGB flag isn't part of the title
+    assert_eq!(info.title, "POKEMON YELAPSE");
+    assert_eq!(info.manufacturer_code, "APSE");
+    assert_eq!(info.cgb_flag, 0x80);
+    assert!(info.sgb_flag);
+    assert_eq!(info.cartridge_type, 0x1B);
+    assert_eq!(info.cartridge_type_name, "MBC5+RAM+BATTERY");
+    assert_eq!(info.rom_size, 0x8000);
+    assert_eq!(info.ram_size, 0x8000);
+    assert_eq!(info.destination_code, 0x01);
+    assert_eq!(info.header_checksum, 0x04);
+    assert_eq!(info.global_checksum, 0x047C);
+}
+
+#[test]
+fn mbc2_ram_is_counted() {
+    let gb = create_gameboy(&[
+        (0x147, &[0x05])
+    ]);
+
+    let info = gb.cartridge_info();
+    assert_eq!(info.cartridge_type_name, "MBC2");
+    assert_eq!(info.ram_size, 512);
+}
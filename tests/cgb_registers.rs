@@ -0,0 +1,64 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program, SPIN, TempRom};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Nothing is run, the registers are only poked at through the memory bus
+fn create_gameboy(name: &str, model: Model) -> GameBoy {
+    let rom = TempRom::new(name, &rom_with_program(&SPIN));
+    GameBoy::new_with_model(rom.path(), None, model)
+}
+
+#[test]
+fn defaults_to_dmg() {
+    let gb = new_gameboy(rom_with_program(&SPIN));
+    assert_eq!(gb.model(), Model::Dmg);
+}
+
+#[test]
+fn dmg_ignores_cgb_registers() {
+    for model in [Model::Dmg0, Model::Dmg, Model::Mgb, Model::Sgb, Model::Sgb2] {
+        let mut gb = create_gameboy("cgb_regs_dmg.gb", model);
+        assert_eq!(gb.model(), model);
+
+        for addr in [0xFF4F, 0xFF70] {
+            assert_eq!(gb.read_byte(addr), 0xFF);
+
+            gb.write_byte(addr, 0x00);
+            assert_eq!(gb.read_byte(addr), 0xFF, "{:?} {:#06X}", model, addr);
+        }
+    }
+}
+
+#[test]
+fn cgb_keeps_cgb_registers() {
+    let mut gb = create_gameboy("cgb_regs_cgb.gb", Model::Cgb);
+
+    // only bit 0 of VBK exists
+    assert_eq!(gb.read_byte(0xFF4F), 0xFE);
+    gb.write_byte(0xFF4F, 0x01);
+    assert_eq!(gb.read_byte(0xFF4F), 0xFF);
+    gb.write_byte(0xFF4F, 0xFE);
+    assert_eq!(gb.read_byte(0xFF4F), 0xFE);
+
+    // and bits 0 - 2 of SVBK
+    assert_eq!(gb.read_byte(0xFF70), 0xF8);
+    gb.write_byte(0xFF70, 0x05);
+    assert_eq!(gb.read_byte(0xFF70), 0xFD);
+    gb.write_byte(0xFF70, 0xFA);
+    assert_eq!(gb.read_byte(0xFF70), 0xFA);
+}
+
+#[test]
+fn set_model_changes_registers() {
+    let mut gb = create_gameboy("cgb_regs_set_model.gb", Model::Dmg);
+    gb.set_model(Model::Cgb);
+
+    gb.write_byte(0xFF70, 0x03);
+    assert_eq!(gb.read_byte(0xFF70), 0xFB);
+
+    gb.set_model(Model::Dmg);
+    assert_eq!(gb.read_byte(0xFF70), 0xFF);
+}
@@ -0,0 +1,87 @@
+use gameboy_rs::gameboy::{CheatError, GameBoy};
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const PATCHED_ADDR: u16 = 0x0150;
+const ORIGINAL_VAL: u8 = 0x11;
+
+// Keeps copying the byte at PATCHED_ADDR to 0xC000
+fn create_gameboy() -> GameBoy {
+    let mut rom = rom_with_program(&[
+        0xFA, 0x50, 0x01,       // ld a, (0x0150)
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xF8              // jr -8
+    ]);
+    rom[PATCHED_ADDR as usize] = ORIGINAL_VAL;
+
+    let mut gb = new_gameboy(rom);
+    boot(&mut gb);
+
+    gb
+}
+
+#[test]
+fn game_genie_patches_rom() {
+    let mut gb = create_gameboy();
+    assert_eq!(gb.read_byte(PATCHED_ADDR), ORIGINAL_VAL);
+
+    // 0x99 at 0x0150
+    gb.add_cheat("991-50F").unwrap();
+    assert_eq!(gb.read_byte(PATCHED_ADDR), 0x99);
+    assert_eq!(gb.read_byte(PATCHED_ADDR + 1), 0x00);
+
+    // the cpu sees the patch too
+    gb.step_frame().unwrap();
+    assert_eq!(gb.read_byte(0xC000), 0x99);
+
+    assert!(gb.remove_cheat("991-50f"));
+    assert!(!gb.remove_cheat("991-50F"));
+    assert_eq!(gb.read_byte(PATCHED_ADDR), ORIGINAL_VAL);
+}
+
+#[test]
+fn game_genie_compare_value() {
+    let mut gb = create_gameboy();
+
+    // compares against 0x22, so nothing is patched
+    gb.add_cheat("991-50F-6E2").unwrap();
+    assert_eq!(gb.read_byte(PATCHED_ADDR), ORIGINAL_VAL);
+
+    // compares against 0x11
+    gb.add_cheat("991-50F-A6E").unwrap();
+    assert_eq!(gb.read_byte(PATCHED_ADDR), 0x99);
+
+    gb.clear_cheats();
+    assert_eq!(gb.read_byte(PATCHED_ADDR), ORIGINAL_VAL);
+}
+
+#[test]
+fn game_shark_forces_ram_every_frame() {
+    let mut gb = create_gameboy();
+
+    // 0x63 at 0xC100
+    gb.add_cheat("016300C1").unwrap();
+
+    for _ in 0..3 {
+        gb.write_byte(0xC100, 0x00);
+        gb.step_frame().unwrap();
+        assert_eq!(gb.read_byte(0xC100), 0x63);
+    }
+
+    gb.remove_cheat("016300C1");
+    gb.write_byte(0xC100, 0x00);
+    gb.step_frame().unwrap();
+    assert_eq!(gb.read_byte(0xC100), 0x00);
+}
+
+#[test]
+fn invalid_codes() {
+    let mut gb = create_gameboy();
+
+    assert_eq!(gb.add_cheat("991-50"), Err(CheatError::InvalidLength));
+    assert_eq!(gb.add_cheat("016300C1FF"), Err(CheatError::InvalidLength));
+    assert_eq!(gb.add_cheat("991-50G"), Err(CheatError::InvalidCharacter('G')));
+}
@@ -1,11 +1,85 @@
-use std::path::PathBuf;
+// Not every test uses everything in here
+#![allow(dead_code)]
 
-use image::{ImageBuffer, RgbImage, RgbaImage, io::Reader};
+use std::{env, fs, path::PathBuf};
+
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Model, NoMbc, Registers};
+use image::{ImageBuffer, RgbImage, RgbaImage};
 
 pub const WIDTH: u32 = 160;
 pub const HEIGHT: u32 = 144;
 pub const CYCLES_PER_SCREEN_DRAW: u64 = 70_224;
 
+// the boot rom takes a little under 100 frames to hand over to the cartridge
+pub const BOOT_FRAMES: usize = 120;
+
+// jr -2, for parking the cpu once a test program is done
+pub const SPIN: [u8; 2] = [0x18, 0xFE];
+
+// A 32KB rom with the program at the entry point
+pub fn rom_with_program(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x100 + program.len()].copy_from_slice(program);
+    rom
+}
+
+// A cartridge with no mapper, put together in memory so there's nothing left behind on disk
+pub fn new_gameboy(rom: Vec<u8>) -> GameBoy {
+    GameBoy::from_cartridge(Cartridge::new(rom, Vec::new(), Box::new(NoMbc::new()), None), None)
+}
+
+// For roms that need the header read, to pick the mapper and the ram size. There's no save file.
+pub fn load_gameboy(rom: Vec<u8>) -> GameBoy {
+    GameBoy::from_rom(rom, None).unwrap()
+}
+
+// Runs the boot rom until the cartridge has taken over
+pub fn boot(gb: &mut GameBoy) {
+    for _ in 0..BOOT_FRAMES {
+        gb.step_frame().unwrap();
+    }
+}
+
+// Starts where the model's boot rom would have left off, parked on SPIN
+pub fn booted_gameboy(model: Model) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.skip_boot_rom(model);
+    gb
+}
+
+// For running single instructions with execute_opcode: every register is 0
+// apart from the stack pointer at the top of HRAM and the pc at the entry point
+pub fn cleared_registers_gameboy() -> GameBoy {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.set_registers(Registers { sp: 0xFFFE, pc: 0x100, ..Registers::default() });
+    gb
+}
+
+// A rom written out for the constructors that take a path, deleted again when dropped
+pub struct TempRom {
+    path: PathBuf
+}
+
+impl TempRom {
+    pub fn new(name: &str, rom: &[u8]) -> Self {
+        let mut path = env::temp_dir();
+        path.push(format!("gameboy_rs_{}", name));
+        fs::write(&path, rom).unwrap();
+
+        Self { path }
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TempRom {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
 #[allow(dead_code)]
 pub fn create_image(fb: &[u8], p: String) {
     let mut img: RgbImage = ImageBuffer::new(WIDTH, HEIGHT);
@@ -0,0 +1,74 @@
+use gameboy_rs::gameboy::GameBoy;
+
+extern crate gameboy_rs;
+
+// (opcode, cycles), from https://gbdev.io/gb-opcodes/optables/
+const UNCONDITIONAL: [(u8, u32); 20] = [
+    (0x00, 1), // nop
+    (0x01, 3), // ld bc, u16
+    (0x02, 2), // ld (bc), a
+    (0x03, 2), // inc bc
+    (0x06, 2), // ld b, u8
+    (0x08, 5), // ld (u16), sp
+    (0x18, 3), // jr i8
+    (0x34, 3), // inc (hl)
+    (0x36, 3), // ld (hl), u8
+    (0x7E, 2), // ld a, (hl)
+    (0x80, 1), // add a, b
+    (0xC1, 3), // pop bc
+    (0xC3, 4), // jp u16
+    (0xC5, 4), // push bc
+    (0xC9, 4), // ret
+    (0xCD, 6), // call u16
+    (0xE0, 3), // ldh (u8), a
+    (0xE8, 4), // add sp, i8
+    (0xE9, 1), // jp hl
+    (0xFA, 4)  // ld a, (u16)
+];
+
+// (opcode, taken, not taken)
+const CONDITIONAL: [(u8, u32, u32); 5] = [
+    (0x20, 3, 2), // jr nz, i8
+    (0xC0, 5, 2), // ret nz
+    (0xC2, 4, 3), // jp nz, u16
+    (0xC4, 6, 3), // call nz, u16
+    (0xD8, 5, 2)  // ret c
+];
+
+// (second byte, cycles)
+const CB_PREFIXED: [(u8, u32); 5] = [
+    (0x00, 2), // rlc b
+    (0x06, 4), // rlc (hl)
+    (0x37, 2), // swap a
+    (0x46, 3), // bit 0, (hl)
+    (0x86, 4)  // res 0, (hl)
+];
+
+#[test]
+fn unconditional_opcodes() {
+    for &(opcode, cycles) in UNCONDITIONAL.iter() {
+        assert_eq!(GameBoy::cycles_for_opcode(opcode, 0, false), Some(cycles), "opcode {:#04X}", opcode);
+        assert_eq!(GameBoy::cycles_for_opcode(opcode, 0, true), Some(cycles), "opcode {:#04X}", opcode);
+    }
+}
+
+#[test]
+fn conditional_opcodes() {
+    for &(opcode, taken, not_taken) in CONDITIONAL.iter() {
+        assert_eq!(GameBoy::cycles_for_opcode(opcode, 0, true), Some(taken), "opcode {:#04X}", opcode);
+        assert_eq!(GameBoy::cycles_for_opcode(opcode, 0, false), Some(not_taken), "opcode {:#04X}", opcode);
+    }
+}
+
+#[test]
+fn cb_prefixed_opcodes() {
+    for &(cb_opcode, cycles) in CB_PREFIXED.iter() {
+        assert_eq!(GameBoy::cycles_for_opcode(0xCB, cb_opcode, false), Some(cycles), "opcode 0xCB {:#04X}", cb_opcode);
+    }
+}
+
+#[test]
+fn illegal_opcodes_have_no_cycles() {
+    assert_eq!(GameBoy::cycles_for_opcode(0xD3, 0, false), None);
+    assert_eq!(GameBoy::cycles_for_opcode(0xFD, 0, false), None);
+}
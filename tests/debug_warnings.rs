@@ -0,0 +1,81 @@
+// The checks are compiled out of release builds
+#![cfg(debug_assertions)]
+
+use gameboy_rs::gameboy::{DebugWarning, GameBoy};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(program));
+    for _ in 0..BOOT_FRAMES {
+        // running wild can end up on an illegal opcode
+        if gb.step_frame().is_err() { break }
+    }
+
+    gb
+}
+
+#[test]
+fn stack_leaving_ram_warns() {
+    let mut gb = create_gameboy(&[
+        0x31, 0x02, 0xC0,       // ld sp, 0xC002
+        0xC5,                   // push bc
+        0xC5,                   // push bc
+        0xC5,                   // push bc
+        0x18, 0xFE              // jr -2
+    ]);
+
+    // only warns once going out of range
+    assert_eq!(gb.take_debug_warnings(), vec![
+        DebugWarning::StackOutOfRange { sp: 0xBFFF, pc: 0x105 }
+    ]);
+    assert!(gb.take_debug_warnings().is_empty());
+}
+
+#[test]
+fn stack_in_ram_doesnt_warn() {
+    let mut gb = create_gameboy(&[
+        0x31, 0x00, 0xE0,       // ld sp, 0xE000
+        0xC5,                   // push bc
+        0xC1,                   // pop bc
+        0xCD, 0x0A, 0x01,       // call 0x010A
+        0x18, 0xFE,             // jr -2
+        0xC9                    // ret
+    ]);
+
+    assert!(gb.take_debug_warnings().is_empty());
+}
+
+#[test]
+fn wild_pc_warns() {
+    let mut gb = create_gameboy(&[
+        0xC3, 0xA0, 0xFE        // jp 0xFEA0
+    ]);
+
+    assert_eq!(gb.take_debug_warnings()[0], DebugWarning::WildPc { pc: 0xFEA0 });
+}
+
+#[test]
+fn read_only_write_warns_when_trapped() {
+    let mut gb = create_gameboy(&[0x18, 0xFE]); // jr -2
+    gb.set_trap_read_only_writes(true);
+
+    gb.write_byte(0xFF44, 0x42);
+    assert_eq!(gb.take_debug_warnings(), vec![
+        DebugWarning::ReadOnlyWrite { register: "LY", addr: 0xFF44, val: 0x42 }
+    ]);
+}
+
+#[test]
+fn read_only_write_ignored_by_default() {
+    let mut gb = create_gameboy(&[
+        0x3E, 0x42,             // ld a, 0x42
+        0xE0, 0x44,             // ldh (LY), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    assert!(gb.take_debug_warnings().is_empty());
+}
@@ -0,0 +1,68 @@
+use gameboy_rs::gameboy::{GameBoy, input::Button};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+// Plays the noise channel with a clock picked from power-on WRAM, then
+// changes the clock to the JOYP lines whenever a button is held
+const NOISE_PROGRAM: &[u8] = &[
+    0x3E, 0x80, 0xE0, 0x26,     // ld a, 0x80; ldh (NR52), a
+    0x3E, 0x77, 0xE0, 0x24,     // ld a, 0x77; ldh (NR50), a
+    0x3E, 0xFF, 0xE0, 0x25,     // ld a, 0xFF; ldh (NR51), a
+    0x3E, 0xF0, 0xE0, 0x21,     // ld a, 0xF0; ldh (NR42), a
+    0xFA, 0x00, 0xC0,           // ld a, (0xC000)
+    0xE6, 0x07,                 // and 0x07
+    0xE0, 0x22,                 // ldh (NR43), a
+    0x3E, 0x80, 0xE0, 0x23,     // ld a, 0x80; ldh (NR44), a
+    0x3E, 0x10, 0xE0, 0x00,     // ld a, 0x10; ldh (JOYP), a
+    // loop:
+    0xF0, 0x00,                 // ldh a, (JOYP)
+    0xE6, 0x0F,                 // and 0x0F
+    0xFE, 0x0F,                 // cp 0x0F
+    0x28, 0xF8,                 // jr z, loop
+    0xE0, 0x22,                 // ldh (NR43), a
+    0x18, 0xF4                  // jr loop
+];
+
+fn record() -> (Vec<f32>, Vec<u8>) {
+    let mut gb = create_gameboy(NOISE_PROGRAM);
+
+    for frame in 0..BOOT_FRAMES + 60 {
+        if frame == BOOT_FRAMES + 10 { gb.press_button(Button::A); }
+        if frame == BOOT_FRAMES + 20 { gb.release_button(Button::A); }
+        if frame == BOOT_FRAMES + 30 { gb.press_button(Button::Start); }
+
+        gb.step_frame().unwrap();
+    }
+
+    (gb.drain_audio(), gb.get_frame_buffer().to_vec())
+}
+
+#[test]
+fn fresh_emulators_produce_identical_audio() {
+    let (audio_1, frame_1) = record();
+    let (audio_2, frame_2) = record();
+
+    // make sure the noise channel actually made some noise
+    assert!(audio_1.iter().any(|sample| *sample != audio_1[0]));
+
+    assert_eq!(audio_1.len(), audio_2.len());
+    assert!(audio_1 == audio_2);
+    assert!(frame_1 == frame_2);
+}
+
+#[test]
+fn power_on_ram_is_the_same_every_run() {
+    let gb_1 = create_gameboy(&[0x18, 0xFE]);
+    let gb_2 = create_gameboy(&[0x18, 0xFE]);
+
+    for addr in 0xC000..=0xDFFF {
+        assert_eq!(gb_1.read_byte(addr), gb_2.read_byte(addr));
+    }
+}
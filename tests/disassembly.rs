@@ -0,0 +1,65 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const PROGRAM: [u8; 16] = [
+    0x00,                   // nop
+    0x3E, 0x42,             // ld a, 0x42
+    0xC3, 0x50, 0x01,       // jp 0x0150
+    0xCB, 0x37,             // swap a
+    0xD3,                   // illegal
+    0x08, 0x00, 0xC0,       // ld (0xC000), sp
+    0x18, 0xFE,             // jr -2
+    0xDD,                   // illegal
+    0xC9                    // ret
+];
+
+fn create_gameboy() -> GameBoy {
+    new_gameboy(rom_with_program(&PROGRAM))
+}
+
+#[test]
+fn disassembly_iterator_yields_instructions() {
+    let gb = create_gameboy();
+
+    let disassembly: Vec<(u16, u8, Vec<u8>)> = gb.disassemble(0x100)
+        .take(10)
+        .map(|(addr, instruction, operands)| (addr, instruction.opcode_val, operands))
+        .collect();
+
+    assert_eq!(disassembly, vec![
+        (0x100, 0x00, vec![]),
+        (0x101, 0x3E, vec![0x42]),
+        (0x103, 0xC3, vec![0x50, 0x01]),
+        (0x106, 0x37, vec![0x37]),
+        (0x108, 0xD3, vec![]),
+        (0x109, 0x08, vec![0x00, 0xC0]),
+        (0x10C, 0x18, vec![0xFE]),
+        (0x10E, 0xDD, vec![]),
+        (0x10F, 0xC9, vec![]),
+        (0x110, 0x00, vec![])
+    ]);
+}
+
+#[test]
+fn disassembly_iterator_marks_illegal_opcodes() {
+    let gb = create_gameboy();
+
+    let (addr, instruction, _) = gb.disassemble(0x108).next().unwrap();
+    assert_eq!(addr, 0x108);
+    assert_eq!(instruction.human_readable, "ILLEGAL");
+    assert_eq!(instruction.length, 1);
+
+    let (addr, instruction, _) = gb.disassemble(0x108).nth(1).unwrap();
+    assert_eq!(addr, 0x109);
+    assert_eq!(instruction.human_readable, "LD (u16), SP");
+}
+
+#[test]
+fn disassembly_iterator_stops_at_end_of_memory() {
+    let gb = create_gameboy();
+    assert_eq!(gb.disassemble(0xFFFE).count(), 2);
+}
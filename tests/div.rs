@@ -0,0 +1,56 @@
+use gameboy_rs::gameboy::Model;
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const DIV: u16 = 0xFF04;
+const TIMA: u16 = 0xFF05;
+const TAC: u16 = 0xFF07;
+
+#[test]
+fn div_is_upper_byte_of_counter() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.run_cycles(10_000).unwrap();
+
+    let counter = gb.div_counter();
+    assert_eq!(gb.read_byte(DIV), (counter >> 8) as u8);
+
+    gb.run_cycles(100).unwrap();
+    assert_eq!(gb.div_counter(), counter.wrapping_add(100));
+}
+
+#[test]
+fn writing_div_resets_whole_counter() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.run_cycles(10_000).unwrap();
+    assert_ne!(gb.div_counter() & 0xFF, 0);
+
+    // the value written doesn't matter
+    gb.write_byte(DIV, 0x5A);
+    assert_eq!(gb.div_counter(), 0);
+    assert_eq!(gb.read_byte(DIV), 0);
+
+    // counts up from 0 rather than carrying on from the old lower byte
+    gb.run_cycles(255).unwrap();
+    assert_eq!(gb.div_counter(), 255);
+    assert_eq!(gb.read_byte(DIV), 0);
+    gb.run_cycles(1).unwrap();
+    assert_eq!(gb.read_byte(DIV), 1);
+}
+
+#[test]
+fn writing_div_with_timer_bit_set_increments_tima() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    // 262144Hz, TIMA follows bit 3 of the counter
+    gb.write_byte(TAC, 0b101);
+
+    while gb.div_counter() & 0b1000 == 0 {
+        gb.run_cycles(1).unwrap();
+    }
+
+    let tima = gb.read_byte(TIMA);
+    gb.write_byte(DIV, 0);
+    assert_eq!(gb.read_byte(TIMA), tima.wrapping_add(1));
+}
@@ -0,0 +1,155 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8], hram_routine: &[u8], rst_38: &[u8]) -> GameBoy {
+    let mut rom = rom_with_program(program);
+    rom[0x200..0x200 + hram_routine.len()].copy_from_slice(hram_routine);
+    rom[0x38..0x38 + rst_38.len()].copy_from_slice(rst_38);
+
+    new_gameboy(rom)
+}
+
+#[test]
+fn non_hram_reads_are_0xff_during_dma() {
+    let mut gb = create_gameboy(&[
+        0x3E, 0x5A,             // ld a, 0x5A
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x21, 0x80, 0xFF,       // ld hl, 0xFF80
+        0x11, 0x00, 0x02,       // ld de, 0x0200
+        0x06, 0x20,             // ld b, 0x20
+        0x1A,                   // ld a, (de)
+        0x13,                   // inc de
+        0x22,                   // ld (hl+), a
+        0x05,                   // dec b
+        0x20, 0xFA,             // jr nz, -6
+        0xC3, 0x80, 0xFF        // jp 0xFF80
+    ], &[
+        // copied to 0xFF80
+        0x3E, 0xC0,             // ld a, 0xC0
+        0xE0, 0x46,             // ldh (DMA), a
+        0xFA, 0x00, 0xC0,       // ld a, (0xC000)
+        0x47,                   // ld b, a
+        0x3E, 0x32,             // ld a, 50
+        0x3D,                   // dec a
+        0x20, 0xFD,             // jr nz, -3
+        0xFA, 0x00, 0xC0,       // ld a, (0xC000)
+        0x4F,                   // ld c, a
+        0x78,                   // ld a, b
+        0xE0, 0xF0,             // ldh (0xF0), a
+        0x79,                   // ld a, c
+        0xE0, 0xF1,             // ldh (0xF1), a
+        0x18, 0xFE              // jr -2
+    ], &[]);
+
+    for _ in 0..BOOT_FRAMES + 2 {
+        gb.step_frame().unwrap();
+    }
+
+    // during the transfer
+    assert_eq!(gb.read_byte(0xFFF0), 0xFF);
+    // after it finished
+    assert_eq!(gb.read_byte(0xFFF1), 0x5A);
+    // the transfer itself could read wram
+    assert_eq!(gb.read_byte(0xFE00), 0x5A);
+}
+
+#[test]
+fn executing_outside_hram_during_dma_reads_0xff() {
+    // the opcode after the DMA write is fetched as 0xFF, "rst 0x38"
+    let mut gb = create_gameboy(&[
+        0x3E, 0xC0,             // ld a, 0xC0
+        0xE0, 0x46,             // ldh (DMA), a
+        0x18, 0xFE              // jr -2
+    ], &[], &[
+        0x3E, 0x01,             // ld a, 0x01
+        0xEA, 0x01, 0xC0,       // ld (0xC001), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    for _ in 0..BOOT_FRAMES + 2 {
+        gb.step_frame().unwrap();
+    }
+
+    assert_eq!(gb.read_byte(0xC001), 0x01);
+}
+
+#[test]
+fn dma_from_0xff_copies_top_of_wram() {
+    let mut gb = create_gameboy(&[
+        0x18, 0xFE              // jr -2
+    ], &[], &[]);
+
+    boot(&mut gb);
+
+    // lcd off so oam can be read back
+    gb.write_byte(0xFF40, 0);
+    for i in 0..0xA0 {
+        gb.write_byte(0xDF00 + i, i as u8 ^ 0xA5);
+    }
+
+    // 0xFF00 - 0xFF9F is the io registers and HRAM, but the DMA reads it as echo ram
+    gb.write_byte(0xFF46, 0xFF);
+    gb.run_cycles(4 * 170).unwrap();
+
+    for i in 0..0xA0 {
+        assert_eq!(gb.read_byte(0xFE00 + i), i as u8 ^ 0xA5, "oam byte {}", i);
+    }
+}
+
+#[test]
+fn oam_writes_ignored_during_dma() {
+    let mut gb = create_gameboy(&[
+        0x18, 0xFE              // jr -2
+    ], &[], &[]);
+
+    boot(&mut gb);
+
+    gb.write_byte(0xFF40, 0);
+    for i in 0..0xA0 {
+        gb.write_byte(0xC000 + i, 0xAA);
+    }
+
+    // part way through, one byte the DMA has already copied and one it hasn't got to yet
+    gb.write_byte(0xFF46, 0xC0);
+    gb.run_cycles(4 * 40).unwrap();
+    gb.write_byte(0xFE00, 0x55);
+    gb.write_byte(0xFE90, 0x55);
+
+    gb.run_cycles(4 * 130).unwrap();
+    for i in 0..0xA0 {
+        assert_eq!(gb.read_byte(0xFE00 + i), 0xAA, "oam byte {}", i);
+    }
+
+    // once it's finished oam can be written again
+    gb.write_byte(0xFE00, 0x55);
+    assert_eq!(gb.read_byte(0xFE00), 0x55);
+}
+
+#[test]
+fn dma_restarted_by_second_write() {
+    let mut gb = create_gameboy(&[
+        0x18, 0xFE              // jr -2
+    ], &[], &[]);
+
+    boot(&mut gb);
+
+    gb.write_byte(0xFF40, 0);
+    for i in 0..0xA0 {
+        gb.write_byte(0xC000 + i, 0xAA);
+        gb.write_byte(0xC100 + i, i as u8);
+    }
+
+    // part way through the first transfer
+    gb.write_byte(0xFF46, 0xC0);
+    gb.run_cycles(4 * 80).unwrap();
+    gb.write_byte(0xFF46, 0xC1);
+
+    gb.run_cycles(4 * 170).unwrap();
+    for i in 0..0xA0 {
+        assert_eq!(gb.read_byte(0xFE00 + i), i as u8, "oam byte {}", i);
+    }
+}
@@ -0,0 +1,57 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = booted_gameboy(Model::Dmg);
+    // so the ppu doesn't lock OAM
+    gb.write_byte(LCDC, 0);
+    gb
+}
+
+#[test]
+fn echo_ram_ends_at_fdff() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(0xFDFF, 0x12);
+    assert_eq!(gb.read_byte(0xDDFF), 0x12);
+    assert_eq!(gb.read_byte(0xFDFF), 0x12);
+
+    gb.write_byte(0xDD00, 0x34);
+    assert_eq!(gb.read_byte(0xFD00), 0x34);
+
+    gb.write_byte(0xE000, 0x56);
+    assert_eq!(gb.read_byte(0xC000), 0x56);
+}
+
+#[test]
+fn fe00_is_oam() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0xDE00, 0x00);
+    gb.write_byte(0xDE9F, 0x00);
+
+    gb.write_byte(0xFE00, 0x78);
+    gb.write_byte(0xFE9F, 0x9A);
+    assert_eq!(gb.read_byte(0xFE00), 0x78);
+    assert_eq!(gb.read_byte(0xFE9F), 0x9A);
+
+    // and not the working ram that 0xFE00 - 0x2000 would be
+    assert_eq!(gb.read_byte(0xDE00), 0x00);
+    assert_eq!(gb.read_byte(0xDE9F), 0x00);
+}
+
+#[test]
+fn unusable_region_writes_go_nowhere() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0xDEA0, 0x00);
+    gb.write_byte(0xFE00, 0x00);
+
+    gb.write_byte(0xFEA0, 0xBC);
+    assert_eq!(gb.read_byte(0xDEA0), 0x00);
+    assert_eq!(gb.read_byte(0xFE00), 0x00);
+}
@@ -0,0 +1,91 @@
+use gameboy_rs::gameboy::{EmuError, FrameStatus, GameBoy};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+fn run_until_error(gb: &mut GameBoy) -> EmuError {
+    for _ in 0..BOOT_FRAMES + 10 {
+        if let Err(err) = gb.step_frame() {
+            return err;
+        }
+    }
+
+    panic!("No error returned");
+}
+
+#[test]
+fn illegal_opcode_returns_error() {
+    let mut gb = create_gameboy(&[
+        0x00,                   // nop
+        0x00,                   // nop
+        0xD3                    // illegal
+    ]);
+
+    let err = run_until_error(&mut gb);
+    assert_eq!(err, EmuError::IllegalOpcode { opcode: 0xD3, addr: 0x102 });
+
+    // the GameBoy stays frozen at the error
+    assert_eq!(gb.step_frame(), Err(err));
+    assert_eq!(gb.run_cycles(100), Err(err));
+    assert_eq!(gb.get_error(), Some(err));
+}
+
+#[test]
+fn push_into_rom_returns_error() {
+    let mut gb = create_gameboy(&[
+        0x31, 0x00, 0x40,       // ld sp, 0x4000
+        0xC5,                   // push bc
+        0x18, 0xFE              // jr -2
+    ]);
+    gb.set_trap_stack_corruption(true);
+
+    let err = run_until_error(&mut gb);
+    assert_eq!(err, EmuError::StackCorruption { sp: 0x3FFF, pc: 0x104 });
+}
+
+#[test]
+fn push_into_rom_is_allowed_by_default() {
+    let mut gb = create_gameboy(&[
+        0x31, 0x00, 0x40,       // ld sp, 0x4000
+        0xC5,                   // push bc
+        0x18, 0xFE              // jr -2
+    ]);
+
+    for _ in 0..BOOT_FRAMES + 10 {
+        assert_eq!(gb.step_frame(), Ok(FrameStatus::Completed));
+    }
+}
+
+#[test]
+fn debugger_reads_are_not_errors() {
+    let mut gb = create_gameboy(&[
+        0x18, 0xFE              // jr -2
+    ]);
+
+    // a debugger looking through every address shouldn't stop the game
+    for addr in 0..=0xFFFF {
+        gb.read_byte(addr);
+    }
+
+    assert_eq!(gb.step_frame(), Ok(FrameStatus::Completed));
+    assert_eq!(gb.get_error(), None);
+}
+
+#[test]
+fn frame_status() {
+    let mut gb = create_gameboy(&[
+        0x18, 0xFE              // jr -2
+    ]);
+
+    assert_eq!(gb.step_frame(), Ok(FrameStatus::Completed));
+    assert_eq!(gb.run_cycles(100), Ok(FrameStatus::Completed));
+
+    gb.pause();
+    assert_eq!(gb.step_frame(), Ok(FrameStatus::Paused));
+}
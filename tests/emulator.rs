@@ -0,0 +1,66 @@
+use gameboy_rs::gameboy::{Emulator, FrameStatus, GameBoy, input::Button};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Keeps copying the action buttons from JOYP into 0xFF80
+fn create_gameboy() -> GameBoy {
+    new_gameboy(rom_with_program(&[
+        0x3E, 0x10,             // ld a, 0x10
+        0xE0, 0x00,             // ldh (JOYP), a
+        0xF0, 0x00,             // ldh a, (JOYP)
+        0xE0, 0x80,             // ldh (0x80), a
+        0x18, 0xF6              // jr -10
+    ]))
+}
+
+fn run_frames(emulator: &mut dyn Emulator, frames: usize) {
+    for _ in 0..frames {
+        assert_eq!(emulator.step_frame(), Ok(FrameStatus::Completed));
+    }
+}
+
+#[test]
+fn runs_boxed() {
+    let mut emulator: Box<dyn Emulator> = Box::new(create_gameboy());
+
+    run_frames(emulator.as_mut(), BOOT_FRAMES);
+    assert_eq!(emulator.framebuffer().len(), 160 * 144);
+    // the boot rom's sound
+    assert!(!emulator.drain_audio().is_empty());
+}
+
+#[test]
+fn buttons() {
+    let mut gb = create_gameboy();
+    run_frames(&mut gb, BOOT_FRAMES);
+    assert_eq!(gb.read_byte(0xFF80) & 0x0F, 0x0F);
+
+    let emulator: &mut dyn Emulator = &mut gb;
+    emulator.press_button(Button::A);
+    run_frames(emulator, 1);
+    assert_eq!(gb.read_byte(0xFF80) & 0x0F, 0x0E);
+
+    let emulator: &mut dyn Emulator = &mut gb;
+    emulator.release_button(Button::A);
+    run_frames(emulator, 1);
+    assert_eq!(gb.read_byte(0xFF80) & 0x0F, 0x0F);
+}
+
+#[test]
+fn save_states() {
+    let mut emulator: Box<dyn Emulator> = Box::new(create_gameboy());
+    run_frames(emulator.as_mut(), BOOT_FRAMES / 2);
+
+    let state = emulator.save_state();
+    run_frames(emulator.as_mut(), BOOT_FRAMES / 2);
+    let expected = emulator.framebuffer().to_vec();
+
+    emulator.load_state(&state).unwrap();
+    run_frames(emulator.as_mut(), BOOT_FRAMES / 2);
+    assert_eq!(emulator.framebuffer(), &expected[..]);
+
+    assert!(emulator.load_state(&[0; 4]).is_err());
+}
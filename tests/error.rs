@@ -0,0 +1,70 @@
+use std::{error, io};
+
+use gameboy_rs::gameboy::{Error, GameBoy, save_state::StateError};
+use common::{new_gameboy, rom_with_program, TempRom};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn load_error(name: &str, header: &[(usize, u8)]) -> Error {
+    let mut rom = vec![0; 0x8000];
+    for (addr, val) in header {
+        rom[*addr] = *val;
+    }
+
+    let rom = TempRom::new(name, &rom);
+    match GameBoy::try_new(rom.path(), None) {
+        Ok(_) => panic!("{} loaded", name),
+        Err(err) => err
+    }
+}
+
+#[test]
+fn error_messages() {
+    let io = Error::Io(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+    assert_eq!(io.to_string(), "Unable to read the ROM: no such file");
+
+    assert_eq!(Error::CgbOnly.to_string(), "This ROM only runs on a Game Boy Color");
+    assert_eq!(
+        Error::InvalidRomSize(0x42).to_string(), 
+        "Cartridge header has an invalid ROM size code: 0x42"
+    );
+    assert_eq!(
+        Error::InvalidRamSize(0x07).to_string(), 
+        "Cartridge header has an invalid RAM size code: 0x07"
+    );
+    assert_eq!(
+        Error::UnsupportedMapper(0x20).to_string(), 
+        "Unsupported cartridge type 0x20 (MBC6)"
+    );
+    assert_eq!(
+        Error::State(StateError::UnsupportedVersion(9)).to_string(), 
+        "Unable to load the save state: Save state version 9 isn't supported, expected version 2"
+    );
+}
+
+#[test]
+fn errors_can_be_boxed() {
+    let err: Box<dyn error::Error> = Box::new(Error::from(StateError::WrongRom));
+    assert_eq!(err.source().unwrap().to_string(), "The save state is for a different ROM");
+}
+
+#[test]
+fn bad_roms_are_errors_instead_of_panics() {
+    let missing = GameBoy::try_new("/this/rom/does/not/exist.gb", None);
+    assert!(matches!(missing, Err(Error::Io(_))));
+
+    assert!(matches!(load_error("error_cgb.gb", &[(0x143, 0xC0)]), Error::CgbOnly));
+    assert!(matches!(load_error("error_rom_size.gb", &[(0x148, 0x42)]), Error::InvalidRomSize(0x42)));
+    assert!(matches!(load_error("error_ram_size.gb", &[(0x149, 0x07)]), Error::InvalidRamSize(0x07)));
+    assert!(matches!(load_error("error_mapper.gb", &[(0x147, 0x20)]), Error::UnsupportedMapper(0x20)));
+}
+
+#[test]
+fn state_errors_convert() {
+    let mut gb = new_gameboy(rom_with_program(&[]));
+
+    let result: Result<(), Error> = gb.load_state(&[0; 4]).map_err(Error::from);
+    assert!(matches!(result, Err(Error::State(StateError::InvalidHeader))));
+}
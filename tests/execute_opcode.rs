@@ -0,0 +1,107 @@
+use gameboy_rs::gameboy::EmuError;
+use common::cleared_registers_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const FLAG_Z: u8 = 0b1000_0000;
+const FLAG_N: u8 = 0b0100_0000;
+const FLAG_H: u8 = 0b0010_0000;
+const FLAG_C: u8 = 0b0001_0000;
+
+#[test]
+fn arithmetic() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_a(0x3A);
+    gb.set_b(0xC6);
+
+    // add a, b
+    assert_eq!(gb.execute_opcode(0x80, 0, 0), Ok(1));
+    assert_eq!(gb.registers().a, 0x00);
+    assert_eq!(gb.registers().f, FLAG_Z | FLAG_H | FLAG_C);
+
+    // sub a, u8
+    assert_eq!(gb.execute_opcode(0xD6, 0x01, 0), Ok(2));
+    assert_eq!(gb.registers().a, 0xFF);
+    assert_eq!(gb.registers().f, FLAG_N | FLAG_H | FLAG_C);
+
+    // xor a
+    assert_eq!(gb.execute_opcode(0xAF, 0, 0), Ok(1));
+    assert_eq!(gb.registers().a, 0x00);
+    assert_eq!(gb.registers().f, FLAG_Z);
+
+    // swap a
+    gb.set_a(0xF1);
+    assert_eq!(gb.execute_opcode(0xCB, 0x37, 0), Ok(2));
+    assert_eq!(gb.registers().a, 0x1F);
+    assert_eq!(gb.registers().f, 0);
+}
+
+#[test]
+fn loads() {
+    let mut gb = cleared_registers_gameboy();
+
+    // ld a, u8
+    assert_eq!(gb.execute_opcode(0x3E, 0x42, 0), Ok(2));
+    assert_eq!(gb.registers().a, 0x42);
+
+    // ld hl, u16
+    assert_eq!(gb.execute_opcode(0x21, 0, 0xC123), Ok(3));
+    assert_eq!((gb.registers().h, gb.registers().l), (0xC1, 0x23));
+
+    // ld (hl+), a
+    assert_eq!(gb.execute_opcode(0x22, 0, 0), Ok(2));
+    assert_eq!(gb.read_byte(0xC123), 0x42);
+    assert_eq!(gb.registers().l, 0x24);
+
+    // ld (u16), a
+    assert_eq!(gb.execute_opcode(0xEA, 0, 0xC200), Ok(4));
+    assert_eq!(gb.read_byte(0xC200), 0x42);
+
+    // loads leave the flags alone
+    assert_eq!(gb.registers().f, 0);
+}
+
+#[test]
+fn pc_moves_past_operands() {
+    let mut gb = cleared_registers_gameboy();
+
+    assert_eq!(gb.execute_opcode(0x00, 0, 0), Ok(1));
+    assert_eq!(gb.registers().pc, 0x101);
+
+    assert_eq!(gb.execute_opcode(0x21, 0, 0x1234), Ok(3));
+    assert_eq!(gb.registers().pc, 0x104);
+
+    // jr -4, taken and not taken
+    assert_eq!(gb.execute_opcode(0x18, 0xFC, 0), Ok(3));
+    assert_eq!(gb.registers().pc, 0x102);
+
+    assert_eq!(gb.execute_opcode(0x28, 0xFC, 0), Ok(2)); // jr z, Z is clear
+    assert_eq!(gb.registers().pc, 0x104);
+}
+
+#[test]
+fn illegal_opcode_is_an_error() {
+    let mut gb = cleared_registers_gameboy();
+
+    assert_eq!(gb.execute_opcode(0xD3, 0, 0), Err(EmuError::IllegalOpcode { opcode: 0xD3, addr: 0x100 }));
+    assert_eq!(gb.registers().pc, 0x100);
+}
+
+#[test]
+fn pop_af_clears_lower_nibble_of_f() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_b(0xFF);
+    gb.set_c(0xFF);
+
+    // push bc, pop af
+    assert_eq!(gb.execute_opcode(0xC5, 0, 0), Ok(4));
+    assert_eq!(gb.execute_opcode(0xF1, 0, 0), Ok(3));
+    assert_eq!(gb.registers().a, 0xFF);
+    assert_eq!(gb.registers().f, 0xF0);
+
+    // and it stays cleared when pushed back
+    assert_eq!(gb.execute_opcode(0xF5, 0, 0), Ok(4));
+    assert_eq!(gb.read_byte(gb.registers().sp), 0xF0);
+}
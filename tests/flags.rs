@@ -0,0 +1,43 @@
+use gameboy_rs::gameboy::Flags;
+use common::cleared_registers_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+#[test]
+fn flags_decoded_after_instruction() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_a(0x3A);
+    gb.set_b(0xC6);
+
+    // add a, b
+    gb.execute_opcode(0x80, 0, 0).unwrap();
+    assert_eq!(gb.flags(), Flags { z: true, n: false, h: true, c: true });
+
+    // sub a, u8
+    gb.execute_opcode(0xD6, 0x01, 0).unwrap();
+    assert_eq!(gb.flags(), Flags { z: false, n: true, h: true, c: true });
+}
+
+#[test]
+fn set_flags_writes_f() {
+    let mut gb = cleared_registers_gameboy();
+
+    gb.set_flags(Flags { z: true, n: false, h: false, c: true });
+    assert_eq!(gb.registers().f, 0b1001_0000);
+
+    gb.set_flags(Flags { z: false, n: true, h: true, c: false });
+    assert_eq!(gb.registers().f, 0b0110_0000);
+    assert_eq!(gb.flags(), Flags { z: false, n: true, h: true, c: false });
+}
+
+#[test]
+fn set_flags_used_by_conditional_jump() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_flags(Flags { c: true, ..Flags::default() });
+
+    // jp c, 0x0200
+    gb.execute_opcode(0xDA, 0, 0x0200).unwrap();
+    assert_eq!(gb.registers().pc, 0x200);
+}
@@ -0,0 +1,86 @@
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Mbc1};
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+// An MBC1 cart with 8 rom banks and 4 ram banks. Every rom bank starts with its own number.
+// Nothing is run, the cartridge is only poked at through the memory bus.
+fn create_gameboy() -> GameBoy {
+    let mut rom = vec![0; 0x4000 * 8];
+    for bank in 0..8 {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom[0x147] = 0x03;
+    rom[0x148] = 0x02;
+    rom[0x149] = 0x03;
+
+    load_gameboy(rom)
+}
+
+#[test]
+fn forced_rom_bank_until_game_reselects() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0x2000, 0x02);
+    assert_eq!(gb.current_rom_bank(), 2);
+
+    gb.set_rom_bank(5);
+    assert_eq!(gb.current_rom_bank(), 5);
+    assert_eq!(gb.read_byte(0x4000), 5);
+    // bank 0 stays where it is
+    assert_eq!(gb.read_byte(0x0147), 0x03);
+
+    // past the end wraps round
+    gb.set_rom_bank(11);
+    assert_eq!(gb.read_byte(0x4000), 3);
+
+    // the game picks its own bank again
+    gb.write_byte(0x2000, 0x04);
+    assert_eq!(gb.current_rom_bank(), 4);
+    assert_eq!(gb.read_byte(0x4000), 4);
+}
+
+#[test]
+fn forced_ram_bank_until_game_reselects() {
+    let mut gb = create_gameboy();
+
+    // ram on, ram banking mode, then the bank number into each bank
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0x6000, 0x01);
+    for bank in 0..4 {
+        gb.write_byte(0x4000, bank);
+        gb.write_byte(0xA000, 0x10 + bank);
+    }
+    assert_eq!(gb.current_ram_bank(), 3);
+
+    gb.set_ram_bank(1);
+    assert_eq!(gb.current_ram_bank(), 1);
+    assert_eq!(gb.read_byte(0xA000), 0x11);
+
+    // writes go to the forced bank too
+    gb.write_byte(0xA001, 0x99);
+    gb.set_ram_bank(2);
+    assert_eq!(gb.read_byte(0xA000), 0x12);
+
+    gb.write_byte(0x4000, 0x01);
+    assert_eq!(gb.current_ram_bank(), 1);
+    assert_eq!(gb.read_byte(0xA000), 0x11);
+    assert_eq!(gb.read_byte(0xA001), 0x99);
+}
+
+#[test]
+fn forced_rom_bank_on_a_rom_shorter_than_a_bank() {
+    // put together by hand, so the header never got to size it
+    let cartridge = Cartridge::new(vec![0x42; 0x200], Vec::new(), Box::new(Mbc1::new()), None);
+    let mut gb = GameBoy::from_cartridge(cartridge, None);
+
+    gb.set_rom_bank(3);
+    assert_eq!(gb.read_byte(0x0150), 0x42);
+    // padded with 0s
+    assert_eq!(gb.read_byte(0x4000), 0x00);
+    assert_eq!(gb.read_byte(0x7FFF), 0x00);
+
+    gb.write_byte(0x2000, 0x05);
+    assert_eq!(gb.read_byte(0x4000), 0x00);
+}
@@ -0,0 +1,106 @@
+use gameboy_rs::gameboy::{GameBoy, MapperKind, Model};
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+// A 128KB rom where every byte of a bank is the bank's number, but
+// the header says it's ROM ONLY
+fn create_gameboy() -> GameBoy {
+    let mut rom = vec![0; 8 * 0x4000];
+    for (i, val) in rom.iter_mut().enumerate() {
+        *val = (i / 0x4000) as u8;
+    }
+    rom[0x147] = 0x00;
+    rom[0x148] = 0x02;
+    rom[0x149] = 0x00;
+
+    let mut gb = load_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb
+}
+
+#[test]
+fn header_mapper_ignores_bank_switches() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(0x2000, 0x03);
+    assert_eq!(gb.read_byte(0x4000), 1);
+}
+
+#[test]
+fn forced_mbc1_switches_banks() {
+    let mut gb = create_gameboy();
+    gb.force_mapper(MapperKind::Mbc1);
+
+    assert_eq!(gb.read_byte(0x4000), 1);
+
+    gb.write_byte(0x2000, 0x03);
+    assert_eq!(gb.read_byte(0x4000), 3);
+    assert_eq!(gb.read_byte(0x7FFF), 3);
+    assert_eq!(gb.memory_layout().rom_bank, 3);
+
+    // past the end of the rom wraps around
+    gb.write_byte(0x2000, 0x0A);
+    assert_eq!(gb.read_byte(0x4000), 2);
+
+    // the header is left as it was
+    assert_eq!(gb.cartridge_info().cartridge_type, 0x00);
+}
+
+#[test]
+fn forced_mbc5_wraps_banks_and_has_no_ram() {
+    let mut gb = create_gameboy();
+    gb.force_mapper(MapperKind::Mbc5);
+
+    gb.write_byte(0x2000, 0x05);
+    assert_eq!(gb.read_byte(0x4000), 5);
+
+    // past the end of the rom wraps around
+    gb.write_byte(0x2000, 0x0D);
+    assert_eq!(gb.read_byte(0x4000), 5);
+
+    // the header asked for no ram
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0xA000, 0x12);
+    assert_eq!(gb.read_byte(0xA000), 0xFF);
+}
+
+#[test]
+fn forced_mbc3_gets_the_ram_the_header_asked_for() {
+    let mut rom = vec![0; 8 * 0x4000];
+    rom[0x147] = 0x00;
+    rom[0x148] = 0x02;
+    rom[0x149] = 0x03; // 32KB
+
+    let mut gb = load_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb.force_mapper(MapperKind::Mbc3);
+    assert_eq!(gb.cartridge_info().ram_size, 0x8000);
+
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0x4000, 0x03);
+    gb.write_byte(0xA000, 0x12);
+    gb.write_byte(0x4000, 0x00);
+    assert_eq!(gb.read_byte(0xA000), 0x00);
+    gb.write_byte(0x4000, 0x03);
+    assert_eq!(gb.read_byte(0xA000), 0x12);
+}
+
+#[test]
+fn forced_mbc1_on_mbc2_header_drops_its_ram() {
+    let mut rom = vec![0; 8 * 0x4000];
+    rom[0x147] = 0x06;
+    rom[0x148] = 0x02;
+    rom[0x149] = 0x00;
+
+    let mut gb = load_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb.force_mapper(MapperKind::Mbc1);
+
+    // the header says there's no ram, the MBC2's built in ram went with the MBC2
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0xBFFF, 0x12);
+    assert_eq!(gb.read_byte(0xBFFF), 0xFF);
+}
@@ -0,0 +1,61 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const WHITE: u8 = 255;
+const BLACK: u8 = 0;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+// Flips BGP between all white and all black every vblank
+const FLICKER_PROGRAM: &[u8] = &[
+    0x3E, 0x00,                 // ld a, 0x00
+    0xE0, 0x47,                 // ldh (BGP), a
+    // loop:
+    0xF0, 0x44,                 // ldh a, (LY)
+    0xFE, 0x90,                 // cp 144
+    0x20, 0xFA,                 // jr nz, loop
+    0xF0, 0x47,                 // ldh a, (BGP)
+    0x2F,                       // cpl
+    0xE0, 0x47,                 // ldh (BGP), a
+    // wait_vblank_end:
+    0xF0, 0x44,                 // ldh a, (LY)
+    0xFE, 0x90,                 // cp 144
+    0x28, 0xFA,                 // jr z, wait_vblank_end
+    0x18, 0xED                  // jr loop
+];
+
+fn frame_color(gb: &GameBoy) -> u8 {
+    let frame = gb.get_frame_buffer();
+    assert!(frame.iter().all(|pixel| *pixel == frame[0]));
+    frame[0]
+}
+
+#[test]
+fn frame_blend_averages_frames() {
+    let mut gb = create_gameboy(FLICKER_PROGRAM);
+    boot(&mut gb);
+
+    // without blending the frames alternate
+    let first = frame_color(&gb);
+    assert!(first == WHITE || first == BLACK);
+
+    gb.step_frame().unwrap();
+    assert_eq!(frame_color(&gb), WHITE - first);
+
+    gb.set_frame_blend(true);
+    for _ in 0..4 {
+        gb.step_frame().unwrap();
+        assert_eq!(frame_color(&gb), ((WHITE as u16 + BLACK as u16) / 2) as u8);
+    }
+
+    gb.set_frame_blend(false);
+    let last = frame_color(&gb);
+    gb.step_frame().unwrap();
+    assert_eq!(frame_color(&gb), WHITE - last);
+}
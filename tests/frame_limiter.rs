@@ -0,0 +1,153 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use gameboy_rs::gameboy::{Clock, FrameLimiter};
+use common::{new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+#[derive(Default)]
+struct MockState {
+    now: Duration,
+    sleeps: Vec<Duration>,
+    // added on to every sleep, like a thread that wakes up late
+    oversleep: Duration
+}
+
+#[derive(Clone, Default)]
+struct MockClock {
+    state: Rc<RefCell<MockState>>
+}
+
+impl MockClock {
+    fn advance(&self, duration: Duration) {
+        self.state.borrow_mut().now += duration;
+    }
+
+    fn sleeps(&self) -> Vec<Duration> {
+        self.state.borrow().sleeps.clone()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.state.borrow().now
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        let mut state = self.state.borrow_mut();
+        state.sleeps.push(duration);
+        let oversleep = state.oversleep;
+        state.now += duration + oversleep;
+    }
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn create_limiter(fps: f64) -> (FrameLimiter, MockClock) {
+    let clock = MockClock::default();
+    let mut limiter = FrameLimiter::with_clock(Box::new(clock.clone()));
+    limiter.set_target_fps(fps);
+
+    (limiter, clock)
+}
+
+#[test]
+fn sleeps_for_rest_of_frame() {
+    let (mut limiter, clock) = create_limiter(50.0);
+
+    // the first frame only sets the pace
+    assert_eq!(limiter.frame(), ms(0));
+
+    for work in [5, 12, 0] {
+        clock.advance(ms(work));
+        assert_eq!(limiter.frame(), ms(20 - work));
+    }
+
+    assert_eq!(clock.sleeps(), vec![ms(15), ms(8), ms(20)]);
+}
+
+#[test]
+fn oversleeping_is_made_up_next_frame() {
+    let (mut limiter, clock) = create_limiter(50.0);
+    clock.state.borrow_mut().oversleep = ms(3);
+
+    limiter.frame();
+    clock.advance(ms(5));
+    assert_eq!(limiter.frame(), ms(15));
+
+    // woke up 3ms late, so there's 3ms less to wait
+    clock.advance(ms(5));
+    assert_eq!(limiter.frame(), ms(12));
+}
+
+#[test]
+fn slow_frames_dont_sleep() {
+    let (mut limiter, clock) = create_limiter(50.0);
+
+    limiter.frame();
+    clock.advance(ms(30));
+    assert_eq!(limiter.frame(), ms(0));
+
+    // 10ms behind is caught up by the next frame
+    clock.advance(ms(5));
+    assert_eq!(limiter.frame(), ms(5));
+}
+
+#[test]
+fn falling_far_behind_starts_again() {
+    let (mut limiter, clock) = create_limiter(50.0);
+
+    limiter.frame();
+    clock.advance(ms(500));
+    assert_eq!(limiter.frame(), ms(0));
+
+    // paced from the slow frame rather than racing to catch up
+    clock.advance(ms(5));
+    assert_eq!(limiter.frame(), ms(15));
+}
+
+#[test]
+fn no_target_never_sleeps() {
+    let (mut limiter, clock) = create_limiter(0.0);
+
+    for _ in 0..5 {
+        clock.advance(ms(1));
+        assert_eq!(limiter.frame(), ms(0));
+    }
+
+    assert!(clock.sleeps().is_empty());
+}
+
+#[test]
+fn measures_fps() {
+    let (mut limiter, clock) = create_limiter(50.0);
+    assert_eq!(limiter.current_fps(), 0.0);
+
+    for _ in 0..10 {
+        clock.advance(ms(2));
+        limiter.frame();
+    }
+
+    assert!((limiter.current_fps() - 50.0).abs() < 0.01);
+}
+
+#[test]
+fn step_frame_is_limited() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    let clock = MockClock::default();
+    gb.set_limiter_clock(Box::new(clock.clone()));
+    gb.set_target_fps(60.0);
+
+    for _ in 0..3 {
+        gb.step_frame().unwrap();
+    }
+
+    // the mock clock doesn't move while frames run, so each frame sleeps for all of it
+    let frame_time = Duration::from_secs_f64(1.0 / 60.0);
+    assert_eq!(clock.sleeps(), vec![frame_time, frame_time]);
+    assert!((gb.current_fps() - 60.0).abs() < 0.01);
+}
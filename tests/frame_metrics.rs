@@ -0,0 +1,51 @@
+use gameboy_rs::gameboy::{FrameMetrics, GameBoy};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, CYCLES_PER_SCREEN_DRAW, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// jr takes 12 cycles when it jumps
+const JR_CYCLES: u64 = 12;
+
+fn create_gameboy(boot_frames: usize) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    for _ in 0..boot_frames {
+        gb.step_frame().unwrap();
+    }
+
+    gb
+}
+
+#[test]
+fn nothing_run_yet() {
+    let gb = create_gameboy(0);
+    assert_eq!(gb.frame_metrics(), FrameMetrics::default());
+}
+
+#[test]
+fn counts_one_frame() {
+    let mut gb = create_gameboy(BOOT_FRAMES);
+
+    for _ in 0..3 {
+        gb.step_frame().unwrap();
+        let metrics = gb.frame_metrics();
+
+        assert_eq!(metrics.cycles, CYCLES_PER_SCREEN_DRAW);
+        assert_eq!(metrics.ppu_dots, CYCLES_PER_SCREEN_DRAW);
+
+        // the frame can start or end part way through a jr
+        let instructions = CYCLES_PER_SCREEN_DRAW / JR_CYCLES;
+        assert!(metrics.instructions >= instructions - 1 && metrics.instructions <= instructions + 1);
+    }
+}
+
+#[test]
+fn covers_every_frame_of_a_fast_forward() {
+    let mut gb = create_gameboy(BOOT_FRAMES);
+
+    gb.set_frame_speed(4);
+    gb.step_frame().unwrap();
+    assert_eq!(gb.frame_metrics().cycles, CYCLES_PER_SCREEN_DRAW * 4);
+    assert_eq!(gb.frame_metrics().ppu_dots, CYCLES_PER_SCREEN_DRAW * 4);
+}
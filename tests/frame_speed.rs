@@ -0,0 +1,99 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Counts VBlank interrupts at 0xC000
+fn create_gameboy() -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x40..0x4A].copy_from_slice(&[
+        0xF5,                   // push af
+        0xFA, 0x00, 0xC0,       // ld a, (0xC000)
+        0x3C,                   // inc a
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0xF1,                   // pop af
+        0xD9                    // reti
+    ]);
+    rom[0x100..0x10E].copy_from_slice(&[
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xAF,                   // xor a
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0xE0, 0x0F,             // ldh (IF), a
+        0xFB,                   // ei
+        0x76,                   // halt
+        0x18, 0xFD              // jr -3
+    ]);
+
+    let mut gb = new_gameboy(rom);
+    boot(&mut gb);
+
+    gb
+}
+
+fn frames_run(gb: &mut GameBoy) -> u8 {
+    let start = gb.read_byte(0xC000);
+    gb.step_frame().unwrap();
+    gb.read_byte(0xC000).wrapping_sub(start)
+}
+
+#[test]
+fn speed_sets_frames_per_step() {
+    let mut gb = create_gameboy();
+    assert_eq!(frames_run(&mut gb), 1);
+
+    for speed in [2, 3, 8] {
+        gb.set_frame_speed(speed);
+        assert_eq!(frames_run(&mut gb), speed as u8);
+    }
+
+    // 0 is treated as normal speed
+    gb.set_frame_speed(0);
+    assert_eq!(frames_run(&mut gb), 1);
+}
+
+#[test]
+fn speed_only_lasts_one_step() {
+    let mut gb = create_gameboy();
+
+    gb.set_frame_speed(4);
+    assert_eq!(frames_run(&mut gb), 4);
+    assert_eq!(frames_run(&mut gb), 1);
+}
+
+#[test]
+fn same_cycles_as_separate_steps() {
+    let mut fast = create_gameboy();
+    let mut slow = create_gameboy();
+
+    fast.set_frame_speed(5);
+    fast.step_frame().unwrap();
+    for _ in 0..5 {
+        slow.step_frame().unwrap();
+    }
+
+    // both have run for exactly the same number of cycles, so the timer agrees
+    assert_eq!(fast.read_byte(0xFF04), slow.read_byte(0xFF04));
+    assert_eq!(fast.read_byte(0xC000), slow.read_byte(0xC000));
+    assert_eq!(fast.registers(), slow.registers());
+}
+
+#[test]
+fn sped_up_frames_are_muted() {
+    let mut gb = create_gameboy();
+
+    gb.drain_audio();
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+    assert!(!gb.drain_audio().is_empty());
+
+    gb.set_frame_speed(4);
+    gb.step_frame().unwrap();
+    assert!(gb.drain_audio().is_empty());
+
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+    assert!(!gb.drain_audio().is_empty());
+}
@@ -0,0 +1,104 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+fn run(gb: &mut GameBoy) {
+    for _ in 0..BOOT_FRAMES + 5 {
+        gb.step_frame().unwrap();
+    }
+}
+
+#[test]
+fn halt_bug_executes_next_opcode_twice() {
+    // IME=0 with a VBlank interrupt already pending when HALT executes
+    let mut gb = create_gameboy(&[
+        0xF3,                   // di
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xE0, 0x0F,             // ldh (IF), a
+        0xAF,                   // xor a
+        0x76,                   // halt
+        0x3C,                   // inc a
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 2);
+}
+
+#[test]
+fn halt_bug_rereads_byte_as_operand() {
+    // "ld b, 0x04" becomes "ld b, 0x06" followed by "inc b"
+    let mut gb = create_gameboy(&[
+        0xF3,                   // di
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xE0, 0x0F,             // ldh (IF), a
+        0x76,                   // halt
+        0x06, 0x04,             // ld b, 0x04
+        0x78,                   // ld a, b
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x07);
+}
+
+#[test]
+fn halt_without_pending_interrupt_wakes_normally() {
+    // IME=0 and nothing pending, HALT waits for the VBlank and then
+    // carries on without re-reading anything
+    let mut gb = create_gameboy(&[
+        0xF3,                   // di
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xAF,                   // xor a
+        0xE0, 0x0F,             // ldh (IF), a
+        0x76,                   // halt
+        0x3C,                   // inc a
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 1);
+}
+
+#[test]
+fn halt_bug_after_ei_returns_to_the_halt() {
+    // IME=1 from an EI just before the HALT, with the VBlank interrupt already pending.
+    // It's dispatched straight away, but the return address is the HALT rather than
+    // the byte after it.
+    let mut rom = rom_with_program(&[
+        0xF3,                   // di
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xE0, 0x0F,             // ldh (IF), a
+        0xFB,                   // ei
+        0x76,                   // halt
+        0x18, 0xFE              // jr -2
+    ]);
+    // the VBlank handler stores the return address and spins
+    rom[0x40..0x4B].copy_from_slice(&[
+        0xE1,                   // pop hl
+        0x7D,                   // ld a, l
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x7C,                   // ld a, h
+        0xEA, 0x01, 0xC0,       // ld (0xC001), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    let mut gb = new_gameboy(rom);
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x08);
+    assert_eq!(gb.read_byte(0xC001), 0x01);
+}
@@ -0,0 +1,82 @@
+use gameboy_rs::gameboy::{GameBoy, InterruptFlag, Model};
+use common::new_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x40] = 0xD9; // reti
+    rom[0x100..0x100 + program.len()].copy_from_slice(program);
+
+    let mut gb = new_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb
+}
+
+fn run_until_halted(gb: &mut GameBoy) {
+    for _ in 0..1000 {
+        if gb.is_idle() { return }
+        gb.tick();
+    }
+    panic!("The cpu never halted");
+}
+
+// Clock cycles from requesting a VBlank to pc reaching the given address
+fn cycles_until_pc(gb: &mut GameBoy, pc: u16) -> u32 {
+    gb.force_interrupt(InterruptFlag::VBlank);
+
+    let mut cycles = 0;
+    while gb.registers().pc != pc {
+        gb.tick();
+        cycles += 1;
+        assert!(cycles < 1000, "pc never got to {:#06X}", pc);
+    }
+    cycles
+}
+
+#[test]
+fn halt_wake_with_ime_set_runs_the_handler() {
+    let mut gb = create_gameboy(&[
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xAF,                   // xor a
+        0xE0, 0x0F,             // ldh (IF), a
+        0xFB,                   // ei
+        0x76,                   // halt
+        0x18, 0xFE              // jr -2
+    ]);
+
+    run_until_halted(&mut gb);
+
+    // the dispatch starts on the cycle the interrupt is seen
+    assert_eq!(cycles_until_pc(&mut gb, 0x40), 20);
+    assert_eq!(gb.read_byte(0xFF0F) & 0x01, 0);
+}
+
+#[test]
+fn halt_wake_with_ime_clear_falls_through() {
+    let mut gb = create_gameboy(&[
+        0xF3,                   // di
+        0x3E, 0x01,             // ld a, 0x01
+        0xE0, 0xFF,             // ldh (IE), a
+        0xAF,                   // xor a
+        0xE0, 0x0F,             // ldh (IF), a
+        0x76,                   // halt
+        0x00,                   // nop
+        0x00,                   // nop
+        0x18, 0xFE              // jr -2
+    ]);
+
+    run_until_halted(&mut gb);
+    let halt_pc = gb.registers().pc;
+
+    // the nop after HALT is fetched on the cycle the interrupt is seen,
+    // then it takes 4 before the second one is fetched
+    assert_eq!(cycles_until_pc(&mut gb, halt_pc + 1), 1);
+    assert_eq!(cycles_until_pc(&mut gb, halt_pc + 2), 4);
+
+    // the interrupt was never taken, it's still waiting in IF
+    assert_eq!(gb.read_byte(0xFF0F) & 0x01, 0x01);
+}
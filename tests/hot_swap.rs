@@ -0,0 +1,65 @@
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Mbc1, NoMbc};
+use common::{rom_with_program, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_cartridge(program: &[u8], ram: Vec<u8>) -> Cartridge {
+    let rom = rom_with_program(program);
+
+    match ram.is_empty() {
+        true => Cartridge::new(rom, ram, Box::new(NoMbc::new()), None),
+        false => Cartridge::new(rom, ram, Box::new(Mbc1::new()), None)
+    }
+}
+
+fn run(gb: &mut GameBoy) {
+    for _ in 0..BOOT_FRAMES + 5 {
+        gb.step_frame().unwrap();
+    }
+}
+
+#[test]
+fn swapped_cartridge_runs_from_reset() {
+    let saving = create_cartridge(&[
+        0x3E, 0x0A,             // ld a, 0x0A
+        0xEA, 0x00, 0x00,       // ld (0x0000), a   enable ram
+        0x3E, 0x42,             // ld a, 0x42
+        0xEA, 0x00, 0xA0,       // ld (0xA000), a
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xFE              // jr -2
+    ], vec![0; 0x2000]);
+
+    let mut gb = GameBoy::from_cartridge(saving, None);
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x42);
+
+    let ram = gb.eject_cartridge();
+    assert_eq!(ram.len(), 0x2000);
+    assert_eq!(ram[0], 0x42);
+
+    // nothing in the slot
+    assert_eq!(gb.read_byte(0x0150), 0xFF);
+
+    let other = create_cartridge(&[
+        0x3E, 0x99,             // ld a, 0x99
+        0xEA, 0x01, 0xC0,       // ld (0xC001), a
+        0x18, 0xFE              // jr -2
+    ], Vec::new());
+
+    gb.insert_cartridge(other);
+    assert_eq!(gb.registers().pc, 0x0000);
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC001), 0x99);
+    assert!(gb.registers().pc >= 0x0100);
+}
+
+#[test]
+fn eject_without_ram_returns_nothing() {
+    let mut gb = GameBoy::from_cartridge(create_cartridge(&[0x18, 0xFE], Vec::new()), None);
+    run(&mut gb);
+
+    assert!(gb.eject_cartridge().is_empty());
+}
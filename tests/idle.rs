@@ -0,0 +1,157 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, CYCLES_PER_SCREEN_DRAW};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const INSTRUCTION_LIMIT: usize = 20;
+
+const COUNTER: u16 = 0xC000;
+
+// Enables the vblank interrupt then waits for it in a jr -2,
+// the handler counts vblanks in COUNTER
+const VBLANK_WAIT: [u8; 13] = [
+    0x21, 0x00, 0xC0,   // ld hl, COUNTER
+    0x3E, 0x01,         // ld a, 0x01
+    0xE0, 0xFF,         // ldh (IE), a
+    0xAF,               // xor a
+    0xE0, 0x0F,         // ldh (IF), a
+    0xFB,               // ei
+    0x18, 0xFE          // jr -2
+];
+
+// The same with the stat interrupt at the start of hblank, which comes a dot later for each
+// pixel SCX scrolls by. So it can come at any point in the loop, depending on SCX.
+fn hblank_wait(scx: u8) -> [u8; 19] {
+    [
+        0x21, 0x00, 0xC0,   // ld hl, COUNTER
+        0x3E, scx,          // ld a, scx
+        0xE0, 0x43,         // ldh (SCX), a
+        0x3E, 0x08,         // ld a, 0x08
+        0xE0, 0x41,         // ldh (STAT), a
+        0x3E, 0x02,         // ld a, 0x02
+        0xE0, 0xFF,         // ldh (IE), a
+        0xFB,               // ei
+        0x00,               // nop
+        0x18, 0xFE          // jr -2
+    ]
+}
+
+// Reads LY over and over, like a loop waiting for a line
+const LY_POLL: [u8; 6] = [
+    0xF0, 0x44,         // ldh a, (LY)
+    0xFE, 0x90,         // cp 144
+    0x18, 0xFA          // jr -6
+];
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x40..0x42].copy_from_slice(&[0x34, 0xD9]); // inc (hl), reti
+    rom[0x48..0x4A].copy_from_slice(&[0x34, 0xD9]); // inc (hl), reti
+    rom[0x100..0x100 + program.len()].copy_from_slice(program);
+
+    let mut gb = new_gameboy(rom);
+    boot(&mut gb);
+
+    gb
+}
+
+// Stops in between instructions
+fn step_until_idle(gb: &mut GameBoy) {
+    for _ in 0..INSTRUCTION_LIMIT {
+        gb.step_instruction().unwrap();
+        if gb.is_idle() { break }
+    }
+
+    assert!(gb.is_idle());
+}
+
+#[test]
+fn jr_to_itself_is_idle() {
+    let mut gb = create_gameboy(&VBLANK_WAIT);
+    // past the vblank handler for the frame that just finished
+    gb.run_cycles(1000).unwrap();
+    step_until_idle(&mut gb);
+    assert_eq!(gb.registers().pc, 0x10B);
+
+    // it stays idle going round the loop
+    for _ in 0..3 {
+        gb.step_instruction().unwrap();
+        assert!(gb.is_idle());
+    }
+}
+
+#[test]
+fn polling_loop_is_not_idle() {
+    let mut gb = create_gameboy(&LY_POLL);
+
+    for _ in 0..INSTRUCTION_LIMIT {
+        gb.step_instruction().unwrap();
+        assert!(!gb.is_idle());
+    }
+}
+
+#[test]
+fn skipping_idle_still_takes_vblank() {
+    let mut gb = create_gameboy(&VBLANK_WAIT);
+    step_until_idle(&mut gb);
+
+    for _ in 0..3 {
+        let count = gb.read_byte(COUNTER);
+
+        // stops at the end of the frame, the interrupt is taken just after
+        assert!(gb.skip_idle() > 0);
+        for _ in 0..INSTRUCTION_LIMIT {
+            if gb.read_byte(COUNTER) != count { break }
+            gb.step_instruction().unwrap();
+        }
+        assert_eq!(gb.read_byte(COUNTER), count.wrapping_add(1));
+
+        // back in the loop after the reti
+        step_until_idle(&mut gb);
+        assert_eq!(gb.registers().pc, 0x10B);
+    }
+}
+
+#[test]
+fn skipping_idle_leaves_the_cpu_out() {
+    let mut gb = create_gameboy(&VBLANK_WAIT);
+    gb.run_cycles(1000).unwrap();
+    step_until_idle(&mut gb);
+
+    let cycles = gb.skip_idle();
+    assert!(cycles > 1000);
+    assert_eq!(gb.frame_metrics().cycles, cycles);
+    assert_eq!(gb.frame_metrics().instructions, 0);
+}
+
+// Through a few interrupts, which each need the cpu caught up to take them
+fn check_skipping_idle_matches_running_normally(program: &[u8], cycles_to_run: u64) {
+    let mut skipped = create_gameboy(program);
+    let mut ran = create_gameboy(program);
+
+    let mut cycles = 0;
+    while cycles < cycles_to_run {
+        cycles += match skipped.skip_idle() {
+            0 => {
+                skipped.tick();
+                1
+            }
+            skipped_cycles => skipped_cycles
+        };
+    }
+
+    ran.run_cycles(cycles).unwrap();
+    assert!(skipped.save_state() == ran.save_state());
+    assert!(skipped.read_byte(COUNTER) > 0);
+    assert_eq!(skipped.read_byte(COUNTER), ran.read_byte(COUNTER));
+}
+
+#[test]
+fn skipping_idle_matches_running_normally() {
+    check_skipping_idle_matches_running_normally(&VBLANK_WAIT, 5 * CYCLES_PER_SCREEN_DRAW);
+    for scx in 0..4 {
+        check_skipping_idle_matches_running_normally(&hblank_wait(scx), CYCLES_PER_SCREEN_DRAW);
+    }
+}
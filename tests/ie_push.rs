@@ -0,0 +1,93 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, CYCLES_PER_SCREEN_DRAW};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// 0x0000 and each interrupt vector write their own address to 0xC000 and then spin
+const VECTORS: [u8; 6] = [0x00, 0x40, 0x48, 0x50, 0x58, 0x60];
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    let mut rom = rom_with_program(program);
+
+    for vector in VECTORS {
+        let addr = vector as usize;
+        rom[addr..addr + 7].copy_from_slice(&[
+            0x3E, vector,           // ld a, vector
+            0xEA, 0x00, 0xC0,       // ld (0xC000), a
+            0x18, 0xFE              // jr -2
+        ]);
+    }
+
+    new_gameboy(rom)
+}
+
+// Requests the interrupts in IF with only those in IE enabled and the stack pointer at SP.
+// The lcd is turned off first so a VBlank can't sneak in.
+fn dispatch_program(ie: u8, requested: u8, sp: u16) -> Vec<u8> {
+    vec![
+        0xF3,                   // di
+        0x3E, 0xAA,             // ld a, 0xAA
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0xAF,                   // xor a
+        0xE0, 0x40,             // ldh (LCDC), a
+        0xE0, 0x0F,             // ldh (IF), a
+        0x3E, ie,               // ld a, ie
+        0xE0, 0xFF,             // ldh (IE), a
+        0x31, sp as u8, (sp >> 8) as u8, // ld sp, sp
+        0x3E, requested,        // ld a, requested
+        0xFB,                   // ei
+        0xE0, 0x0F,             // ldh (IF), a
+        0x18, 0xFE              // jr -2
+    ]
+}
+
+// step_frame never finishes with the lcd off, so this runs a frame's worth of cycles at a time
+fn run(gb: &mut GameBoy) {
+    for _ in 0..BOOT_FRAMES + 5 {
+        gb.run_cycles(CYCLES_PER_SCREEN_DRAW).unwrap();
+    }
+}
+
+#[test]
+fn dispatch_without_ie_write() {
+    let mut gb = create_gameboy(&dispatch_program(0x04, 0x04, 0xD000));
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x50);
+    assert_eq!(gb.read_byte(0xFF0F) & 0x1F, 0);
+}
+
+#[test]
+fn upper_byte_push_cancels_dispatch() {
+    // The upper byte of PC (0x01) lands on IE, which disables the timer interrupt
+    let mut gb = create_gameboy(&dispatch_program(0x04, 0x04, 0x0000));
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x00);
+    assert_eq!(gb.read_byte(0xFFFF), 0x01);
+
+    // a cancelled dispatch leaves the request pending
+    assert_eq!(gb.read_byte(0xFF0F) & 0x1F, 0x04);
+}
+
+#[test]
+fn upper_byte_push_changes_vector() {
+    // IE becomes 0x01, so the VBlank request is taken instead of the timer
+    let mut gb = create_gameboy(&dispatch_program(0x04, 0x05, 0x0000));
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x40);
+    assert_eq!(gb.read_byte(0xFF0F) & 0x1F, 0x04);
+}
+
+#[test]
+fn lower_byte_push_is_too_late() {
+    // The upper byte goes to rom and the lower byte lands on IE after the vector is latched
+    let mut gb = create_gameboy(&dispatch_program(0x04, 0x04, 0x0001));
+
+    run(&mut gb);
+    assert_eq!(gb.read_byte(0xC000), 0x50);
+    assert_ne!(gb.read_byte(0xFFFF), 0x04);
+}
@@ -0,0 +1,60 @@
+use gameboy_rs::gameboy::{Flags, GameBoy};
+use common::cleared_registers_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const INC_A: u8 = 0x3C;
+const DEC_A: u8 = 0x3D;
+const INC_B: u8 = 0x04;
+const DEC_B: u8 = 0x05;
+
+const VALUES: [u8; 5] = [0x00, 0x01, 0x0F, 0x10, 0xFF];
+
+fn expected_flags(val: u8, inc: bool, c: bool) -> Flags {
+    let result = if inc { val.wrapping_add(1) } else { val.wrapping_sub(1) };
+    let h = if inc { val & 0x0F == 0x0F } else { val & 0x0F == 0 };
+
+    Flags { z: result == 0, n: !inc, h, c }
+}
+
+fn check(gb: &mut GameBoy, opcode: u8, set: fn(&mut GameBoy, u8), get: fn(&GameBoy) -> u8, inc: bool) {
+    for &c in &[true, false] {
+        for &val in &VALUES {
+            set(gb, val);
+            // start with the other flags the wrong way round so they have to be written
+            gb.set_flags(Flags { z: inc, n: inc, h: true, c });
+
+            gb.execute_opcode(opcode, 0, 0).unwrap();
+
+            let expected = if inc { val.wrapping_add(1) } else { val.wrapping_sub(1) };
+            assert_eq!(get(gb), expected, "opcode {:#04X} on {:#04X}", opcode, val);
+            assert_eq!(gb.flags(), expected_flags(val, inc, c), "opcode {:#04X} on {:#04X}", opcode, val);
+        }
+    }
+}
+
+#[test]
+fn inc_a_keeps_carry() {
+    let mut gb = cleared_registers_gameboy();
+    check(&mut gb, INC_A, GameBoy::set_a, |gb| gb.registers().a, true);
+}
+
+#[test]
+fn dec_a_keeps_carry() {
+    let mut gb = cleared_registers_gameboy();
+    check(&mut gb, DEC_A, GameBoy::set_a, |gb| gb.registers().a, false);
+}
+
+#[test]
+fn inc_b_keeps_carry() {
+    let mut gb = cleared_registers_gameboy();
+    check(&mut gb, INC_B, GameBoy::set_b, |gb| gb.registers().b, true);
+}
+
+#[test]
+fn dec_b_keeps_carry() {
+    let mut gb = cleared_registers_gameboy();
+    check(&mut gb, DEC_B, GameBoy::set_b, |gb| gb.registers().b, false);
+}
@@ -0,0 +1,76 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::new_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const ADDR: u16 = 0xC000;
+
+const FLAG_Z: u8 = 0b1000_0000;
+const FLAG_N: u8 = 0b0100_0000;
+const FLAG_H: u8 = 0b0010_0000;
+const FLAG_C: u8 = 0b0001_0000;
+
+// Runs the opcode at 0x100 with HL pointing at ADDR, which holds val.
+// C starts off set to check it's left alone.
+fn create_gameboy(opcode: u8, val: u8) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100] = opcode;
+    rom[0x101..0x103].copy_from_slice(&[0x18, 0xFE]); // jr -2
+
+    let mut gb = new_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+
+    let mut registers = gb.registers();
+    registers.h = (ADDR >> 8) as u8;
+    registers.l = ADDR as u8;
+    registers.f = FLAG_C;
+    gb.set_registers(registers);
+    gb.write_byte(ADDR, val);
+
+    gb
+}
+
+fn run_opcode(opcode: u8, val: u8) -> (u8, u8) {
+    let mut gb = create_gameboy(opcode, val);
+    gb.step_instruction().unwrap();
+
+    (gb.read_byte(ADDR), gb.registers().f)
+}
+
+// Clock cycles until the opcode has run and the next instruction has been fetched
+fn cycles_until_next_fetch(opcode: u8) -> u64 {
+    let mut gb = create_gameboy(opcode, 0);
+
+    let mut cycles = 0;
+    while gb.registers().pc != 0x102 {
+        gb.tick();
+        cycles += 1;
+    }
+
+    cycles
+}
+
+#[test]
+fn inc_hl_half_carry() {
+    assert_eq!(run_opcode(0x34, 0x0F), (0x10, FLAG_H | FLAG_C));
+    assert_eq!(run_opcode(0x34, 0x10), (0x11, FLAG_C));
+    assert_eq!(run_opcode(0x34, 0xFF), (0x00, FLAG_Z | FLAG_H | FLAG_C));
+}
+
+#[test]
+fn dec_hl_half_carry() {
+    assert_eq!(run_opcode(0x35, 0x10), (0x0F, FLAG_N | FLAG_H | FLAG_C));
+    assert_eq!(run_opcode(0x35, 0x0F), (0x0E, FLAG_N | FLAG_C));
+    assert_eq!(run_opcode(0x35, 0x01), (0x00, FLAG_Z | FLAG_N | FLAG_C));
+}
+
+#[test]
+fn inc_dec_hl_take_3_machine_cycles() {
+    // a nop is 1 machine cycle
+    let nop = cycles_until_next_fetch(0x00);
+
+    assert_eq!(cycles_until_next_fetch(0x34) - nop, 2 * 4);
+    assert_eq!(cycles_until_next_fetch(0x35) - nop, 2 * 4);
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use gameboy_rs::gameboy::input::{Button, HostKey, Input};
+use sdl2::keyboard::Keycode;
+
+extern crate gameboy_rs;
+
+// bit 4 low = direction keys, bit 5 low = action buttons
+const SELECT_DIRECTIONS: u8 = 0b0010_0000;
+const SELECT_ACTIONS: u8 = 0b0001_0000;
+const SELECT_BOTH: u8 = 0b0000_0000;
+const SELECT_NONE: u8 = 0b0011_0000;
+
+#[test]
+fn joyp_nothing_pressed() {
+    let mut input = Input::new();
+
+    input.set_column_line(SELECT_DIRECTIONS);
+    assert_eq!(input.read_joyp(), 0b1110_1111);
+
+    input.set_column_line(SELECT_ACTIONS);
+    assert_eq!(input.read_joyp(), 0b1101_1111);
+
+    input.set_column_line(SELECT_BOTH);
+    assert_eq!(input.read_joyp(), 0b1100_1111);
+
+    input.set_column_line(SELECT_NONE);
+    assert_eq!(input.read_joyp(), 0b1111_1111);
+}
+
+#[test]
+fn joyp_direction_column() {
+    let mut input = Input::new();
+    input.key_down(Keycode::D); // right
+    input.key_down(Keycode::S); // down
+    input.key_down(Keycode::O); // a
+
+    input.set_column_line(SELECT_DIRECTIONS);
+    assert_eq!(input.read_joyp(), 0b1110_0110);
+}
+
+#[test]
+fn joyp_action_column() {
+    let mut input = Input::new();
+    input.key_down(Keycode::K); // b
+    input.key_down(Keycode::M); // start
+    input.key_down(Keycode::W); // up
+
+    input.set_column_line(SELECT_ACTIONS);
+    assert_eq!(input.read_joyp(), 0b1101_0101);
+}
+
+#[test]
+fn joyp_both_columns() {
+    let mut input = Input::new();
+    input.key_down(Keycode::A); // left
+    input.key_down(Keycode::N); // select
+
+    input.set_column_line(SELECT_BOTH);
+    assert_eq!(input.read_joyp(), 0b1100_1001);
+}
+
+#[test]
+fn joyp_unselected_column_reads_released() {
+    let mut input = Input::new();
+    input.key_down(Keycode::W);
+    input.key_down(Keycode::A);
+    input.key_down(Keycode::S);
+    input.key_down(Keycode::D);
+    input.key_down(Keycode::O);
+    input.key_down(Keycode::K);
+    input.key_down(Keycode::N);
+    input.key_down(Keycode::M);
+
+    input.set_column_line(SELECT_NONE);
+    assert_eq!(input.read_joyp() & 0x0F, 0x0F);
+
+    input.set_column_line(SELECT_DIRECTIONS);
+    assert_eq!(input.read_joyp() & 0x0F, 0x00);
+
+    input.key_up(Keycode::D);
+    assert_eq!(input.read_joyp() & 0x0F, 0x01);
+}
+
+#[test]
+fn custom_mapping() {
+    let mut mapping = HashMap::new();
+    mapping.insert(HostKey::from(Keycode::Up), Button::Up);
+    mapping.insert(HostKey::Gamepad(0), Button::A);
+    mapping.insert(HostKey::Gamepad(7), Button::Start);
+
+    let mut input = Input::new();
+    input.set_mapping(mapping);
+
+    assert_eq!(input.host_key_down(HostKey::Gamepad(0)), Some(Button::A));
+    assert_eq!(input.host_key_down(HostKey::Gamepad(7)), Some(Button::Start));
+    assert_eq!(input.host_key_down(HostKey::from(Keycode::Up)), Some(Button::Up));
+
+    // the default keys aren't bound any more
+    assert_eq!(input.host_key_down(HostKey::from(Keycode::S)), None);
+    assert!(!input.key_down(Keycode::K));
+
+    input.set_column_line(SELECT_ACTIONS);
+    assert_eq!(input.read_joyp(), 0b1101_0110);
+
+    input.set_column_line(SELECT_DIRECTIONS);
+    assert_eq!(input.read_joyp(), 0b1110_1011);
+
+    assert_eq!(input.host_key_up(HostKey::Gamepad(0)), Some(Button::A));
+    input.set_column_line(SELECT_ACTIONS);
+    assert_eq!(input.read_joyp(), 0b1101_0111);
+}
+
+#[test]
+fn press_and_release_button() {
+    let mut input = Input::new();
+    input.press_button(Button::Select);
+    input.press_button(Button::Left);
+
+    input.set_column_line(SELECT_BOTH);
+    assert_eq!(input.read_joyp(), 0b1100_1001);
+
+    input.release_button(Button::Left);
+    assert_eq!(input.read_joyp(), 0b1100_1011);
+}
+
+#[test]
+fn joyp_column_select_persists_across_reads() {
+    let mut input = Input::new();
+
+    // only the select bits are kept from a write
+    input.set_column_line(SELECT_ACTIONS | 0b1100_1111);
+    assert_eq!(input.read_joyp(), 0b1101_1111);
+
+    input.press_button(Button::Start);
+    assert_eq!(input.read_joyp(), 0b1101_0111);
+
+    // directions aren't selected so don't show up
+    input.press_button(Button::Up);
+    assert_eq!(input.read_joyp(), 0b1101_0111);
+
+    input.press_button(Button::A);
+    assert_eq!(input.read_joyp(), 0b1101_0110);
+
+    input.release_button(Button::Start);
+    input.release_button(Button::A);
+    assert_eq!(input.read_joyp(), 0b1101_1111);
+
+    // until it's rewritten
+    input.set_column_line(SELECT_DIRECTIONS);
+    assert_eq!(input.read_joyp(), 0b1110_1011);
+    assert_eq!(input.read_joyp(), 0b1110_1011);
+}
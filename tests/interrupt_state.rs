@@ -0,0 +1,92 @@
+use gameboy_rs::gameboy::{GameBoy, INTERRUPT_VECTORS, InterruptFlag, InterruptState};
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const INSTRUCTION_LIMIT: usize = 10;
+
+// The lcd is turned off and the timer is never started, so nothing but
+// force_interrupt requests an interrupt
+fn create_gameboy(ei: bool) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&[
+        if ei { 0xFB } else { 0xF3 }, // ei / di
+        0x18, 0xFE              // jr -2
+    ]));
+    boot(&mut gb);
+
+    gb.write_byte(0xFF40, 0x00);
+    gb.write_byte(0xFF0F, 0x00);
+    gb.write_byte(0xFFFF, 0x00);
+
+    gb
+}
+
+#[test]
+fn state_reflects_registers() {
+    let mut gb = create_gameboy(true);
+    assert_eq!(gb.interrupt_state(), InterruptState { ime: true, enable: 0, flags: 0 });
+
+    gb.write_byte(0xFFFF, 0b0001_0101);
+    gb.force_interrupt(InterruptFlag::Stat);
+    gb.force_interrupt(InterruptFlag::Serial);
+    assert_eq!(gb.interrupt_state(), InterruptState { ime: true, enable: 0b0001_0101, flags: 0b0000_1010 });
+}
+
+#[test]
+fn forced_vblank_is_dispatched() {
+    let mut gb = create_gameboy(true);
+    gb.write_byte(0xFFFF, InterruptFlag::VBlank as u8);
+    gb.force_interrupt(InterruptFlag::VBlank);
+
+    for _ in 0..INSTRUCTION_LIMIT {
+        gb.step_instruction().unwrap();
+        if gb.registers().pc == 0x40 { break }
+    }
+
+    assert_eq!(gb.registers().pc, 0x40);
+    assert_eq!(gb.interrupt_state(), InterruptState { ime: false, enable: 0x01, flags: 0 });
+}
+
+#[test]
+fn forced_interrupt_waits_for_ime() {
+    let mut gb = create_gameboy(false);
+    gb.write_byte(0xFFFF, InterruptFlag::VBlank as u8);
+    gb.force_interrupt(InterruptFlag::VBlank);
+
+    for _ in 0..INSTRUCTION_LIMIT {
+        gb.step_instruction().unwrap();
+        assert_eq!(gb.registers().pc, 0x101);
+    }
+
+    assert_eq!(gb.interrupt_state(), InterruptState { ime: false, enable: 0x01, flags: 0x01 });
+}
+
+#[test]
+fn vectors_are_in_priority_order() {
+    let vectors: Vec<u16> = INTERRUPT_VECTORS.iter().map(|(_, vector)| *vector).collect();
+    assert_eq!(vectors, vec![0x40, 0x48, 0x50, 0x58, 0x60]);
+    assert_eq!(INTERRUPT_VECTORS[2].0, InterruptFlag::Timer);
+}
+
+#[test]
+fn dispatch_pushes_pc_and_jumps() {
+    let mut gb = create_gameboy(true);
+    gb.force_interrupt(InterruptFlag::Timer);
+    gb.force_interrupt(InterruptFlag::Serial);
+
+    // so the registers aren't read part way through the jr
+    gb.step_instruction().unwrap();
+    let before = gb.registers();
+    assert_eq!(gb.dispatch_interrupt(InterruptFlag::Timer), 0x50);
+
+    let after = gb.registers();
+    assert_eq!(after.pc, 0x50);
+    assert_eq!(after.sp, before.sp - 2);
+    assert_eq!(gb.read_byte(after.sp), (before.pc & 0xFF) as u8);
+    assert_eq!(gb.read_byte(after.sp + 1), (before.pc >> 8) as u8);
+
+    // only the dispatched interrupt is cleared
+    assert_eq!(gb.interrupt_state(), InterruptState { ime: false, enable: 0, flags: InterruptFlag::Serial as u8 });
+}
@@ -0,0 +1,47 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, CYCLES_PER_SCREEN_DRAW};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+#[test]
+fn vram_and_oam_are_accessible_with_lcd_off() {
+    let mut gb = create_gameboy(&[
+        // wait for mode 3
+        0xF0, 0x41,             // ldh a, (STAT)
+        0xE6, 0x03,             // and 0x03
+        0xFE, 0x03,             // cp 0x03
+        0x20, 0xF8,             // jr nz, -8
+        // blocked, the ppu is drawing
+        0x3E, 0x77,             // ld a, 0x77
+        0xEA, 0x01, 0x80,       // ld (0x8001), a
+        // turn the lcd off while still in mode 3
+        0x3E, 0x11,             // ld a, 0x11
+        0xE0, 0x40,             // ldh (LCDC), a
+        0x3E, 0x5A,             // ld a, 0x5A
+        0xEA, 0x00, 0x80,       // ld (0x8000), a
+        0xFA, 0x00, 0x80,       // ld a, (0x8000)
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x3E, 0x3C,             // ld a, 0x3C
+        0xEA, 0x00, 0xFE,       // ld (0xFE00), a
+        0xFA, 0x00, 0xFE,       // ld a, (0xFE00)
+        0xEA, 0x01, 0xC0,       // ld (0xC001), a
+        0xFA, 0x01, 0x80,       // ld a, (0x8001)
+        0xEA, 0x02, 0xC0,       // ld (0xC002), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    for _ in 0..BOOT_FRAMES + 2 {
+        gb.run_cycles(CYCLES_PER_SCREEN_DRAW).unwrap();
+    }
+
+    assert_eq!(gb.read_byte(0xC000), 0x5A);
+    assert_eq!(gb.read_byte(0xC001), 0x3C);
+    assert_eq!(gb.read_byte(0xC002), 0x00);
+    assert_eq!(gb.dump_vram()[0], 0x5A);
+}
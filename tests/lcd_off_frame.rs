@@ -0,0 +1,69 @@
+use gameboy_rs::gameboy::{FrameStatus, GameBoy};
+use common::{boot, new_gameboy, rom_with_program, CYCLES_PER_SCREEN_DRAW, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const VBLANK_IF: u8 = 1 << 0;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    gb
+}
+
+// Interrupts are never enabled, so the request just sits in IF
+fn take_vblank_interrupt(gb: &mut GameBoy) -> bool {
+    let flags = gb.read_byte(0xFF0F);
+    gb.write_byte(0xFF0F, flags & !VBLANK_IF);
+    flags & VBLANK_IF != 0
+}
+
+#[test]
+fn lcd_off_frames_are_white() {
+    let mut gb = create_gameboy();
+    // the boot logo is still on screen
+    assert!(gb.get_frame_buffer().iter().any(|px| *px != 255));
+
+    gb.write_byte(0xFF40, 0x00);
+    take_vblank_interrupt(&mut gb);
+
+    for _ in 0..5 {
+        assert_eq!(gb.step_frame().unwrap(), FrameStatus::Completed);
+        assert_eq!(gb.frame_metrics().cycles, CYCLES_PER_SCREEN_DRAW);
+        assert_eq!(gb.frame_metrics().ppu_dots, 0);
+
+        assert!(gb.get_frame_buffer().iter().all(|px| *px == 255));
+        assert!(!take_vblank_interrupt(&mut gb));
+    }
+}
+
+#[test]
+fn lcd_off_frames_blend_to_white() {
+    let mut gb = create_gameboy();
+    gb.set_frame_blend(true);
+    gb.step_frame().unwrap();
+
+    gb.write_byte(0xFF40, 0x00);
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+
+    assert!(gb.get_frame_buffer().iter().all(|px| *px == 255));
+}
+
+#[test]
+fn vblank_comes_back_with_the_lcd() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(0xFF40, 0x00);
+    gb.step_frame().unwrap();
+    take_vblank_interrupt(&mut gb);
+
+    // the frame is finished just before the interrupt is requested
+    gb.write_byte(0xFF40, 0x91);
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+    assert!(take_vblank_interrupt(&mut gb));
+}
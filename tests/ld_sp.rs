@@ -0,0 +1,57 @@
+use gameboy_rs::gameboy::{GameBoy, Model, Registers};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const CYCLE_LIMIT: u64 = 40;
+
+// Runs ld (0xC000), sp at 0x100 with SP at 0x1234 and 0xAA in the 2 bytes it writes to
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&[
+        0x08, 0x00, 0xC0,       // ld (0xC000), sp
+        0x18, 0xFE              // jr -2
+    ]));
+    gb.skip_boot_rom(Model::Dmg);
+    gb.set_registers(Registers { sp: 0x1234, pc: 0x100, ..Registers::default() });
+    gb.write_byte(0xC000, 0xAA);
+    gb.write_byte(0xC001, 0xAA);
+    gb
+}
+
+#[test]
+fn ld_u16_sp_writes_low_byte_first() {
+    let mut gb = create_gameboy();
+    gb.step_instruction().unwrap();
+
+    assert_eq!(gb.read_byte(0xC000), 0x34);
+    assert_eq!(gb.read_byte(0xC001), 0x12);
+    assert_eq!(gb.registers().pc, 0x103);
+    assert_eq!(gb.registers().sp, 0x1234);
+}
+
+#[test]
+fn ld_u16_sp_writes_in_last_2_cycles() {
+    let mut gb = create_gameboy();
+    let mut low = 0;
+    let mut high = 0;
+
+    for cycle in 1..=CYCLE_LIMIT {
+        gb.tick();
+        let machine_cycle = (cycle + 3) / 4;
+
+        if low == 0 && gb.read_byte(0xC000) != 0xAA { low = machine_cycle }
+        if high == 0 && gb.read_byte(0xC001) != 0xAA { high = machine_cycle }
+    }
+
+    assert_eq!((low, high), (4, 5));
+}
+
+#[test]
+fn ld_u16_sp_takes_5_cycles() {
+    let mut gb = create_gameboy();
+    assert_eq!(gb.execute_opcode(0x08, 0, 0xC000), Ok(5));
+    assert_eq!(gb.read_byte(0xC000), 0x34);
+    assert_eq!(gb.read_byte(0xC001), 0x12);
+}
@@ -0,0 +1,176 @@
+use std::{thread, time::{Duration, Instant}};
+
+use gameboy_rs::gameboy::{ChannelCable, GameBoy, LinkCable, LinkMessage};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const FRAME_LIMIT: usize = 1000;
+
+// Keeps sending 0x42 with the internal clock until the other side answers with 0x99,
+// then stores what it got at 0xC000
+const CLOCKING_PROGRAM: &[u8] = &[
+    0x3E, 0x42,             // ld a, 0x42
+    0xE0, 0x01,             // ldh (SB), a
+    0x3E, 0x81,             // ld a, 0x81
+    0xE0, 0x02,             // ldh (SC), a
+    0xF0, 0x02,             // ldh a, (SC)
+    0xCB, 0x7F,             // bit 7, a
+    0x20, 0xFA,             // jr nz, -6
+    0xF0, 0x01,             // ldh a, (SB)
+    0xFE, 0x99,             // cp 0x99
+    0x20, 0xEC,             // jr nz, -20
+    0xEA, 0x00, 0xC0,       // ld (0xC000), a
+    0x18, 0xFE              // jr -2
+];
+
+// Waits for the other side to clock 0x99 out, then stores what it got at 0xC000
+const EXTERNAL_CLOCK_PROGRAM: &[u8] = &[
+    0x3E, 0x99,             // ld a, 0x99
+    0xE0, 0x01,             // ldh (SB), a
+    0x3E, 0x80,             // ld a, 0x80
+    0xE0, 0x02,             // ldh (SC), a
+    0xF0, 0x02,             // ldh a, (SC)
+    0xCB, 0x7F,             // bit 7, a
+    0x20, 0xFA,             // jr nz, -6
+    0xF0, 0x01,             // ldh a, (SB)
+    0xEA, 0x00, 0xC0,       // ld (0xC000), a
+    0x18, 0xFE              // jr -2
+];
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+// Each emulator runs on its own thread until it has stored the byte it received
+fn run_linked<C: LinkCable + Send + 'static>(program: &'static [u8], cable: C) -> thread::JoinHandle<u8> {
+    thread::spawn(move || {
+        let mut gb = create_gameboy(program);
+        gb.connect_link_cable(Box::new(cable));
+
+        for _ in 0..FRAME_LIMIT {
+            gb.step_frame().unwrap();
+
+            let received = gb.read_byte(0xC000);
+            if received != 0 { return received }
+        }
+
+        0
+    })
+}
+
+fn exchange_bytes<C: LinkCable + Send + 'static>(a: C, b: C) {
+    let clocking = run_linked(CLOCKING_PROGRAM, a);
+    let external = run_linked(EXTERNAL_CLOCK_PROGRAM, b);
+
+    assert_eq!(clocking.join().unwrap(), 0x99);
+    assert_eq!(external.join().unwrap(), 0x42);
+}
+
+#[test]
+fn linked_emulators_exchange_bytes() {
+    let (a, b) = ChannelCable::pair();
+    exchange_bytes(a, b);
+}
+
+#[test]
+fn unlinked_transfer_receives_0xff() {
+    // the external clock side never gets clocked, the internal side shifts in 1s
+    let mut gb = create_gameboy(&[
+        0x3E, 0x42,             // ld a, 0x42
+        0xE0, 0x01,             // ldh (SB), a
+        0x3E, 0x81,             // ld a, 0x81
+        0xE0, 0x02,             // ldh (SC), a
+        0x18, 0xFE              // jr -2
+    ]);
+
+    for _ in 0..125 {
+        gb.step_frame().unwrap();
+    }
+
+    assert_eq!(gb.read_byte(0xFF01), 0xFF);
+    assert_eq!(gb.read_byte(0xFF02), 0x7F);
+    assert!(gb.read_byte(0xFF0F) & 0b0000_1000 != 0);
+}
+
+// Sends 0x42 with the internal clock once, then spins
+const SEND_ONCE_PROGRAM: &[u8] = &[
+    0x3E, 0x42,             // ld a, 0x42
+    0xE0, 0x01,             // ldh (SB), a
+    0x3E, 0x81,             // ld a, 0x81
+    0xE0, 0x02,             // ldh (SC), a
+    0x18, 0xFE              // jr -2
+];
+
+#[test]
+fn waiting_for_a_reply_doesnt_block() {
+    let (a, _b) = ChannelCable::pair();
+    let mut gb = create_gameboy(SEND_ONCE_PROGRAM);
+    gb.connect_link_cable(Box::new(a));
+
+    // the other side is plugged in but never answers
+    let start = Instant::now();
+    for _ in 0..125 {
+        gb.step_frame().unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(900));
+
+    assert_eq!(gb.read_byte(0xFF02) & 0b1000_0000, 0b1000_0000);
+    assert_eq!(gb.read_byte(0xFF0F) & 0b0000_1000, 0);
+
+    // until it gives up on the cable
+    thread::sleep(Duration::from_secs(1));
+    gb.step_frame().unwrap();
+
+    assert_eq!(gb.read_byte(0xFF01), 0xFF);
+    assert_eq!(gb.read_byte(0xFF02), 0x7F);
+}
+
+#[test]
+fn stale_replies_are_ignored() {
+    let (a, mut b) = ChannelCable::pair();
+    let mut gb = create_gameboy(SEND_ONCE_PROGRAM);
+    gb.connect_link_cable(Box::new(a));
+
+    let mut transfer = None;
+    for _ in 0..125 {
+        gb.step_frame().unwrap();
+
+        transfer = b.receive();
+        if transfer.is_some() { break }
+    }
+
+    let id = match transfer {
+        Some(LinkMessage::Transfer { id, val }) => {
+            assert_eq!(val, 0x42);
+            id
+        }
+        message => panic!("Expected a transfer, got {:?}", message)
+    };
+
+    // an answer to an earlier transfer, then the real one
+    b.send(LinkMessage::Reply { id: id.wrapping_sub(1), val: 0x11 });
+    b.send(LinkMessage::Reply { id, val: 0x99 });
+
+    for _ in 0..2 {
+        gb.step_frame().unwrap();
+    }
+
+    assert_eq!(gb.read_byte(0xFF01), 0x99);
+    assert_eq!(gb.read_byte(0xFF02), 0x7F);
+}
+
+#[cfg(feature = "link-tcp")]
+#[test]
+fn linked_over_tcp_exchange_bytes() {
+    use std::net::{TcpListener, TcpStream};
+    use gameboy_rs::gameboy::link_tcp::TcpCable;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+
+    exchange_bytes(TcpCable::from_stream(client).unwrap(), TcpCable::from_stream(server).unwrap());
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "log")]
+
+use std::sync::{Mutex, Once};
+
+use gameboy_rs::gameboy::GameBoy;
+use common::{load_gameboy, rom_with_program, SPIN};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Keeps everything that gets logged, tests run in parallel so they look for their own messages
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String, String)>>
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records.lock().unwrap().push((record.level(), record.target().to_owned(), record.args().to_string()));
+    }
+
+    fn flush(&self) { }
+}
+
+static LOGGER: CapturingLogger = CapturingLogger { records: Mutex::new(Vec::new()) };
+static INIT: Once = Once::new();
+
+fn logged(level: Level, target: &str, message: &str) -> bool {
+    LOGGER.records.lock().unwrap().iter()
+        .any(|(l, t, m)| *l == level && t == target && m == message)
+}
+
+fn create_gameboy(cartridge_type: u8) -> GameBoy {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    let mut rom = rom_with_program(&SPIN);
+    rom[0x147] = cartridge_type;
+
+    load_gameboy(rom)
+}
+
+#[test]
+fn cartridge_type_logged() {
+    let _gb = create_gameboy(0x01);
+    assert!(logged(Level::Info, "gameboy_rs::cartridge", "MBC1 cart created!"));
+}
+
+// The read only write checks are compiled out of release builds
+#[cfg(debug_assertions)]
+#[test]
+fn read_only_write_logs_warning() {
+    let mut gb = create_gameboy(0x00);
+    gb.set_trap_read_only_writes(true);
+
+    gb.write_byte(0xFF44, 0x42);
+    assert!(logged(Level::Warn, "gameboy_rs::memory", "Ignored write of 0x42 to read only LY (0xFF44)"));
+}
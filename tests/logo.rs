@@ -0,0 +1,81 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+fn create_gameboy(logo: &[u8; 48]) -> GameBoy {
+    let mut rom = rom_with_program(&SPIN);
+    rom[0x104..0x134].copy_from_slice(logo);
+
+    new_gameboy(rom)
+}
+
+// Each bit of the logo gets doubled up, and each row of the tile drawn twice
+fn scaled_logo_tiles(logo: &[u8]) -> Vec<u8> {
+    let mut tiles = Vec::new();
+
+    for byte in logo {
+        for nibble in [byte >> 4, byte & 0x0F] {
+            let mut row = 0;
+            for bit in (0..4).rev() {
+                let set = (nibble >> bit) & 1;
+                row = (row << 2) | (set << 1) | set;
+            }
+
+            tiles.extend_from_slice(&[row, 0, row, 0]);
+        }
+    }
+
+    tiles
+}
+
+#[test]
+fn logo_reads_back_unchanged() {
+    let gb = create_gameboy(&NINTENDO_LOGO);
+
+    // the boot rom only covers 0x0000 - 0x00FF, so the logo is the cartridge's even while it runs
+    let logo: Vec<u8> = (0x104..0x134).map(|addr| gb.read_byte(addr)).collect();
+    assert_eq!(logo, NINTENDO_LOGO);
+    assert!(gb.has_nintendo_logo());
+}
+
+#[test]
+fn altered_logo_reads_back_unchanged() {
+    let mut logo = NINTENDO_LOGO;
+    logo[0] = 0x00;
+    logo[47] ^= 0xFF;
+
+    let gb = create_gameboy(&logo);
+
+    let read: Vec<u8> = (0x104..0x134).map(|addr| gb.read_byte(addr)).collect();
+    assert_eq!(read, logo);
+    assert!(!gb.has_nintendo_logo());
+}
+
+#[test]
+fn boot_rom_sees_cartridge_logo() {
+    let mut logo = NINTENDO_LOGO;
+    logo[5] = 0xA5;
+
+    for logo in [NINTENDO_LOGO, logo] {
+        let mut gb = create_gameboy(&logo);
+        for _ in 0..BOOT_FRAMES {
+            gb.step_frame().unwrap();
+        }
+
+        // the boot rom scales up the logo it read from the header into the tiles from 0x8010
+        let vram = gb.dump_vram();
+        assert_eq!(vram[0x10..0x10 + 48 * 8], scaled_logo_tiles(&logo));
+
+        // and boots either way, this boot rom doesn't lock up on a bad logo
+        assert!(gb.registers().pc >= 0x100);
+    }
+}
@@ -0,0 +1,68 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LY: u16 = 0xFF44;
+const LYC: u16 = 0xFF45;
+const STAT: u16 = 0xFF41;
+
+fn tick_until_ly(gb: &mut GameBoy, ly: u8) {
+    for _ in 0..456 * 154 {
+        if gb.read_byte(LY) == ly { return }
+        gb.tick();
+    }
+    panic!("LY never got to {}", ly);
+}
+
+// How many cycles LY reads the same value for
+fn cycles_at_ly(gb: &mut GameBoy, ly: u8) -> u32 {
+    let mut cycles = 0;
+    while gb.read_byte(LY) == ly {
+        gb.tick();
+        cycles += 1;
+    }
+    cycles
+}
+
+#[test]
+fn ly_153_only_lasts_4_cycles() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    tick_until_ly(&mut gb, 152);
+    tick_until_ly(&mut gb, 153);
+
+    assert_eq!(cycles_at_ly(&mut gb, 153), 4);
+
+    // 0 for the rest of line 153 and then all of line 0
+    assert_eq!(gb.read_byte(LY), 0);
+    assert_eq!(cycles_at_ly(&mut gb, 0), 452 + 456);
+    assert_eq!(gb.read_byte(LY), 1);
+}
+
+#[test]
+fn ly_reads_0_early_in_vblank() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.write_byte(LYC, 0);
+    tick_until_ly(&mut gb, 152);
+    tick_until_ly(&mut gb, 153);
+    cycles_at_ly(&mut gb, 153);
+
+    // still in VBlank on line 153, but LY and the LYC comparison have moved on to 0
+    assert_eq!(gb.read_byte(LY), 0);
+    assert_eq!(gb.read_byte(STAT) & 0b111, 0b101);
+}
+
+#[test]
+fn lyc_153_matches_during_the_short_window() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.write_byte(LYC, 153);
+    tick_until_ly(&mut gb, 152);
+    tick_until_ly(&mut gb, 153);
+
+    assert_eq!(gb.read_byte(STAT) & 0b100, 0b100);
+
+    cycles_at_ly(&mut gb, 153);
+    assert_eq!(gb.read_byte(STAT) & 0b100, 0);
+}
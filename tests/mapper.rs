@@ -0,0 +1,117 @@
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Mapper, Mbc1, NoMbc, save_state::{StateReader, StateWriter}};
+use common::BOOT_FRAMES;
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Every byte of a rom bank is the bank's number
+fn banked_rom(num_banks: usize) -> Vec<u8> {
+    let mut rom = vec![0; 0x4000 * num_banks];
+    for (i, val) in rom.iter_mut().enumerate() {
+        *val = (i / 0x4000) as u8;
+    }
+    rom
+}
+
+#[test]
+fn no_mbc_maps_rom_directly() {
+    let mut mapper = NoMbc::new();
+    let rom = banked_rom(2);
+
+    assert_eq!(mapper.read_rom(&rom, 0x0000), 0);
+    assert_eq!(mapper.read_rom(&rom, 0x7FFF), 1);
+
+    // nothing to switch
+    mapper.write_control(0x2000, 0x01);
+    assert_eq!(mapper.read_rom(&rom, 0x4000), 1);
+}
+
+#[test]
+fn mbc1_switches_rom_banks() {
+    let mut cartridge = Cartridge::new(banked_rom(8), Vec::new(), Box::new(Mbc1::new()), None);
+
+    assert_eq!(cartridge.read_rom(0x0000), 0);
+    assert_eq!(cartridge.read_rom(0x4000), 1);
+
+    cartridge.write_rom(0x2000, 0x05);
+    assert_eq!(cartridge.read_rom(0x0000), 0);
+    assert_eq!(cartridge.read_rom(0x7FFF), 5);
+
+    // bank 0 can't be mapped to 0x4000
+    cartridge.write_rom(0x2000, 0x00);
+    assert_eq!(cartridge.read_rom(0x4000), 1);
+}
+
+#[test]
+fn mbc1_ram_needs_enabling() {
+    let mut cartridge = Cartridge::new(banked_rom(2), vec![0; 0x2000], Box::new(Mbc1::new()), None);
+
+    cartridge.write_ram(0x0010, 0x42);
+    assert_eq!(cartridge.read_ram(0x0010), 0xFF);
+
+    cartridge.write_rom(0x0000, 0x0A);
+    cartridge.write_ram(0x0010, 0x42);
+    assert_eq!(cartridge.read_ram(0x0010), 0x42);
+
+    cartridge.write_rom(0x0000, 0x00);
+    assert_eq!(cartridge.read_ram(0x0010), 0xFF);
+}
+
+// Writing anywhere in 0x0000 - 0x7FFF selects that bank, there's no ram
+struct AnyAddressMapper {
+    bank: usize
+}
+
+impl Mapper for AnyAddressMapper {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => rom[addr as usize],
+            _ => rom[self.bank * 0x4000 + (addr - 0x4000) as usize]
+        }
+    }
+
+    fn write_control(&mut self, _addr: u16, value: u8) {
+        self.bank = (value as usize).max(1);
+    }
+
+    fn read_ram(&self, _ram: &[u8], _addr: u16) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _ram: &mut [u8], _addr: u16, _value: u8) {
+
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.bank as u8);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) {
+        self.bank = state.read_u8() as usize;
+    }
+}
+
+#[test]
+fn custom_mapper_runs_in_gameboy() {
+    let mut rom = banked_rom(4);
+    rom[0x100..0x10A].copy_from_slice(&[
+        0x3E, 0x03,             // ld a, 0x03
+        0xEA, 0x00, 0x70,       // ld (0x7000), a
+        0xFA, 0x00, 0x40,       // ld a, (0x4000)
+        0x18, 0xFE              // jr -2
+    ]);
+    rom[0x147] = 0x00;
+    rom[0x148] = 0x01;
+    rom[0x149] = 0x00;
+
+    let cartridge = Cartridge::new(rom, Vec::new(), Box::new(AnyAddressMapper { bank: 1 }), None);
+    let mut gb = GameBoy::from_cartridge(cartridge, None);
+
+    for _ in 0..BOOT_FRAMES + 1 {
+        gb.step_frame().unwrap();
+    }
+
+    assert_eq!(gb.registers().a, 3);
+    assert_eq!(gb.read_byte(0x4000), 3);
+}
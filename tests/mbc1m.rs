@@ -0,0 +1,88 @@
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Mbc1};
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NUM_ROM_BANKS: usize = 64;
+
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+// A 1MB rom where byte 0x200 of every bank is the bank's number, clear of the boot rom and header.
+// A multicart has 4 games of 16 banks each, with a header at the start of every game.
+fn create_rom(multicart: bool) -> Vec<u8> {
+    let mut rom = vec![0; 0x4000 * NUM_ROM_BANKS];
+    for bank in 0..NUM_ROM_BANKS {
+        rom[bank * 0x4000 + 0x200] = bank as u8;
+    }
+
+    let num_games = if multicart { 4 } else { 1 };
+    for game in 0..num_games {
+        let header = game * 16 * 0x4000;
+        rom[header + 0x104..header + 0x134].copy_from_slice(&NINTENDO_LOGO);
+        rom[header + 0x147] = 0x01; // MBC1
+        rom[header + 0x148] = 0x05; // 64 banks
+        rom[header + 0x149] = 0x00;
+    }
+
+    rom
+}
+
+// Nothing is run, the cartridge is only poked at through the memory bus
+fn create_gameboy(multicart: bool) -> GameBoy {
+    load_gameboy(create_rom(multicart))
+}
+
+#[test]
+fn multicart_bank2_selects_game() {
+    let mut gb = create_gameboy(true);
+
+    // game 2, bank 3 of it
+    gb.write_byte(0x4000, 0x02);
+    gb.write_byte(0x2000, 0x03);
+    assert_eq!(gb.read_byte(0x4200), 0x23);
+
+    // BANK1's top bit isn't connected
+    gb.write_byte(0x2000, 0x13);
+    assert_eq!(gb.read_byte(0x4200), 0x23);
+}
+
+#[test]
+fn multicart_mode_1_maps_game_to_bank_0() {
+    let mut gb = create_gameboy(true);
+
+    gb.write_byte(0x6000, 0x01);
+    gb.write_byte(0x4000, 0x03);
+    assert_eq!(gb.read_byte(0x0200), 0x30);
+    assert_eq!(gb.read_byte(0x4200), 0x31);
+
+    // 0x10 passes the 0 check but maps the game's first bank
+    gb.write_byte(0x2000, 0x10);
+    assert_eq!(gb.read_byte(0x4200), 0x30);
+}
+
+#[test]
+fn plain_mbc1_isnt_a_multicart() {
+    let mut gb = create_gameboy(false);
+
+    gb.write_byte(0x4000, 0x01);
+    gb.write_byte(0x2000, 0x03);
+    assert_eq!(gb.read_byte(0x4200), 0x23);
+
+    gb.write_byte(0x2000, 0x13);
+    assert_eq!(gb.read_byte(0x4200), 0x33);
+}
+
+#[test]
+fn multicart_can_be_chosen_by_hand() {
+    let mut cartridge = Cartridge::new(create_rom(false), Vec::new(), Box::new(Mbc1::new_multicart()), None);
+
+    cartridge.write_rom(0x4000, 0x01);
+    cartridge.write_rom(0x2000, 0x02);
+    assert_eq!(cartridge.read_rom(0x4200), 0x12);
+}
@@ -0,0 +1,74 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NUM_ROM_BANKS: usize = 4;
+
+// An MBC2 cartridge where the first byte of every rom bank is the bank's number.
+// Nothing is run, the cartridge is only poked at through the memory bus.
+fn create_gameboy() -> GameBoy {
+    let mut rom = vec![0; 0x4000 * NUM_ROM_BANKS];
+    for bank in 0..NUM_ROM_BANKS {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom[0x147] = 0x06; // MBC2 + battery
+    rom[0x148] = 0x01; // 4 banks
+    rom[0x149] = 0x00;
+
+    load_gameboy(rom)
+}
+
+#[test]
+fn ram_is_4_bits() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0x0000, 0x0A);
+
+    // only the low nibble is stored, the high nibble reads as 1s
+    gb.write_byte(0xA000, 0xFF);
+    assert_eq!(gb.read_byte(0xA000) & 0x0F, 0x0F);
+    assert_eq!(gb.read_byte(0xA000), 0xFF);
+
+    gb.write_byte(0xA001, 0x5A);
+    assert_eq!(gb.read_byte(0xA001), 0xFA);
+
+    // 512 half-bytes, repeated through the whole ram area
+    assert_eq!(gb.read_byte(0xA201), 0xFA);
+    assert_eq!(gb.read_byte(0xBE01), 0xFA);
+    gb.write_byte(0xB1FF, 0x03);
+    assert_eq!(gb.read_byte(0xA1FF), 0xF3);
+}
+
+#[test]
+fn address_bit_8_picks_register() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0xA000, 0x05);
+    assert_eq!(gb.read_byte(0xA000), 0xF5);
+
+    // disabled ram reads as 0xFF
+    gb.write_byte(0x0000, 0x00);
+    assert_eq!(gb.read_byte(0xA000), 0xFF);
+
+    // bit 8 set: this selects a rom bank rather than enabling ram
+    gb.write_byte(0x0100, 0x0A);
+    assert_eq!(gb.read_byte(0xA000), 0xFF);
+
+    gb.write_byte(0x2100, 0x02);
+    assert_eq!(gb.read_byte(0x4000), 0x02);
+
+    // bit 8 clear: this enables ram rather than selecting a rom bank
+    gb.write_byte(0x3E0A, 0x0A);
+    assert_eq!(gb.read_byte(0x4000), 0x02);
+    assert_eq!(gb.read_byte(0xA000), 0xF5);
+
+    // bank 0 selects bank 1
+    gb.write_byte(0x3F00, 0x00);
+    assert_eq!(gb.read_byte(0x4000), 0x01);
+
+    gb.write_byte(0x0100, 0x03);
+    assert_eq!(gb.read_byte(0x4000), 0x03);
+}
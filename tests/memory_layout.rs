@@ -0,0 +1,89 @@
+use gameboy_rs::gameboy::{Cartridge, GameBoy, Mapper, MemoryLayout, save_state::{StateReader, StateWriter}};
+use common::load_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Nothing is run, the cartridge is only poked at through the memory bus
+fn create_gameboy(cartridge_type: u8, rom_size: u8, ram_size: u8) -> GameBoy {
+    let num_banks = 2 << rom_size;
+    let mut rom = vec![0; 0x4000 * num_banks];
+    rom[0x147] = cartridge_type;
+    rom[0x148] = rom_size;
+    rom[0x149] = ram_size;
+
+    load_gameboy(rom)
+}
+
+#[test]
+fn mbc1_layout_follows_control_writes() {
+    // 8 rom banks, 4 ram banks
+    let mut gb = create_gameboy(0x03, 0x02, 0x03);
+    assert_eq!(gb.memory_layout(), MemoryLayout {
+        low_rom_bank: 0,
+        rom_bank: 1,
+        ram_bank: 0,
+        ram_enabled: false,
+        banking_mode: 0
+    });
+
+    gb.write_byte(0x0000, 0x0A);
+    gb.write_byte(0x2000, 0x05);
+    gb.write_byte(0x4000, 0x02);
+    let layout = gb.memory_layout();
+    assert_eq!(layout.rom_bank, 5);
+    assert_eq!(layout.ram_bank, 0);
+    assert!(layout.ram_enabled);
+
+    // ram banking only happens in mode 1
+    gb.write_byte(0x6000, 0x01);
+    let layout = gb.memory_layout();
+    assert_eq!(layout.ram_bank, 2);
+    assert_eq!(layout.banking_mode, 1);
+
+    gb.write_byte(0x0000, 0x00);
+    assert!(!gb.memory_layout().ram_enabled);
+}
+
+#[test]
+fn mbc5_layout_follows_control_writes() {
+    // 512 rom banks, 16 ram banks
+    let mut gb = create_gameboy(0x1B, 0x08, 0x04);
+
+    gb.write_byte(0x2000, 0x34);
+    gb.write_byte(0x3000, 0x01);
+    gb.write_byte(0x4000, 0x0C);
+
+    let layout = gb.memory_layout();
+    assert_eq!(layout.rom_bank, 0x134);
+    assert_eq!(layout.ram_bank, 0x0C);
+    assert_eq!(layout.banking_mode, 0);
+}
+
+struct FixedMapper;
+
+impl Mapper for FixedMapper {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        rom[addr as usize]
+    }
+
+    fn write_control(&mut self, _addr: u16, _value: u8) { }
+
+    fn read_ram(&self, _ram: &[u8], _addr: u16) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _ram: &mut [u8], _addr: u16, _value: u8) { }
+
+    fn save_state(&self, _state: &mut StateWriter) { }
+
+    fn load_state(&mut self, _state: &mut StateReader) { }
+}
+
+#[test]
+fn mapper_without_layout_reports_no_banking() {
+    let cartridge = Cartridge::new(vec![0; 0x8000], Vec::new(), Box::new(FixedMapper), None);
+    assert_eq!(cartridge.memory_layout(), MemoryLayout::default());
+    assert_eq!(cartridge.memory_layout().rom_bank, 1);
+}
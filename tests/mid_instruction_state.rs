@@ -0,0 +1,69 @@
+use gameboy_rs::gameboy::{GameBoy, Model, Registers};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const SP: u16 = 0xD000;
+
+// Calls a subroutine over and over, with a CB opcode and both sides of a conditional jump
+fn create_gameboy() -> GameBoy {
+    let mut rom = rom_with_program(&[
+        0x31, 0x00, 0xD0,       // 0x100: ld sp, 0xD000
+        0xCD, 0x00, 0x02,       // 0x103: call 0x0200
+        0xCB, 0x37,             // 0x106: swap a
+        0x18, 0xF9              // 0x108: jr 0x103
+    ]);
+    rom[0x200..0x206].copy_from_slice(&[
+        0x3C,                   // 0x200: inc a
+        0x20, 0x02,             // 0x201: jr nz, 0x205
+        0x3E, 0x01,             // 0x203: ld a, 0x01
+        0xC9                    // 0x205: ret
+    ]);
+
+    let mut gb = new_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb.set_registers(Registers { sp: 0xFFFE, pc: 0x100, ..Registers::default() });
+    gb
+}
+
+fn run(gb: &mut GameBoy, ticks: usize) -> Vec<(Registers, u8, u8)> {
+    (0..ticks).map(|_| {
+        gb.tick();
+        (gb.registers(), gb.read_byte(SP - 1), gb.read_byte(SP - 2))
+    }).collect()
+}
+
+#[test]
+fn call_completes_after_loading_mid_instruction() {
+    let mut gb = create_gameboy();
+
+    // ld sp takes 3 machine cycles, then the call pushes the high byte in its 5th
+    run(&mut gb, (3 + 5) * 4);
+    assert_eq!(gb.read_byte(SP - 1), 0x01);
+    assert_eq!(gb.read_byte(SP - 2), 0x00);
+    let state = gb.save_state();
+
+    let mut loaded = create_gameboy();
+    loaded.load_state(&state).unwrap();
+    run(&mut loaded, 4);
+
+    assert_eq!(loaded.read_byte(SP - 2), 0x06);
+    assert_eq!(loaded.registers().pc, 0x200);
+    assert_eq!(loaded.registers().sp, SP - 2);
+}
+
+#[test]
+fn loaded_state_runs_the_same_from_any_cycle() {
+    for saved_at in 0..160 {
+        let mut gb = create_gameboy();
+        run(&mut gb, saved_at);
+        let state = gb.save_state();
+        let expected = run(&mut gb, 200);
+
+        let mut loaded = create_gameboy();
+        loaded.load_state(&state).unwrap();
+        assert_eq!(run(&mut loaded, 200), expected, "saved after {} ticks", saved_at);
+    }
+}
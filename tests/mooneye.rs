@@ -8,8 +8,11 @@ extern crate image;
 
 mod common;
 
+// Long enough for almost every test to finish, the ones that need longer say so
+const DEFAULT_SECONDS: u64 = 5;
+
 macro_rules! mooneye_test {
-    ($($name:ident: $path:expr,)*) => {
+    ($($name:ident: $path:expr $(=> $seconds:expr)?,)*) => {
     $(
         #[test]
         fn $name() {
@@ -26,7 +29,8 @@ macro_rules! mooneye_test {
             {
                 let mut s = GameBoy::new(rom_str, None);
 
-                let cycles_to_run = CYCLES_PER_SCREEN_DRAW * 60 * 5;
+                let seconds: u64 = None$(.or(Some($seconds)))?.unwrap_or(DEFAULT_SECONDS);
+                let cycles_to_run = CYCLES_PER_SCREEN_DRAW * 60 * seconds;
                 for _ in 0..cycles_to_run {
                     s.tick();
                 }
@@ -63,7 +67,7 @@ mooneye_test! {
     halt_ime1_timing2: "halt_ime1_timing2-GS.gb",
     if_ie_registers: "if_ie_registers.gb",
     daa: "instr/daa.gb",
-    // ie_push: "interrupts/ie_push.gb",
+    ie_push: "interrupts/ie_push.gb",
     intr_timing: "intr_timing.gb",
     jp_cc_timing: "jp_cc_timing.gb",
     jp_timing: "jp_timing.gb",
@@ -79,7 +83,7 @@ mooneye_test! {
     intr_1_2_timing: "ppu/intr_1_2_timing-GS.gb",
     intr_2_0_timing: "ppu/intr_2_0_timing.gb",
     intr_2_mode0_timing: "ppu/intr_2_mode0_timing.gb",
-    // intr_2_mode0_timing_sprites: "ppu/intr_2_mode0_timing_sprites.gb",
+    intr_2_mode0_timing_sprites: "ppu/intr_2_mode0_timing_sprites.gb" => 10,
     intr_2_mode3_timing: "ppu/intr_2_mode3_timing.gb",
     intr_2_oam_ok_timing: "ppu/intr_2_oam_ok_timing.gb",
     lcdon_timing: "ppu/lcdon_timing-GS.gb",
@@ -94,7 +98,7 @@ mooneye_test! {
     ret_cc_timing: "ret_cc_timing.gb",
     ret_timing: "ret_timing.gb",
     rst_timing: "rst_timing.gb",
-    // boot_sclk_align: "serial/boot_sclk_align-dmgABCmgb.gb",
+    boot_sclk_align: "serial/boot_sclk_align-dmgABCmgb.gb",
     div_write: "timer/div_write.gb",
     rapid_toggle: "timer/rapid_toggle.gb",
     tim00: "timer/tim00.gb",
@@ -0,0 +1,85 @@
+use gameboy_rs::gameboy::{GameBoy, InputMovie, input::Button};
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const MOVIE_FRAMES: usize = 60;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+// Keeps logging the action buttons to 0xC000 - 0xCFFF
+const JOYP_LOG_PROGRAM: &[u8] = &[
+    0x21, 0x00, 0xC0,           // ld hl, 0xC000
+    0x3E, 0x10,                 // ld a, 0x10
+    0xE0, 0x00,                 // ldh (JOYP), a
+    // loop:
+    0xF0, 0x00,                 // ldh a, (JOYP)
+    0x22,                       // ld (hl+), a
+    0x7C,                       // ld a, h
+    0xFE, 0xD0,                 // cp 0xD0
+    0x20, 0xF8,                 // jr nz, loop
+    0x21, 0x00, 0xC0,           // ld hl, 0xC000
+    0x18, 0xF3                  // jr loop
+];
+
+fn read_wram(gb: &GameBoy) -> Vec<u8> {
+    (0xC000..=0xDFFF).map(|addr| gb.read_byte(addr)).collect()
+}
+
+#[test]
+fn replayed_movie_reproduces_run() {
+    let mut gb = create_gameboy(JOYP_LOG_PROGRAM);
+    boot(&mut gb);
+
+    let start = gb.save_state();
+    gb.start_recording();
+
+    for frame in 0..MOVIE_FRAMES {
+        match frame {
+            5 => gb.press_button(Button::A),
+            12 => gb.press_button(Button::Start),
+            20 => gb.release_button(Button::A),
+            33 => gb.release_button(Button::Start),
+            40 => gb.press_button(Button::B),
+            _ => {}
+        }
+
+        gb.step_frame().unwrap();
+    }
+
+    let movie = gb.stop_recording();
+    assert_eq!(movie.len(), MOVIE_FRAMES);
+    assert_eq!(movie.frames()[0], 0);
+    assert_eq!(movie.frames()[12], Button::A.mask() | Button::Start.mask());
+    assert_eq!(movie.frames()[MOVIE_FRAMES - 1], Button::B.mask());
+
+    let recorded = read_wram(&gb);
+    assert!(recorded.iter().any(|val| val & 0x0F != 0x0F));
+
+    // the live buttons don't match the movie, playback has to override them
+    gb.release_button(Button::B);
+    gb.press_button(Button::Select);
+
+    gb.load_state(&start).unwrap();
+    gb.play_movie(InputMovie::from_bytes(&movie.to_bytes()).unwrap());
+
+    for _ in 0..MOVIE_FRAMES {
+        assert!(gb.is_playing_movie());
+        gb.step_frame().unwrap();
+    }
+
+    assert!(read_wram(&gb) == recorded);
+
+    gb.step_frame().unwrap();
+    assert!(!gb.is_playing_movie());
+}
+
+#[test]
+fn stop_recording_without_recording() {
+    let mut gb = create_gameboy(JOYP_LOG_PROGRAM);
+    assert!(gb.stop_recording().is_empty());
+}
@@ -0,0 +1,78 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+const FRAMES: usize = 2;
+
+// Keeps incrementing and decrementing HL while it points into OAM
+fn create_gameboy(model: Model) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&[
+        0x21, 0x40, 0xFE,       // ld hl, 0xFE40
+        0x23,                   // inc hl
+        0x2B,                   // dec hl
+        0x18, 0xFC              // jr -4
+    ]));
+    gb.skip_boot_rom(model);
+    gb
+}
+
+// Every byte of OAM different, then runs the program with the lcd on and reads OAM back
+fn run_with_oam(gb: &mut GameBoy) -> (Vec<u8>, Vec<u8>) {
+    let oam: Vec<u8> = (0..0xA0).map(|i| (i as u8).wrapping_mul(37)).collect();
+
+    gb.write_byte(LCDC, 0);
+    for (i, &val) in oam.iter().enumerate() {
+        gb.write_byte(0xFE00 + i as u16, val);
+    }
+    gb.write_byte(LCDC, 0x91);
+
+    for _ in 0..FRAMES {
+        gb.step_frame().unwrap();
+    }
+
+    gb.write_byte(LCDC, 0);
+    let after = (0..0xA0).map(|i| gb.read_byte(0xFE00 + i)).collect();
+    (oam, after)
+}
+
+#[test]
+fn inc_dec_in_oam_corrupts_on_dmg() {
+    let mut gb = create_gameboy(Model::Dmg);
+    let (before, after) = run_with_oam(&mut gb);
+
+    assert_ne!(before, after);
+    // the first row is never touched
+    assert_eq!(before[0..8], after[0..8]);
+}
+
+#[test]
+fn corrupted_rows_copy_the_row_before() {
+    let mut gb = create_gameboy(Model::Dmg);
+    let (before, after) = run_with_oam(&mut gb);
+
+    // the last 3 words of every row end up with what was in the row above, and that
+    // spreads all the way down from the first row
+    for row in 1..20 {
+        assert_eq!(after[row * 8 + 2..row * 8 + 8], before[2..8], "row {}", row);
+    }
+}
+
+#[test]
+fn no_corruption_on_cgb() {
+    let mut gb = create_gameboy(Model::Cgb);
+    let (before, after) = run_with_oam(&mut gb);
+    assert_eq!(before, after);
+}
+
+#[test]
+fn no_corruption_when_disabled() {
+    let mut gb = create_gameboy(Model::Dmg);
+    gb.set_oam_bug(false);
+
+    let (before, after) = run_with_oam(&mut gb);
+    assert_eq!(before, after);
+}
@@ -0,0 +1,71 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+const OBP0: u16 = 0xFF48;
+const OPRI: u16 = 0xFF6C;
+
+const BLACK: u8 = 0;
+const LIGHT_GREY: u8 = 192;
+
+fn create_gameboy(model: Model) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.skip_boot_rom(model);
+    gb
+}
+
+// Sprite 0 is solid colour 3 at screen x 12, sprite 1 is solid colour 1 at screen x 8,
+// so they overlap from x 12 to 15. Returns the colour drawn at x 13.
+fn draw_overlapping_sprites(gb: &mut GameBoy, opri: u8) -> u8 {
+    gb.write_byte(LCDC, 0);
+    for i in 0..16 {
+        gb.write_byte(0x8010 + i, 0xFF);
+        gb.write_byte(0x8020 + i, if i % 2 == 0 { 0xFF } else { 0x00 });
+    }
+    for addr in 0xFE00..0xFEA0 {
+        gb.write_byte(addr, 0);
+    }
+
+    gb.write_byte(0xFE00, 32);
+    gb.write_byte(0xFE01, 20);
+    gb.write_byte(0xFE02, 1);
+
+    gb.write_byte(0xFE04, 32);
+    gb.write_byte(0xFE05, 16);
+    gb.write_byte(0xFE06, 2);
+
+    gb.write_byte(OBP0, 0b1110_0100);
+    gb.write_byte(OPRI, opri);
+    gb.write_byte(LCDC, 0b1000_0010);
+
+    for _ in 0..2 {
+        gb.step_frame().unwrap();
+    }
+
+    gb.get_frame_buffer()[16 * 160 + 13]
+}
+
+#[test]
+fn cgb_oam_order_priority() {
+    let mut gb = create_gameboy(Model::Cgb);
+    assert_eq!(draw_overlapping_sprites(&mut gb, 0), BLACK);
+    assert_eq!(gb.read_byte(OPRI), 0xFE);
+}
+
+#[test]
+fn cgb_x_coordinate_priority() {
+    let mut gb = create_gameboy(Model::Cgb);
+    assert_eq!(draw_overlapping_sprites(&mut gb, 1), LIGHT_GREY);
+    assert_eq!(gb.read_byte(OPRI), 0xFF);
+}
+
+#[test]
+fn opri_not_there_on_dmg() {
+    let mut gb = create_gameboy(Model::Dmg);
+    assert_eq!(draw_overlapping_sprites(&mut gb, 0), LIGHT_GREY);
+    assert_eq!(gb.read_byte(OPRI), 0xFF);
+}
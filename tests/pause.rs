@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use gameboy_rs::gameboy::GameBoy;
+
+extern crate gameboy_rs;
+
+mod common;
+use common::{boot, new_gameboy, rom_with_program};
+
+fn create_gameboy() -> GameBoy {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("./tests/roms/mooneye/acceptance/div_timing.gb");
+    GameBoy::new(d.to_str().unwrap(), None)
+}
+
+fn cycles_until_next_frame(gb: &mut GameBoy) -> u64 {
+    gb.clear_draw_flag();
+
+    let mut cycles = 0;
+    while !gb.get_draw_flag() {
+        gb.tick();
+        cycles += 1;
+    }
+
+    cycles
+}
+
+#[test]
+fn pause_mid_instruction_preserves_state() {
+    let mut paused_gb = create_gameboy();
+    let mut gb = create_gameboy();
+
+    // an odd number of cycles so we stop part way through an instruction
+    paused_gb.run_cycles(12_345).unwrap();
+    gb.run_cycles(12_345).unwrap();
+
+    paused_gb.pause();
+    assert!(paused_gb.is_paused());
+
+    // none of these should advance the machine
+    paused_gb.run_cycles(1_000).unwrap();
+    paused_gb.step_frame().unwrap();
+
+    paused_gb.resume();
+    assert!(!paused_gb.is_paused());
+
+    paused_gb.run_cycles(3).unwrap();
+    gb.run_cycles(3).unwrap();
+
+    for _ in 0..10 {
+        assert_eq!(cycles_until_next_frame(&mut paused_gb), cycles_until_next_frame(&mut gb));
+    }
+
+    assert_eq!(paused_gb.get_frame_buffer(), gb.get_frame_buffer());
+}
+
+#[test]
+fn pause_part_way_through_an_instruction_resumes_from_the_same_step() {
+    // ld hl,C000; loop: inc (hl); jr loop
+    let program = [0x21, 0x00, 0xC0, 0x34, 0x18, 0xFD];
+    let mut paused_gb = new_gameboy(rom_with_program(&program));
+    let mut gb = new_gameboy(rom_with_program(&program));
+    boot(&mut paused_gb);
+    boot(&mut gb);
+
+    for gb in [&mut paused_gb, &mut gb].iter_mut() {
+        while gb.step_instruction().unwrap().0 != 0x104 {}
+
+        // inc (hl) takes 12 cycles, stop 5 into it
+        for _ in 0..5 {
+            gb.tick();
+        }
+    }
+
+    let registers = paused_gb.registers();
+    let state = paused_gb.save_state();
+    assert_eq!(state, gb.save_state());
+
+    paused_gb.pause();
+    paused_gb.run_cycles(1_000).unwrap();
+    paused_gb.step_frame().unwrap();
+    assert_eq!(paused_gb.registers(), registers);
+    assert_eq!(paused_gb.save_state(), state);
+
+    paused_gb.resume();
+
+    // the write lands on the last cycle of inc (hl), so it's 7 cycles away if the
+    // instruction carried on and 12 if it was started over
+    let value = paused_gb.read_byte(0xC000);
+    let mut cycles = 0;
+    while paused_gb.read_byte(0xC000) == value {
+        paused_gb.tick();
+        cycles += 1;
+    }
+    assert_eq!(cycles, 7);
+    assert_eq!(paused_gb.read_byte(0xC000), value.wrapping_add(1));
+
+    for _ in 0..cycles {
+        gb.tick();
+    }
+    assert_eq!(paused_gb.registers(), gb.registers());
+    assert_eq!(paused_gb.save_state(), gb.save_state());
+}
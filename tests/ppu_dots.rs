@@ -0,0 +1,87 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const DOTS_PER_LINE: u64 = 456;
+const LINES_PER_FRAME: u64 = 154;
+const LINE_LIMIT: u64 = 1000;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    gb
+}
+
+// Runs a cycle at a time until LY changes, returns how many cycles that took
+fn cycles_to_next_line(gb: &mut GameBoy) -> u64 {
+    let ly = gb.read_byte(0xFF44);
+
+    for cycles in 1..=LINE_LIMIT {
+        gb.run_cycles(1).unwrap();
+        if gb.read_byte(0xFF44) != ly { return cycles }
+    }
+
+    panic!("LY never changed from {}", ly);
+}
+
+fn wait_for_line(gb: &mut GameBoy, line: u8) {
+    for _ in 0..LINES_PER_FRAME {
+        cycles_to_next_line(gb);
+        if gb.read_byte(0xFF44) == line { return }
+    }
+
+    panic!("Never reached line {}", line);
+}
+
+#[test]
+fn every_line_is_456_dots() {
+    let mut gb = create_gameboy();
+    wait_for_line(&mut gb, 1);
+
+    // LY reads 0 a few dots into line 153, so 153 and 0 are only right together
+    let mut frame_cycles = 0;
+    for line in 1..LINES_PER_FRAME {
+        let cycles = cycles_to_next_line(&mut gb);
+        frame_cycles += cycles;
+
+        if line < 153 {
+            assert_eq!(cycles, DOTS_PER_LINE, "line {}", line);
+        }
+    }
+
+    frame_cycles += cycles_to_next_line(&mut gb);
+    assert_eq!(gb.read_byte(0xFF44), 1);
+    assert_eq!(frame_cycles, DOTS_PER_LINE * LINES_PER_FRAME);
+}
+
+// Mode 3 is longer by SCX % 8 dots, so HBlank starts on dots that aren't on a
+// machine cycle boundary
+#[test]
+fn hblank_starts_on_exact_dot() {
+    let mut gb = create_gameboy();
+    wait_for_line(&mut gb, 10);
+
+    let mut hblank_starts = Vec::new();
+    for scx in 0..4 {
+        gb.write_byte(0xFF43, scx);
+        cycles_to_next_line(&mut gb);
+
+        // STAT still says HBlank for the first few dots of the line
+        let mut cycles = 0;
+        let mut mode_3 = false;
+        while !mode_3 || gb.read_byte(0xFF41) & 3 != 0 {
+            gb.run_cycles(1).unwrap();
+            cycles += 1;
+            mode_3 |= gb.read_byte(0xFF41) & 3 == 3;
+        }
+
+        hblank_starts.push(cycles);
+    }
+
+    let first = hblank_starts[0];
+    assert_eq!(hblank_starts, vec![first, first + 1, first + 2, first + 3]);
+}
@@ -0,0 +1,442 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, CYCLES_PER_SCREEN_DRAW, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+const STAT: u16 = 0xFF41;
+const SCX: u16 = 0xFF43;
+const LY: u16 = 0xFF44;
+const BGP: u16 = 0xFF47;
+const OBP0: u16 = 0xFF48;
+const OAM: u16 = 0xFE00;
+
+// lcd on, bg on, sprites on, 8x8 sprites, tile data at 0x8000
+const LCDC_ON: u8 = 0b1001_0011;
+
+const MODE_3_MIN_LENGTH: u32 = 172;
+
+// Creates a gameboy running a rom that just spins on `jr -2`,
+// so the cpu never touches the ppu while we're measuring it.
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.run_cycles(CYCLES_PER_SCREEN_DRAW * BOOT_FRAMES as u64).unwrap();
+
+    stop_lcd(&mut gb);
+
+    for addr in OAM..OAM + 0xA0 {
+        gb.write_byte(addr, 0);
+    }
+
+    gb
+}
+
+// oam is only accessible while the lcd is off
+fn stop_lcd(gb: &mut GameBoy) {
+    gb.write_byte(LCDC, 0);
+    gb.run_cycles(4).unwrap();
+}
+
+fn start_lcd(gb: &mut GameBoy) {
+    gb.write_byte(LCDC, LCDC_ON);
+
+    // let the first frame after turning the lcd on pass
+    gb.step_frame().unwrap();
+}
+
+fn set_sprite(gb: &mut GameBoy, index: u16, x: u8) {
+    let addr = OAM + index * 4;
+
+    // y = 36 puts the sprite on lines 20 - 27
+    gb.write_byte(addr, 36);
+    gb.write_byte(addr + 1, x);
+    gb.write_byte(addr + 2, 0);
+    gb.write_byte(addr + 3, 0);
+}
+
+fn stat_mode(gb: &GameBoy) -> u8 {
+    gb.read_byte(STAT) & 0b11
+}
+
+// Number of dots the ppu spends in mode 3 on line 20
+fn mode_3_length(gb: &mut GameBoy) -> u32 {
+    while !(gb.read_byte(LY) == 20 && stat_mode(gb) == 2) {
+        gb.tick();
+    }
+
+    while stat_mode(gb) != 3 {
+        gb.tick();
+    }
+
+    let mut dots = 0;
+    while stat_mode(gb) == 3 {
+        gb.tick();
+        dots += 1;
+    }
+
+    dots
+}
+
+#[test]
+fn mode_3_length_without_scroll_or_sprites() {
+    let mut gb = create_gameboy();
+    start_lcd(&mut gb);
+
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH);
+}
+
+#[test]
+fn mode_3_length_scx_fine_scroll() {
+    let mut gb = create_gameboy();
+    start_lcd(&mut gb);
+
+    // the pixels discarded for scx & 7 extend mode 3 by a dot each
+    for scx in 0..8 {
+        gb.write_byte(SCX, scx);
+        assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + scx as u32);
+    }
+
+    // only the fine scroll matters
+    gb.write_byte(SCX, 0b1010_0011);
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + 3);
+}
+
+#[test]
+fn mode_3_length_sprite_penalty() {
+    // a sprite costs 6 dots, plus up to 5 more dots depending
+    // on where it sits in the bg tile being fetched
+    let expected_penalty = [11, 10, 9, 8, 7, 6, 6, 6];
+
+    let mut gb = create_gameboy();
+
+    for scx in [0, 3] {
+        for x in 0..24 {
+            stop_lcd(&mut gb);
+            gb.write_byte(SCX, scx);
+            set_sprite(&mut gb, 0, x);
+            start_lcd(&mut gb);
+
+            let penalty = expected_penalty[((x + scx) & 7) as usize];
+            assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + scx as u32 + penalty);
+        }
+    }
+}
+
+#[test]
+fn mode_3_length_sprites_sharing_a_tile() {
+    let mut gb = create_gameboy();
+
+    // both in the same bg tile, the second one doesn't wait for the tile fetch
+    set_sprite(&mut gb, 0, 16);
+    set_sprite(&mut gb, 1, 18);
+
+    // in different bg tiles, both wait for their tile
+    set_sprite(&mut gb, 2, 40);
+    set_sprite(&mut gb, 3, 48);
+    start_lcd(&mut gb);
+
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + (11 + 6) + (11 + 11));
+}
+
+#[test]
+fn mode_3_length_offscreen_sprites() {
+    let mut gb = create_gameboy();
+
+    // x = 0 is fetched even though it can't be seen, x >= 168 never is
+    set_sprite(&mut gb, 0, 0);
+    set_sprite(&mut gb, 1, 168);
+    start_lcd(&mut gb);
+
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + 11);
+}
+
+#[test]
+fn mode_3_length_ten_sprite_limit() {
+    let mut gb = create_gameboy();
+
+    // each at the start of its own bg tile, 11 dots apiece
+    for i in 0..10 {
+        set_sprite(&mut gb, i, 8 + i as u8 * 8);
+    }
+    start_lcd(&mut gb);
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + 10 * 11);
+
+    // the 11th and 12th sprites on the line are never fetched
+    stop_lcd(&mut gb);
+    set_sprite(&mut gb, 10, 96);
+    set_sprite(&mut gb, 11, 104);
+    start_lcd(&mut gb);
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + 10 * 11);
+}
+
+#[test]
+fn only_ten_sprites_drawn_per_line() {
+    let mut gb = create_gameboy();
+
+    // tile 1 is solid colour 3, the bg uses the blank tile 0
+    for addr in 0x8010..0x8020 {
+        gb.write_byte(addr, 0xFF);
+    }
+    gb.write_byte(OBP0, 0xFF);
+
+    // 12 sprites side by side, picked in oam order rather than by x, so the
+    // last 2 in oam are the leftmost ones
+    for i in 0..12 {
+        let x = 8 + ((i + 2) % 12) as u8 * 12;
+        set_sprite(&mut gb, i, x);
+        gb.write_byte(OAM + i * 4 + 2, 1);
+    }
+    start_lcd(&mut gb);
+    gb.step_frame().unwrap();
+
+    let fb = gb.get_frame_buffer();
+    let line = &fb[20 * 160..21 * 160];
+    let bg = line[159];
+
+    for slot in 0..12 {
+        let drawn = line[slot * 12] != bg;
+        assert_eq!(drawn, slot >= 2, "sprite at x {}", slot * 12);
+    }
+}
+
+// 15 solid sprites side by side on lines 20 - 27, returns which of them got drawn
+fn draw_fifteen_sprites(gb: &mut GameBoy) -> Vec<bool> {
+    for addr in 0x8010..0x8020 {
+        gb.write_byte(addr, 0xFF);
+    }
+    gb.write_byte(OBP0, 0xFF);
+
+    for i in 0..15 {
+        set_sprite(gb, i, 8 + i as u8 * 10);
+        gb.write_byte(OAM + i * 4 + 2, 1);
+    }
+    start_lcd(gb);
+    gb.step_frame().unwrap();
+
+    let fb = gb.get_frame_buffer();
+    let line = &fb[20 * 160..21 * 160];
+    let bg = line[159];
+
+    (0..15).map(|i| line[i * 10] != bg).collect()
+}
+
+#[test]
+fn sprite_limit_defaults_to_ten() {
+    let mut gb = create_gameboy();
+
+    let drawn = draw_fifteen_sprites(&mut gb);
+    let expected: Vec<bool> = (0..15).map(|i| i < 10).collect();
+    assert_eq!(drawn, expected);
+}
+
+#[test]
+fn no_sprite_limit_draws_every_sprite() {
+    let mut gb = create_gameboy();
+    gb.set_sprite_limit(None);
+
+    let drawn = draw_fifteen_sprites(&mut gb);
+    assert_eq!(drawn, vec![true; 15]);
+}
+
+#[test]
+fn lower_sprite_limit() {
+    let mut gb = create_gameboy();
+    gb.set_sprite_limit(Some(4));
+
+    let drawn = draw_fifteen_sprites(&mut gb);
+    let expected: Vec<bool> = (0..15).map(|i| i < 4).collect();
+    assert_eq!(drawn, expected);
+}
+
+#[test]
+fn mode_3_length_no_sprite_limit() {
+    let mut gb = create_gameboy();
+    gb.set_sprite_limit(None);
+
+    // each at the start of its own bg tile, the sprites past the 10th don't add anything
+    for i in 0..15 {
+        set_sprite(&mut gb, i, 8 + i as u8 * 8);
+    }
+    start_lcd(&mut gb);
+    assert_eq!(mode_3_length(&mut gb), MODE_3_MIN_LENGTH + 10 * 11);
+}
+
+#[test]
+fn sprite_colour_0_shows_background() {
+    let mut gb = create_gameboy();
+
+    // the bg's tile 0 is solid colour 1, which BGP makes light grey
+    for addr in (0x8000..0x8010).step_by(2) {
+        gb.write_byte(addr, 0xFF);
+        gb.write_byte(addr + 1, 0x00);
+    }
+    gb.write_byte(BGP, 0b1110_0100);
+
+    // the sprite's left half is colour 0 and its right half colour 1.
+    // OBP0 maps colour 0 to black and the rest to white, so drawing colour 0 would show up.
+    for addr in (0x8010..0x8020).step_by(2) {
+        gb.write_byte(addr, 0x0F);
+        gb.write_byte(addr + 1, 0x00);
+    }
+    gb.write_byte(OBP0, 0b0000_0011);
+
+    // on screen at x 32 - 39
+    set_sprite(&mut gb, 0, 40);
+    gb.write_byte(OAM + 2, 1);
+    start_lcd(&mut gb);
+    gb.step_frame().unwrap();
+
+    let fb = gb.get_frame_buffer();
+    for line in 20..28 {
+        let row = &fb[line * 160..(line + 1) * 160];
+        assert_eq!(row[31], 192);
+        assert_eq!(&row[32..36], &[192; 4], "line {}", line);
+        assert_eq!(&row[36..40], &[255; 4], "line {}", line);
+        assert_eq!(row[40], 192);
+    }
+}
+
+// Tile 1 is a diagonal line, row n only has pixel n set. The sprite is black on the white bg,
+// returns every drawn pixel as (x, line).
+fn draw_edge_sprite(gb: &mut GameBoy, x: u8, y: u8, flags: u8) -> Vec<(usize, usize)> {
+    // the boot rom's logo is still in the tile map
+    for addr in 0x9800..0x9C00 {
+        gb.write_byte(addr, 0);
+    }
+    for row in 0..8 {
+        gb.write_byte(0x8010 + row * 2, 0x80 >> row);
+        gb.write_byte(0x8011 + row * 2, 0x80 >> row);
+    }
+    gb.write_byte(BGP, 0b1110_0100);
+    gb.write_byte(OBP0, 0xFF);
+
+    gb.write_byte(OAM, y);
+    gb.write_byte(OAM + 1, x);
+    gb.write_byte(OAM + 2, 1);
+    gb.write_byte(OAM + 3, flags);
+    start_lcd(gb);
+    gb.step_frame().unwrap();
+
+    let fb = gb.get_frame_buffer();
+    (0..160 * 144)
+        .filter(|i| fb[*i] != 255)
+        .map(|i| (i % 160, i / 160))
+        .collect()
+}
+
+#[test]
+fn sprite_clipped_at_top_edge() {
+    let mut gb = create_gameboy();
+
+    // starts 4 lines above the screen, so only rows 4 - 7 are drawn
+    let drawn = draw_edge_sprite(&mut gb, 40, 12, 0);
+    assert_eq!(drawn, vec![(36, 0), (37, 1), (38, 2), (39, 3)]);
+}
+
+#[test]
+fn yflipped_sprite_clipped_at_top_edge() {
+    let mut gb = create_gameboy();
+
+    // flipped, the rows on screen are 3 - 0
+    let drawn = draw_edge_sprite(&mut gb, 40, 12, 0b0100_0000);
+    assert_eq!(drawn, vec![(35, 0), (34, 1), (33, 2), (32, 3)]);
+}
+
+#[test]
+fn sprite_clipped_at_bottom_edge() {
+    let mut gb = create_gameboy();
+
+    // rows 0 - 3 on the last 4 lines, the rest are below the screen
+    let drawn = draw_edge_sprite(&mut gb, 40, 156, 0);
+    assert_eq!(drawn, vec![(32, 140), (33, 141), (34, 142), (35, 143)]);
+}
+
+#[test]
+fn sprite_clipped_at_left_edge() {
+    let mut gb = create_gameboy();
+
+    // starts 4 pixels left of the screen, so only columns 4 - 7 are drawn
+    let drawn = draw_edge_sprite(&mut gb, 4, 36, 0);
+    assert_eq!(drawn, vec![(0, 24), (1, 25), (2, 26), (3, 27)]);
+}
+
+#[test]
+fn sprite_clipped_at_right_edge() {
+    let mut gb = create_gameboy();
+
+    // columns 0 - 3 in the last 4 pixels, nothing wraps round to the left
+    let drawn = draw_edge_sprite(&mut gb, 164, 36, 0);
+    assert_eq!(drawn, vec![(156, 20), (157, 21), (158, 22), (159, 23)]);
+}
+
+#[test]
+fn sprites_fully_off_screen_not_drawn() {
+    for (x, y) in [(0, 36), (168, 36), (40, 8), (40, 160)] {
+        let mut gb = create_gameboy();
+        assert!(draw_edge_sprite(&mut gb, x, y, 0).is_empty(), "x {} y {}", x, y);
+    }
+}
+
+// The whole bg is tile 0, which is solid colour 1
+fn fill_bg_with_colour_1(gb: &mut GameBoy) {
+    for addr in 0x9800..0x9C00 {
+        gb.write_byte(addr, 0);
+    }
+    for addr in (0x8000..0x8010).step_by(2) {
+        gb.write_byte(addr, 0xFF);
+        gb.write_byte(addr + 1, 0x00);
+    }
+}
+
+#[test]
+fn bgp_change_mid_frame() {
+    let mut gb = create_gameboy();
+    fill_bg_with_colour_1(&mut gb);
+
+    // colour 1 is light grey
+    gb.write_byte(BGP, 0b0000_0100);
+    start_lcd(&mut gb);
+
+    // before line 72 is drawn, colour 1 turns black
+    while gb.read_byte(LY) != 72 {
+        gb.tick();
+    }
+    gb.write_byte(BGP, 0b0000_1100);
+    gb.step_frame().unwrap();
+
+    let fb = gb.get_frame_buffer();
+    assert!(fb[..72 * 160].iter().all(|p| *p == 192));
+    assert!(fb[72 * 160..].iter().all(|p| *p == 0));
+}
+
+#[test]
+fn bgp_change_mid_line() {
+    let mut gb = create_gameboy();
+    fill_bg_with_colour_1(&mut gb);
+
+    gb.write_byte(BGP, 0b0000_0100);
+    start_lcd(&mut gb);
+
+    // part way through drawing line 20
+    while !(gb.read_byte(LY) == 20 && stat_mode(&gb) == 3) {
+        gb.tick();
+    }
+    for _ in 0..80 {
+        gb.tick();
+    }
+    gb.write_byte(BGP, 0b0000_1100);
+    gb.step_frame().unwrap();
+
+    // the pixels already pushed keep the old palette
+    let fb = gb.get_frame_buffer();
+    let line = &fb[20 * 160..21 * 160];
+    let split = line.iter().position(|p| *p == 0).unwrap();
+    assert!(split > 0 && split < 160);
+    assert!(line[..split].iter().all(|p| *p == 192));
+    assert!(line[split..].iter().all(|p| *p == 0));
+
+    assert!(fb[19 * 160..20 * 160].iter().all(|p| *p == 192));
+    assert!(fb[21 * 160..22 * 160].iter().all(|p| *p == 0));
+}
@@ -0,0 +1,70 @@
+use gameboy_rs::gameboy::{GameBoy, RamInit};
+use common::{new_gameboy, rom_with_program, SPIN, TempRom};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(name: &str, ram_init: RamInit) -> GameBoy {
+    let rom = TempRom::new(name, &rom_with_program(&SPIN));
+    GameBoy::new_with_ram_init(rom.path(), None, ram_init)
+}
+
+// WRAM, VRAM and OAM, read before anything has run
+fn read_ram(gb: &GameBoy) -> Vec<u8> {
+    (0xC000..=0xDFFF)
+        .chain(0x8000..=0x9FFF)
+        .chain(0xFE00..=0xFE9F)
+        .map(|addr| gb.read_byte(addr))
+        .collect()
+}
+
+#[test]
+fn default_is_zero() {
+    let gb = new_gameboy(rom_with_program(&SPIN));
+    assert!(read_ram(&gb).iter().all(|val| *val == 0x00));
+}
+
+#[test]
+fn zero_and_ones() {
+    let gb = create_gameboy("ram_init_zero.gb", RamInit::Zero);
+    assert!(read_ram(&gb).iter().all(|val| *val == 0x00));
+
+    let gb = create_gameboy("ram_init_ones.gb", RamInit::Ones);
+    assert!(read_ram(&gb).iter().all(|val| *val == 0xFF));
+}
+
+#[test]
+fn pattern() {
+    let gb = create_gameboy("ram_init_pattern.gb", RamInit::Pattern);
+
+    for addr in 0xC000..=0xDFFF {
+        let expected = if addr & 8 == 0 { 0x00 } else { 0xFF };
+        assert_eq!(gb.read_byte(addr), expected);
+    }
+
+    assert_eq!(gb.read_byte(0xFE00), 0x00);
+    assert_eq!(gb.read_byte(0xFE08), 0xFF);
+}
+
+#[test]
+fn random_depends_on_seed() {
+    let gb_1 = create_gameboy("ram_init_random_1.gb", RamInit::Random(1));
+    let gb_2 = create_gameboy("ram_init_random_2.gb", RamInit::Random(1));
+    let gb_3 = create_gameboy("ram_init_random_3.gb", RamInit::Random(2));
+
+    let ram = read_ram(&gb_1);
+    assert!(ram == read_ram(&gb_2));
+    assert!(ram != read_ram(&gb_3));
+
+    // not all one value
+    assert!(ram.iter().any(|val| *val != ram[0]));
+}
+
+#[test]
+fn random_is_the_same_for_a_seed_everywhere() {
+    // replays depend on these never changing
+    let gb = create_gameboy("ram_init_random_fixed.gb", RamInit::Random(1));
+    let ram: Vec<u8> = (0xC000..0xC008).map(|addr| gb.read_byte(addr)).collect();
+    assert_eq!(ram, [0x91, 0xBE, 0xF8, 0x71, 0x71, 0xC3, 0xE0, 0x85]);
+}
@@ -0,0 +1,84 @@
+use gameboy_rs::gameboy::{GameBoy, Registers};
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+fn booted_gameboy(program: &[u8]) -> GameBoy {
+    let mut gb = create_gameboy(program);
+    boot(&mut gb);
+
+    // let the instruction in flight finish
+    gb.step_instruction().unwrap();
+    gb
+}
+
+#[test]
+fn set_registers_reads_back() {
+    let mut gb = booted_gameboy(&[0x18, 0xFE]);
+
+    let registers = Registers {
+        a: 0x12,
+        f: 0xB0,
+        b: 0x34,
+        c: 0x56,
+        d: 0x78,
+        e: 0x9A,
+        h: 0xBC,
+        l: 0xDE,
+
+        sp: 0xDFF0,
+        pc: 0x0100
+    };
+
+    gb.set_registers(registers);
+    assert_eq!(gb.registers(), registers);
+
+    // F's lower nibble always reads as 0
+    gb.set_f(0xFF);
+    assert_eq!(gb.registers().f, 0xF0);
+}
+
+#[test]
+fn individual_setters() {
+    let mut gb = booted_gameboy(&[0x18, 0xFE]);
+
+    gb.set_a(1);
+    gb.set_b(2);
+    gb.set_c(3);
+    gb.set_d(4);
+    gb.set_e(5);
+    gb.set_h(6);
+    gb.set_l(7);
+    gb.set_sp(0xC100);
+
+    let registers = gb.registers();
+    assert_eq!(
+        (registers.a, registers.b, registers.c, registers.d, registers.e, registers.h, registers.l),
+        (1, 2, 3, 4, 5, 6, 7)
+    );
+    assert_eq!(registers.sp, 0xC100);
+}
+
+#[test]
+fn set_pc_runs_from_new_address() {
+    // 0x100: jr -2
+    // 0x102: ld a, 0x42; ld (0xC000), a; jr -2
+    let mut gb = booted_gameboy(&[
+        0x18, 0xFE,
+        0x3E, 0x42,
+        0xEA, 0x00, 0xC0,
+        0x18, 0xFE
+    ]);
+
+    gb.write_byte(0xC000, 0);
+    gb.set_pc(0x102);
+    gb.step_frame().unwrap();
+
+    assert_eq!(gb.read_byte(0xC000), 0x42);
+}
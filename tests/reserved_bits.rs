@@ -0,0 +1,80 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const JOYP: u16 = 0xFF00;
+const SC: u16 = 0xFF02;
+const TAC: u16 = 0xFF07;
+const IF: u16 = 0xFF0F;
+const LCDC: u16 = 0xFF40;
+const STAT: u16 = 0xFF41;
+
+// di so that setting IF doesn't start any interrupts
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&[
+        0xF3,                   // di
+        0x18, 0xFE              // jr -2
+    ]));
+    gb.skip_boot_rom(Model::Dmg);
+    gb.run_cycles(100).unwrap();
+    gb
+}
+
+#[test]
+fn stat_bit_7_reads_1() {
+    let mut gb = create_gameboy();
+
+    // with the lcd off the mode and coincidence bits stay put
+    gb.write_byte(LCDC, 0);
+    gb.write_byte(STAT, 0x00);
+    assert_eq!(gb.read_byte(STAT) & 0b1111_1000, 0b1000_0000);
+
+    gb.write_byte(STAT, 0xFF);
+    assert_eq!(gb.read_byte(STAT) & 0b1111_1000, 0b1111_1000);
+}
+
+#[test]
+fn if_upper_bits_read_1() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(IF, 0x00);
+    assert_eq!(gb.read_byte(IF), 0b1110_0000);
+
+    gb.write_byte(IF, 0b0000_0101);
+    assert_eq!(gb.read_byte(IF), 0b1110_0101);
+
+    // the upper bits aren't kept, there are only 5 interrupts
+    gb.write_byte(IF, 0xFF);
+    assert_eq!(gb.read_byte(IF), 0xFF);
+    assert_eq!(gb.interrupt_state().flags, 0b0001_1111);
+}
+
+#[test]
+fn other_registers_unused_bits_read_1() {
+    let mut gb = create_gameboy();
+
+    gb.write_byte(JOYP, 0x00);
+    assert_eq!(gb.read_byte(JOYP) & 0b1100_0000, 0b1100_0000);
+
+    gb.write_byte(SC, 0x00);
+    assert_eq!(gb.read_byte(SC), 0b0111_1110);
+
+    gb.write_byte(TAC, 0x00);
+    assert_eq!(gb.read_byte(TAC), 0b1111_1000);
+    gb.write_byte(TAC, 0x05);
+    assert_eq!(gb.read_byte(TAC), 0b1111_1101);
+}
+
+#[test]
+fn unmapped_io_registers_read_0xff() {
+    let mut gb = create_gameboy();
+    let unmapped = [0xFF03, 0xFF08, 0xFF0E, 0xFF15, 0xFF1F, 0xFF27, 0xFF2F, 0xFF4C, 0xFF4E, 0xFF56, 0xFF60, 0xFF6C, 0xFF71, 0xFF7F];
+
+    for &addr in unmapped.iter() {
+        gb.write_byte(addr, 0x00);
+        assert_eq!(gb.read_byte(addr), 0xFF, "{:#06X}", addr);
+    }
+}
@@ -0,0 +1,100 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Increments SCX once every vblank, so SCX counts the frames
+const PROGRAM: [u8; 19] = [
+    0xF0, 0x44,             // ldh a, (LY)
+    0xFE, 0x90,             // cp 144
+    0x20, 0xFA,             // jr nz, -6
+    0xF0, 0x43,             // ldh a, (SCX)
+    0x3C,                   // inc a
+    0xE0, 0x43,             // ldh (SCX), a
+    0xF0, 0x44,             // ldh a, (LY)
+    0xFE, 0x90,             // cp 144
+    0x28, 0xFA,             // jr z, -6
+    0x18, 0xED              // jr -19
+];
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&PROGRAM));
+    gb.set_rewind(true, 30);
+    gb
+}
+
+#[test]
+fn rewind_restores_earlier_frames() {
+    let mut gb = create_gameboy();
+    boot(&mut gb);
+
+    let mut history = Vec::new();
+    for _ in 0..40 {
+        gb.step_frame().unwrap();
+        history.push((gb.read_byte(0xFF43), gb.get_frame_buffer().to_vec()));
+    }
+
+    let scx = gb.read_byte(0xFF43);
+    assert!(gb.rewind_frame());
+    let first_rewind_scx = gb.read_byte(0xFF43);
+    assert!(gb.rewind_frame());
+    let second_rewind_scx = gb.read_byte(0xFF43);
+
+    // snapshots are a few frames apart
+    assert!(first_rewind_scx < scx);
+    assert!(second_rewind_scx < first_rewind_scx);
+    assert!(scx - second_rewind_scx <= 8);
+
+    // running forward again replays the same frames
+    for _ in 0..6 {
+        gb.step_frame().unwrap();
+        let scx = gb.read_byte(0xFF43);
+        let recorded = history.iter().find(|(recorded_scx, _)| *recorded_scx == scx).unwrap();
+        assert!(recorded.1 == gb.get_frame_buffer());
+    }
+}
+
+#[test]
+fn rewind_without_snapshots() {
+    let mut gb = create_gameboy();
+    assert!(!gb.rewind_frame());
+}
+
+#[test]
+fn rewind_is_off_by_default() {
+    let mut gb = new_gameboy(rom_with_program(&PROGRAM));
+    boot(&mut gb);
+
+    for _ in 0..10 {
+        gb.step_frame().unwrap();
+    }
+
+    assert!(!gb.rewind_frame());
+}
+
+#[test]
+fn rewind_goes_back_as_far_as_asked() {
+    let mut gb = create_gameboy();
+    gb.set_rewind(true, 1);
+    boot(&mut gb);
+
+    for _ in 0..100 {
+        gb.step_frame().unwrap();
+    }
+
+    let scx = gb.read_byte(0xFF43);
+    let mut rewinds = 0;
+    let mut prev_scx = scx;
+    while gb.rewind_frame() {
+        let rewound_scx = gb.read_byte(0xFF43);
+        assert!(rewound_scx < prev_scx);
+        prev_scx = rewound_scx;
+        rewinds += 1;
+    }
+
+    // a snapshot every 4 frames for a second
+    assert_eq!(rewinds, 15);
+    assert!(scx - prev_scx <= 60);
+}
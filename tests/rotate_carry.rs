@@ -0,0 +1,73 @@
+use gameboy_rs::gameboy::{Flags, GameBoy};
+use common::cleared_registers_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const RLA: u8 = 0x17;
+const RRA: u8 = 0x1F;
+
+// A and C as one 9 bit value, with C as the top bit
+fn carry_chain(gb: &GameBoy) -> u16 {
+    (gb.flags().c as u16) << 8 | gb.registers().a as u16
+}
+
+#[test]
+fn rla_chain_rotates_through_carry() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_a(0b1000_0101);
+    gb.set_flags(Flags::default());
+
+    let mut expected = carry_chain(&gb);
+    for _ in 0..9 {
+        gb.execute_opcode(RLA, 0, 0).unwrap();
+
+        // the old carry comes in at the bottom, bit 7 goes out into C
+        expected = ((expected << 1) | (expected >> 8)) & 0x1FF;
+        assert_eq!(carry_chain(&gb), expected);
+
+        let flags = gb.flags();
+        assert!(!flags.z && !flags.n && !flags.h);
+    }
+
+    // all 9 bits have gone round once
+    assert_eq!(gb.registers().a, 0b1000_0101);
+    assert!(!gb.flags().c);
+}
+
+#[test]
+fn rra_chain_rotates_through_carry() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_a(0b0110_0001);
+    gb.set_flags(Flags { c: true, ..Flags::default() });
+
+    let mut expected = carry_chain(&gb);
+    for _ in 0..9 {
+        gb.execute_opcode(RRA, 0, 0).unwrap();
+
+        // the old carry comes in at the top, bit 0 goes out into C
+        expected = ((expected >> 1) | ((expected & 1) << 8)) & 0x1FF;
+        assert_eq!(carry_chain(&gb), expected);
+    }
+
+    assert_eq!(gb.registers().a, 0b0110_0001);
+    assert!(gb.flags().c);
+}
+
+#[test]
+fn rra_undoes_rla() {
+    let mut gb = cleared_registers_gameboy();
+    gb.set_a(0x80);
+    gb.set_flags(Flags::default());
+
+    // a zero result still leaves Z clear
+    gb.execute_opcode(RLA, 0, 0).unwrap();
+    assert_eq!(gb.registers().a, 0x00);
+    assert!(gb.flags().c);
+    assert!(!gb.flags().z);
+
+    gb.execute_opcode(RRA, 0, 0).unwrap();
+    assert_eq!(gb.registers().a, 0x80);
+    assert!(!gb.flags().c);
+}
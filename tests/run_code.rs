@@ -0,0 +1,93 @@
+use gameboy_rs::gameboy::{EmuError, GameBoy, Model, RunCodeError};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+// an illegal opcode, used as a breakpoint
+const SENTINEL: u8 = 0xDD;
+const WRAM: u16 = 0xC000;
+const MAX_INSTRUCTIONS: u64 = 1000;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.run_cycles(1000).unwrap();
+    gb
+}
+
+#[test]
+fn runs_to_sentinel() {
+    let mut gb = create_gameboy();
+
+    let registers = gb.run_code(WRAM, &[
+        0x3E, 0x42,             // ld a, 0x42
+        SENTINEL
+    ], SENTINEL, MAX_INSTRUCTIONS).unwrap();
+
+    assert_eq!(registers.a, 0x42);
+    assert_eq!(registers.pc, WRAM + 2);
+    assert_eq!(gb.registers(), registers);
+    assert_eq!(gb.get_error(), None);
+}
+
+#[test]
+fn runs_loops_and_memory_accesses() {
+    let mut gb = create_gameboy();
+
+    let registers = gb.run_code(WRAM, &[
+        0x21, 0x00, 0xD0,       // ld hl, 0xD000
+        0x06, 0x05,             // ld b, 5
+        0x70,                   // ld (hl), b
+        0x23,                   // inc hl
+        0x05,                   // dec b
+        0x20, 0xFB,             // jr nz, -5
+        SENTINEL
+    ], SENTINEL, MAX_INSTRUCTIONS).unwrap();
+
+    assert_eq!(registers.b, 0);
+    assert_eq!(registers.h, 0xD0);
+    assert_eq!(registers.l, 0x05);
+    for i in 0..5 {
+        assert_eq!(gb.read_byte(0xD000 + i), 5 - i as u8);
+    }
+}
+
+#[test]
+fn can_run_again_after_sentinel() {
+    let mut gb = create_gameboy();
+
+    gb.run_code(WRAM, &[0x3E, 0x01, SENTINEL], SENTINEL, MAX_INSTRUCTIONS).unwrap();
+    let registers = gb.run_code(WRAM + 0x10, &[0x3C, SENTINEL], SENTINEL, MAX_INSTRUCTIONS).unwrap(); // inc a
+    assert_eq!(registers.a, 0x02);
+}
+
+#[test]
+fn other_illegal_opcodes_are_errors() {
+    let mut gb = create_gameboy();
+
+    let result = gb.run_code(WRAM, &[0x00, 0xD3, SENTINEL], SENTINEL, MAX_INSTRUCTIONS);
+    assert_eq!(result, Err(RunCodeError::Emu(EmuError::IllegalOpcode { opcode: 0xD3, addr: WRAM + 1 })));
+}
+
+#[test]
+fn sentinel_has_to_be_illegal() {
+    let mut gb = create_gameboy();
+
+    let result = gb.run_code(WRAM, &[0x00], 0x00, MAX_INSTRUCTIONS);
+    assert_eq!(result, Err(RunCodeError::InvalidSentinel(0x00)));
+}
+
+#[test]
+fn never_reaching_sentinel_times_out() {
+    let mut gb = create_gameboy();
+
+    let result = gb.run_code(WRAM, &[
+        0x18, 0xFE,             // jr -2
+        SENTINEL
+    ], SENTINEL, MAX_INSTRUCTIONS);
+
+    assert_eq!(result, Err(RunCodeError::Timeout { instructions: MAX_INSTRUCTIONS }));
+    assert_eq!(gb.registers().pc, WRAM);
+    assert_eq!(gb.get_error(), None);
+}
@@ -0,0 +1,32 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program, CYCLES_PER_SCREEN_DRAW, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LY: u16 = 0xFF44;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    gb
+}
+
+#[test]
+fn stops_on_entering_vblank() {
+    let mut gb = create_gameboy();
+    // part way through a frame
+    gb.run_cycles(12_345).unwrap();
+
+    let frame = gb.run_to_vblank().to_vec();
+    assert_eq!(gb.read_byte(LY), 144);
+    assert_eq!(frame, gb.get_frame_buffer());
+
+    for _ in 0..2 {
+        gb.run_to_vblank();
+        assert_eq!(gb.read_byte(LY), 144);
+        assert_eq!(gb.frame_metrics().cycles, CYCLES_PER_SCREEN_DRAW);
+    }
+}
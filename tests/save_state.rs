@@ -0,0 +1,88 @@
+use gameboy_rs::gameboy::{GameBoy, save_state::StateError};
+use common::{boot, load_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Enables the cartridge ram then increments SCX once every vblank,
+// copying the new value into cartridge ram at 0xA000.
+const PROGRAM: [u8; 27] = [
+    0x3E, 0x0A,             // ld a, 0x0A
+    0xEA, 0x00, 0x00,       // ld (0x0000), a
+    0xF0, 0x44,             // ldh a, (LY)
+    0xFE, 0x90,             // cp 144
+    0x20, 0xFA,             // jr nz, -6
+    0xF0, 0x43,             // ldh a, (SCX)
+    0x3C,                   // inc a
+    0xE0, 0x43,             // ldh (SCX), a
+    0xEA, 0x00, 0xA0,       // ld (0xA000), a
+    0xF0, 0x44,             // ldh a, (LY)
+    0xFE, 0x90,             // cp 144
+    0x28, 0xFA,             // jr z, -6
+    0x18, 0xEA              // jr -22
+];
+
+fn create_gameboy() -> GameBoy {
+    let mut rom = rom_with_program(&PROGRAM);
+    rom[0x147] = 0x03; // MBC1 + RAM + battery
+    rom[0x149] = 0x02; // 8KB ram
+
+    let mut gb = load_gameboy(rom);
+    boot(&mut gb);
+
+    gb
+}
+
+fn record_frames(gb: &mut GameBoy, frames: usize) -> Vec<(u8, u8, Vec<u8>)> {
+    let mut frames_recorded = Vec::new();
+
+    for _ in 0..frames {
+        gb.step_frame().unwrap();
+        frames_recorded.push((
+            gb.read_byte(0xFF43),
+            gb.read_byte(0xA000),
+            gb.get_frame_buffer().to_vec()
+        ));
+    }
+
+    frames_recorded
+}
+
+#[test]
+fn load_state_restores_saved_point() {
+    let mut gb = create_gameboy();
+    gb.run_cycles(12_345).unwrap();
+
+    let state = gb.save_state();
+    let expected = record_frames(&mut gb, 30);
+
+    gb.load_state(&state).unwrap();
+    assert_eq!(record_frames(&mut gb, 30), expected);
+
+    // and into a different instance running the same rom
+    let mut other_gb = create_gameboy();
+    other_gb.step_frame().unwrap();
+    other_gb.load_state(&state).unwrap();
+    assert_eq!(record_frames(&mut other_gb, 30), expected);
+}
+
+#[test]
+fn load_state_rejects_invalid_data() {
+    let mut gb = create_gameboy();
+    let state = gb.save_state();
+
+    assert_eq!(gb.load_state(&[1, 2, 3]), Err(StateError::InvalidHeader));
+
+    let mut wrong_version = state.clone();
+    wrong_version[4] = 0xFF;
+    assert_eq!(gb.load_state(&wrong_version), Err(StateError::UnsupportedVersion(0xFF)));
+
+    assert_eq!(gb.load_state(&state[..state.len() - 1]), Err(StateError::InvalidLength));
+
+    // a rejected state leaves the machine running as before
+    let scx = gb.read_byte(0xFF43);
+    gb.step_frame().unwrap();
+    gb.step_frame().unwrap();
+    assert_eq!(gb.read_byte(0xFF43), scx.wrapping_add(2));
+}
@@ -0,0 +1,42 @@
+use gameboy_rs::gameboy::scale_nearest;
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+#[test]
+fn each_pixel_becomes_a_block() {
+    let scaled = scale_nearest(&[0, 96, 192, 255], 2, 2, 2);
+
+    assert_eq!(scaled, vec![
+        0,   0,   96,  96,
+        0,   0,   96,  96,
+        192, 192, 255, 255,
+        192, 192, 255, 255
+    ]);
+}
+
+#[test]
+fn factor_1_is_a_copy() {
+    let pixels = [0, 96, 192, 255, 0, 96];
+    assert_eq!(scale_nearest(&pixels, 3, 2, 1), pixels.to_vec());
+}
+
+#[test]
+fn framebuffer_scaled_by_3() {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    let frame = gb.get_frame_buffer().to_vec();
+    let scaled = gb.framebuffer_scaled(3);
+    assert_eq!(scaled.len(), 160 * 3 * 144 * 3);
+
+    for y in 0..144 * 3 {
+        for x in 0..160 * 3 {
+            assert_eq!(scaled[y * 160 * 3 + x], frame[(y / 3) * 160 + x / 3], "{}, {}", x, y);
+        }
+    }
+
+    assert_eq!(gb.framebuffer_scaled(1), frame);
+}
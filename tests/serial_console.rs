@@ -0,0 +1,65 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, BOOT_FRAMES};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Sends the 0 terminated string at 0x0200 out of the serial port a byte at a time
+const PRINT_PROGRAM: &[u8] = &[
+    0x21, 0x00, 0x02,       // ld hl, 0x0200
+    0x2A,                   // ld a, (hl+)
+    0xB7,                   // or a
+    0x28, 0x0E,             // jr z, +14
+    0xE0, 0x01,             // ldh (SB), a
+    0x3E, 0x81,             // ld a, 0x81
+    0xE0, 0x02,             // ldh (SC), a
+    0xF0, 0x02,             // ldh a, (SC)
+    0xCB, 0x7F,             // bit 7, a
+    0x20, 0xFA,             // jr nz, -6
+    0x18, 0xEE,             // jr -18
+    0x18, 0xFE              // jr -2
+];
+
+fn create_gameboy(text: &[u8]) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x100 + PRINT_PROGRAM.len()].copy_from_slice(PRINT_PROGRAM);
+    rom[0x200..0x200 + text.len()].copy_from_slice(text);
+
+    new_gameboy(rom)
+}
+
+fn run(gb: &mut GameBoy) {
+    for _ in 0..BOOT_FRAMES + 5 {
+        gb.step_frame().unwrap();
+    }
+}
+
+#[test]
+fn captures_printed_text() {
+    let mut gb = create_gameboy(b"OK\n");
+    gb.set_serial_console(true);
+
+    run(&mut gb);
+    assert_eq!(gb.serial_console_output(), "OK\n");
+
+    gb.clear_serial_console();
+    assert_eq!(gb.serial_console_output(), "");
+}
+
+#[test]
+fn unprintable_bytes_are_dropped() {
+    let mut gb = create_gameboy(b"A\x01B\x7F\n");
+    gb.set_serial_console(true);
+
+    run(&mut gb);
+    assert_eq!(gb.serial_console_output(), "AB\n");
+}
+
+#[test]
+fn off_by_default() {
+    let mut gb = create_gameboy(b"OK\n");
+
+    run(&mut gb);
+    assert_eq!(gb.serial_console_output(), "");
+}
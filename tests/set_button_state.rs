@@ -0,0 +1,71 @@
+use gameboy_rs::gameboy::{GameBoy, input::Button};
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const JOYPAD_IF: u8 = 1 << 4;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    gb
+}
+
+// Interrupts are never enabled, so the request just sits in IF
+fn take_joypad_interrupt(gb: &mut GameBoy) -> bool {
+    let flags = gb.read_byte(0xFF0F);
+    gb.write_byte(0xFF0F, flags & !JOYPAD_IF);
+    flags & JOYPAD_IF != 0
+}
+
+// Reads JOYP with one column selected, a set bit in the result means pressed
+fn read_column(gb: &mut GameBoy, select: u8) -> u8 {
+    gb.write_byte(0xFF00, select);
+    !gb.read_byte(0xFF00) & 0x0F
+}
+
+#[test]
+fn mask_sets_every_button() {
+    let mut gb = create_gameboy();
+
+    // Right, Up, B, Start
+    gb.set_button_state(0b1010_0101);
+    assert_eq!(read_column(&mut gb, 0x20), 0b0101);
+    assert_eq!(read_column(&mut gb, 0x10), 0b1010);
+
+    gb.set_button_state(Button::Down.mask() | Button::A.mask());
+    assert_eq!(read_column(&mut gb, 0x20), 0b1000);
+    assert_eq!(read_column(&mut gb, 0x10), 0b0001);
+
+    gb.set_button_state(0);
+    assert_eq!(read_column(&mut gb, 0x20), 0);
+    assert_eq!(read_column(&mut gb, 0x10), 0);
+}
+
+#[test]
+fn interrupt_only_on_new_presses() {
+    let mut gb = create_gameboy();
+    take_joypad_interrupt(&mut gb);
+
+    // (mask, whether a button went from released to pressed)
+    let frames = [
+        (0b0000_0000, false),
+        (0b0000_0001, true),
+        (0b0000_0001, false),
+        (0b0001_0001, true),
+        (0b0001_0000, false),
+        (0b0000_0000, false),
+        (0b0001_0000, true),
+        (0b1110_1111, true),
+        (0b0000_0001, false)
+    ];
+
+    for (mask, pressed) in frames {
+        gb.set_button_state(mask);
+        gb.step_frame().unwrap();
+        assert_eq!(take_joypad_interrupt(&mut gb), pressed, "mask {:#010b}", mask);
+    }
+}
@@ -0,0 +1,70 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8], header_checksum: u8) -> GameBoy {
+    let mut rom = rom_with_program(program);
+    rom[0x14D] = header_checksum;
+
+    new_gameboy(rom)
+}
+
+#[test]
+fn cgb_starts_with_a_11() {
+    let mut gb = create_gameboy(&[0x18, 0xFE], 0x00);
+    gb.skip_boot_rom(Model::Cgb);
+
+    let registers = gb.registers();
+    assert_eq!(registers.a, 0x11);
+    assert_eq!(registers.f, 0x80);
+    assert_eq!(registers.d, 0xFF);
+    assert_eq!(registers.e, 0x56);
+    assert_eq!(registers.sp, 0xFFFE);
+    assert_eq!(registers.pc, 0x0100);
+}
+
+#[test]
+fn dmg_starts_with_a_01() {
+    let mut gb = create_gameboy(&[0x18, 0xFE], 0x00);
+    gb.skip_boot_rom(Model::Dmg);
+
+    let registers = gb.registers();
+    assert_eq!(registers.a, 0x01);
+    assert_eq!(registers.f, 0x80);
+    assert_eq!((registers.b, registers.c), (0x00, 0x13));
+    assert_eq!((registers.d, registers.e), (0x00, 0xD8));
+    assert_eq!((registers.h, registers.l), (0x01, 0x4D));
+    assert_eq!(registers.pc, 0x0100);
+}
+
+#[test]
+fn dmg_flags_depend_on_header_checksum() {
+    let mut gb = create_gameboy(&[0x18, 0xFE], 0x3C);
+    gb.skip_boot_rom(Model::Dmg);
+    assert_eq!(gb.registers().f, 0xB0);
+
+    // the SGB boot rom doesn't leave the flags set
+    let mut gb = create_gameboy(&[0x18, 0xFE], 0x3C);
+    gb.skip_boot_rom(Model::Sgb);
+    assert_eq!(gb.registers().f, 0x00);
+}
+
+#[test]
+fn cartridge_runs_straight_away() {
+    let mut gb = create_gameboy(&[
+        0x3E, 0x42,             // ld a, 0x42
+        0xEA, 0x00, 0xC0,       // ld (0xC000), a
+        0x18, 0xFE              // jr -2
+    ], 0x00);
+    gb.skip_boot_rom(Model::Dmg);
+
+    gb.step_frame().unwrap();
+    assert_eq!(gb.read_byte(0xC000), 0x42);
+
+    // the boot rom isn't mapped over the cartridge
+    assert_eq!(gb.read_byte(0x0000), 0x00);
+    assert_eq!(gb.read_byte(0xFF40), 0x91);
+}
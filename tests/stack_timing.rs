@@ -0,0 +1,63 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const CYCLE_LIMIT: u64 = 100;
+const SP: u16 = 0xFFFE;
+
+// Runs the instruction at 0x100 with SP at 0xFFFE and 0xAA in the 2 bytes below it
+fn create_gameboy(instruction: &[u8]) -> GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x100 + instruction.len()].copy_from_slice(instruction);
+
+    let mut gb = new_gameboy(rom);
+    gb.skip_boot_rom(Model::Dmg);
+    gb.write_byte(SP - 1, 0xAA);
+    gb.write_byte(SP - 2, 0xAA);
+    gb
+}
+
+// Which machine cycle of the instruction each byte of the return address
+// was pushed in, high byte first, and the one PC changed to target in
+fn push_machine_cycles(instruction: &[u8], target: u16) -> (u64, u64, u64) {
+    let mut gb = create_gameboy(instruction);
+    let mut high = 0;
+    let mut low = 0;
+    let mut jump = 0;
+
+    for cycle in 1..=CYCLE_LIMIT {
+        gb.tick();
+        let machine_cycle = (cycle + 3) / 4;
+
+        if high == 0 && gb.read_byte(SP - 1) != 0xAA { high = machine_cycle }
+        if low == 0 && gb.read_byte(SP - 2) != 0xAA { low = machine_cycle }
+        if jump == 0 && gb.registers().pc == target { jump = machine_cycle }
+    }
+
+    let return_addr = 0x100 + instruction.len() as u16;
+    assert_eq!(gb.read_byte(SP - 1), (return_addr >> 8) as u8);
+    assert_eq!(gb.read_byte(SP - 2), return_addr as u8);
+
+    (high, low, jump)
+}
+
+#[test]
+fn call_pushes_in_last_2_cycles() {
+    // call 0x0200
+    assert_eq!(push_machine_cycles(&[0xCD, 0x00, 0x02], 0x200), (5, 6, 6));
+}
+
+#[test]
+fn call_cc_pushes_in_last_2_cycles() {
+    // call z, 0x0200, the DMG boot rom leaves Z set
+    assert_eq!(push_machine_cycles(&[0xCC, 0x00, 0x02], 0x200), (5, 6, 6));
+}
+
+#[test]
+fn rst_pushes_in_last_2_cycles() {
+    // rst 0x38
+    assert_eq!(push_machine_cycles(&[0xFF], 0x38), (3, 4, 4));
+}
@@ -0,0 +1,51 @@
+use gameboy_rs::gameboy::{GameBoy, Model, Registers};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy() -> GameBoy {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.set_registers(Registers { b: 0x12, c: 0x34, sp: 0xFFFE, pc: 0x100, ..Registers::default() });
+    gb
+}
+
+#[test]
+fn push_at_0000_wraps_to_fffe() {
+    let mut gb = create_gameboy();
+    gb.set_sp(0x0000);
+
+    // push bc
+    gb.execute_opcode(0xC5, 0, 0).unwrap();
+    assert_eq!(gb.registers().sp, 0xFFFE);
+    assert_eq!(gb.read_byte(0xFFFF), 0x12);
+    assert_eq!(gb.read_byte(0xFFFE), 0x34);
+    assert_eq!(gb.get_error(), None);
+}
+
+#[test]
+fn pop_at_fffe_wraps_to_0000() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0xFFFE, 0x78);
+    gb.write_byte(0xFFFF, 0x56);
+
+    // pop de
+    gb.execute_opcode(0xD1, 0, 0).unwrap();
+    assert_eq!(gb.registers().sp, 0x0000);
+    assert_eq!(gb.registers().d, 0x56);
+    assert_eq!(gb.registers().e, 0x78);
+}
+
+#[test]
+fn pop_at_ffff_reads_0000_next() {
+    let mut gb = create_gameboy();
+    gb.write_byte(0xFFFF, 0x9A);
+    gb.set_sp(0xFFFF);
+
+    // pop de, the high byte comes from the start of the rom
+    gb.execute_opcode(0xD1, 0, 0).unwrap();
+    assert_eq!(gb.registers().sp, 0x0001);
+    assert_eq!(gb.registers().d, gb.read_byte(0x0000));
+    assert_eq!(gb.registers().e, 0x9A);
+}
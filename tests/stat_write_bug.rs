@@ -0,0 +1,100 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const INSTRUCTION_LIMIT: usize = 100_000;
+const STAT_IF: u8 = 1 << 1;
+
+fn create_gameboy(model: Model) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.set_model(model);
+    boot(&mut gb);
+
+    // LY never matches, and no STAT sources are enabled
+    gb.write_byte(0xFF45, 0xFF);
+    gb.write_byte(0xFF41, 0x00);
+
+    gb
+}
+
+// Steps until the ppu has just switched into the mode
+fn wait_for_mode(gb: &mut GameBoy, mode: u8) {
+    let mut last_mode = gb.read_byte(0xFF41) & 3;
+
+    for _ in 0..INSTRUCTION_LIMIT {
+        gb.step_instruction().unwrap();
+
+        let current_mode = gb.read_byte(0xFF41) & 3;
+        if current_mode == mode && last_mode != mode { return }
+        last_mode = current_mode;
+    }
+
+    panic!("Never reached mode {}", mode);
+}
+
+// Interrupts are never enabled, so the request just sits in IF
+fn write_stat_fires_interrupt(gb: &mut GameBoy) -> bool {
+    let flags = gb.read_byte(0xFF0F);
+    gb.write_byte(0xFF0F, flags & !STAT_IF);
+
+    gb.write_byte(0xFF41, 0x00);
+    gb.read_byte(0xFF0F) & STAT_IF != 0
+}
+
+#[test]
+fn dmg_fires_in_hblank_and_vblank() {
+    let mut gb = create_gameboy(Model::Dmg);
+
+    wait_for_mode(&mut gb, 0);
+    assert!(write_stat_fires_interrupt(&mut gb));
+
+    wait_for_mode(&mut gb, 1);
+    assert!(write_stat_fires_interrupt(&mut gb));
+}
+
+#[test]
+fn dmg_doesnt_fire_in_oam_scan_or_transfer() {
+    let mut gb = create_gameboy(Model::Dmg);
+
+    wait_for_mode(&mut gb, 2);
+    assert!(!write_stat_fires_interrupt(&mut gb));
+
+    wait_for_mode(&mut gb, 3);
+    assert!(!write_stat_fires_interrupt(&mut gb));
+}
+
+#[test]
+fn dmg_fires_on_lyc_match() {
+    let mut gb = create_gameboy(Model::Dmg);
+
+    wait_for_mode(&mut gb, 3);
+    gb.write_byte(0xFF45, gb.read_byte(0xFF44));
+    assert!(write_stat_fires_interrupt(&mut gb));
+}
+
+#[test]
+fn dmg_doesnt_fire_with_lcd_off() {
+    let mut gb = create_gameboy(Model::Dmg);
+
+    wait_for_mode(&mut gb, 1);
+    gb.write_byte(0xFF40, 0x00);
+    assert!(!write_stat_fires_interrupt(&mut gb));
+}
+
+#[test]
+fn cgb_never_fires() {
+    let mut gb = create_gameboy(Model::Cgb);
+
+    wait_for_mode(&mut gb, 0);
+    assert!(!write_stat_fires_interrupt(&mut gb));
+
+    wait_for_mode(&mut gb, 1);
+    assert!(!write_stat_fires_interrupt(&mut gb));
+
+    wait_for_mode(&mut gb, 3);
+    gb.write_byte(0xFF45, gb.read_byte(0xFF44));
+    assert!(!write_stat_fires_interrupt(&mut gb));
+}
@@ -0,0 +1,115 @@
+use std::{env, fs, io, path::PathBuf};
+
+use gameboy_rs::gameboy::{GameBoy, save_state::StateError};
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Counts up in 0xC000 as fast as it can
+const PROGRAM: [u8; 8] = [
+    0xFA, 0x00, 0xC0,   // ld a, (0xC000)
+    0x3C,               // inc a
+    0xEA, 0x00, 0xC0,   // ld (0xC000), a
+    0x18                // jr ...
+];
+
+fn create_gameboy(title: &str, state_dir: &str) -> GameBoy {
+    let mut rom = rom_with_program(&PROGRAM);
+    rom[0x108] = 0xF6; // ... -10
+    rom[0x134..0x134 + title.len()].copy_from_slice(title.as_bytes());
+
+    let mut gb = new_gameboy(rom);
+    gb.set_state_directory(state_dir);
+    gb
+}
+
+fn create_state_dir(name: &str) -> String {
+    let mut path = PathBuf::from(env::temp_dir());
+    path.push(name);
+    fs::remove_dir_all(&path).ok();
+    fs::create_dir_all(&path).unwrap();
+
+    path.to_str().unwrap().to_owned()
+}
+
+fn run_frames(gb: &mut GameBoy, frames: usize) {
+    for _ in 0..frames {
+        gb.step_frame().unwrap();
+    }
+}
+
+#[test]
+fn slots_load_back_what_was_saved() {
+    let dir = create_state_dir("state_slots_two");
+    let mut gb = create_gameboy("SLOTS", &dir);
+    // past the boot rom
+    run_frames(&mut gb, 100);
+
+    let first = gb.save_state_to_slot(1).unwrap();
+    let first_count = gb.read_byte(0xC000);
+
+    run_frames(&mut gb, 10);
+    let second = gb.save_state_to_slot(2).unwrap();
+    let second_count = gb.read_byte(0xC000);
+    assert!(second_count != first_count);
+    assert_eq!(second.frame_count, first.frame_count + 10);
+
+    run_frames(&mut gb, 10);
+
+    assert_eq!(gb.load_state_from_slot(1), Ok(first));
+    assert_eq!(gb.read_byte(0xC000), first_count);
+
+    assert_eq!(gb.load_state_from_slot(2), Ok(second));
+    assert_eq!(gb.read_byte(0xC000), second_count);
+
+    assert_eq!(gb.list_states(), vec![first, second]);
+}
+
+#[test]
+fn state_from_another_rom_is_refused() {
+    let dir = create_state_dir("state_slots_wrong_rom");
+    let mut gb = create_gameboy("SLOTS A", &dir);
+    run_frames(&mut gb, 100);
+    let meta = gb.save_state_to_slot(0).unwrap();
+    assert_eq!(meta.rom_hash, gb.rom_hash());
+
+    let mut other_gb = create_gameboy("SLOTS B", &dir);
+    assert!(other_gb.rom_hash() != gb.rom_hash());
+
+    other_gb.step_frame().unwrap();
+    let registers = other_gb.registers();
+    assert_eq!(other_gb.load_state_from_slot(0), Err(StateError::WrongRom));
+    assert_eq!(other_gb.registers(), registers);
+
+    // it still gets listed
+    assert_eq!(other_gb.list_states(), vec![meta]);
+}
+
+#[test]
+fn missing_slots_and_directory() {
+    let dir = create_state_dir("state_slots_missing");
+    let mut gb = create_gameboy("SLOTS", &dir);
+
+    assert_eq!(gb.load_state_from_slot(3), Err(StateError::Io(io::ErrorKind::NotFound)));
+    assert!(gb.list_states().is_empty());
+
+    let mut no_dir_gb = new_gameboy(vec![0; 0x8000]);
+    assert_eq!(no_dir_gb.save_state_to_slot(0), Err(StateError::NoStateDirectory));
+}
+
+#[test]
+fn slot_files_and_plain_states_dont_mix() {
+    let dir = create_state_dir("state_slots_mixed");
+    let mut gb = create_gameboy("SLOTS", &dir);
+    run_frames(&mut gb, 100);
+
+    gb.save_state_to_slot(0).unwrap();
+    let slot_file = fs::read(PathBuf::from(&dir).join("slot0.state")).unwrap();
+    assert_eq!(gb.load_state(&slot_file), Err(StateError::InvalidHeader));
+
+    fs::write(PathBuf::from(&dir).join("slot1.state"), gb.save_state()).unwrap();
+    assert_eq!(gb.load_state_from_slot(1), Err(StateError::InvalidHeader));
+    assert_eq!(gb.list_states().len(), 1);
+}
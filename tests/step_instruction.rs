@@ -0,0 +1,74 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const BOOT_INSTRUCTION_LIMIT: usize = 10_000_000;
+
+fn create_gameboy() -> GameBoy {
+    let mut rom = rom_with_program(&[
+        0x00,                   // nop
+        0x3E, 0x42,             // ld a, 0x42
+        0x47,                   // ld b, a
+        0xC3, 0x50, 0x01        // jp 0x0150
+    ]);
+    rom[0x150..0x154].copy_from_slice(&[
+        0xCB, 0x37,             // swap a
+        0x18, 0xFC              // jr 0x0150
+    ]);
+
+    new_gameboy(rom)
+}
+
+// Steps through the boot rom until the instruction at 0x100 has run
+fn step_past_boot(gb: &mut GameBoy) {
+    for _ in 0..BOOT_INSTRUCTION_LIMIT {
+        let (addr, _, _) = gb.step_instruction().unwrap();
+        if addr == 0x100 { return }
+    }
+
+    panic!("Never reached 0x100");
+}
+
+#[test]
+fn step_instruction_stops_on_instruction_boundaries() {
+    let mut gb = create_gameboy();
+    step_past_boot(&mut gb);
+    assert_eq!(gb.registers().pc, 0x101);
+
+    let expected: [(u16, u8, Vec<u8>, u16); 6] = [
+        (0x101, 0x3E, vec![0x42], 0x103),
+        (0x103, 0x47, vec![], 0x104),
+        (0x104, 0xC3, vec![0x50, 0x01], 0x150),
+        (0x150, 0x37, vec![0x37], 0x152),
+        (0x152, 0x18, vec![0xFC], 0x150),
+        (0x150, 0x37, vec![0x37], 0x152)
+    ];
+
+    for (addr, opcode, operands, next_pc) in expected {
+        let (step_addr, instruction, step_operands) = gb.step_instruction().unwrap();
+        assert_eq!(step_addr, addr);
+        assert_eq!(instruction.opcode_val, opcode);
+        assert_eq!(step_operands, operands);
+        assert_eq!(gb.registers().pc, next_pc);
+    }
+
+    assert_eq!(gb.registers().a, 0x42);
+    assert_eq!(gb.registers().b, 0x42);
+}
+
+#[test]
+fn step_instruction_finishes_a_partly_run_instruction() {
+    let mut gb = create_gameboy();
+    step_past_boot(&mut gb);
+
+    // one cycle into "ld a, 0x42"
+    gb.tick();
+    let (addr, instruction, _) = gb.step_instruction().unwrap();
+    assert_eq!(addr, 0x101);
+    assert_eq!(instruction.opcode_val, 0x3E);
+    assert_eq!(gb.registers().pc, 0x103);
+    assert_eq!(gb.registers().a, 0x42);
+}
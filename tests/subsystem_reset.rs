@@ -0,0 +1,82 @@
+use gameboy_rs::gameboy::{GameBoy, Registers};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NR52: u16 = 0xFF26;
+const LY: u16 = 0xFF44;
+const STAT: u16 = 0xFF41;
+
+fn create_gameboy(boot_frames: usize) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    for _ in 0..boot_frames {
+        gb.step_frame().unwrap();
+    }
+
+    for i in 0..0x100 {
+        gb.write_byte(0xC000 + i, i as u8);
+    }
+
+    gb
+}
+
+fn cpu_and_ram(gb: &GameBoy) -> (Registers, Vec<u8>) {
+    (gb.registers(), (0..0x100).map(|i| gb.read_byte(0xC000 + i)).collect())
+}
+
+fn play_note(gb: &mut GameBoy) {
+    gb.write_byte(NR52, 0x80);
+    gb.write_byte(0xFF24, 0x77); // NR50
+    gb.write_byte(0xFF25, 0xFF); // NR51
+    gb.write_byte(0xFF12, 0xF0); // NR12, full volume
+    gb.write_byte(0xFF14, 0x80); // NR14, trigger
+}
+
+#[test]
+fn reset_ppu_starts_again_from_line_0() {
+    let mut gb = create_gameboy(BOOT_FRAMES);
+    gb.run_cycles(456 * 50).unwrap();
+    assert_ne!(gb.read_byte(LY), 0);
+
+    let before = cpu_and_ram(&gb);
+    gb.reset_ppu();
+
+    assert_eq!(gb.read_byte(LY), 0);
+    assert_eq!(gb.read_byte(STAT) & 0b11, 0);
+    assert!(gb.get_frame_buffer().iter().all(|&pixel| pixel == 255));
+    assert_eq!(cpu_and_ram(&gb), before);
+
+    // and carries on drawing from there
+    gb.run_cycles(456 * 10 + 100).unwrap();
+    assert_eq!(gb.read_byte(LY), 10);
+}
+
+#[test]
+fn reset_apu_silences_channels() {
+    let mut gb = create_gameboy(BOOT_FRAMES);
+    // before the boot rom has touched the sound registers
+    let power_on = create_gameboy(0);
+
+    play_note(&mut gb);
+    gb.step_frame().unwrap();
+    assert_eq!(gb.read_byte(NR52) & 1, 1);
+    assert!(gb.audio_samples_available() > 0);
+
+    let before = cpu_and_ram(&gb);
+    gb.reset_apu();
+
+    // still on, with nothing playing or waiting to be played
+    assert_eq!(gb.read_byte(NR52), 0b1111_0000);
+    assert_eq!(gb.audio_samples_available(), 0);
+    assert_eq!(cpu_and_ram(&gb), before);
+
+    for addr in 0xFF10..=0xFF23 {
+        assert_eq!(gb.read_byte(addr), power_on.read_byte(addr), "{:#06X}", addr);
+    }
+
+    // the mixer settings are kept
+    assert_eq!(gb.read_byte(0xFF24), 0x77);
+    assert_eq!(gb.read_byte(0xFF25), 0xFF);
+}
@@ -0,0 +1,51 @@
+use std::convert::TryInto;
+
+use gameboy_rs::gameboy::{decode_tile, decode_tile_row};
+use common::{boot, new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// The example tile from https://gbdev.io/pandocs/Tile_Data.html
+const TILE: [u8; 16] = [
+    0x3C, 0x7E, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42,
+    0x7E, 0x5E, 0x7E, 0x0A, 0x7C, 0x56, 0x38, 0x7C
+];
+
+const PIXELS: [[u8; 8]; 8] = [
+    [0, 2, 3, 3, 3, 3, 2, 0],
+    [0, 3, 0, 0, 0, 0, 3, 0],
+    [0, 3, 0, 0, 0, 0, 3, 0],
+    [0, 3, 0, 0, 0, 0, 3, 0],
+    [0, 3, 1, 3, 3, 3, 3, 0],
+    [0, 1, 1, 1, 3, 1, 3, 0],
+    [0, 3, 1, 3, 1, 3, 2, 0],
+    [0, 2, 3, 3, 3, 2, 0, 0]
+];
+
+#[test]
+fn decodes_tile() {
+    assert_eq!(decode_tile(&TILE), PIXELS);
+}
+
+#[test]
+fn decodes_row() {
+    // low bits in the first byte, high bits in the second, leftmost pixel in bit 7
+    assert_eq!(decode_tile_row(0b1010_0000, 0b1100_0000), [3, 2, 1, 0, 0, 0, 0, 0]);
+    assert_eq!(decode_tile_row(0xFF, 0x00), [1; 8]);
+    assert_eq!(decode_tile_row(0x00, 0xFF), [2; 8]);
+}
+
+#[test]
+fn matches_vram_tileset() {
+    // the boot rom's logo tiles
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    boot(&mut gb);
+
+    let vram = gb.dump_vram();
+    for index in 0..384 {
+        let bytes: [u8; 16] = vram[index * 16..index * 16 + 16].try_into().unwrap();
+        assert_eq!(decode_tile(&bytes), gb.get_tile(index), "tile {}", index);
+    }
+}
@@ -0,0 +1,51 @@
+use gameboy_rs::gameboy::Model;
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const LCDC: u16 = 0xFF40;
+
+#[test]
+fn tile_maps_are_read_separately() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.write_byte(LCDC, 0);
+
+    for i in 0..0x400 {
+        gb.write_byte(0x9800 + i, i as u8);
+        gb.write_byte(0x9C00 + i, !(i as u8));
+    }
+
+    let map0 = gb.tile_map(0);
+    let map1 = gb.tile_map(1);
+    assert_eq!(map0.len(), 0x400);
+    assert_eq!(map1.len(), 0x400);
+
+    for i in 0..0x400 {
+        assert_eq!(map0[i], i as u8);
+        assert_eq!(map1[i], !(i as u8));
+    }
+}
+
+#[test]
+fn tile_data_covers_the_tiles() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.write_byte(LCDC, 0);
+
+    gb.write_byte(0x8000, 0x12);
+    gb.write_byte(0x97FF, 0x34);
+    gb.write_byte(0x9800, 0x56);
+
+    let data = gb.tile_data();
+    assert_eq!(data.len(), 0x1800);
+    assert_eq!(data[0], 0x12);
+    assert_eq!(data[0x17FF], 0x34);
+}
+
+#[test]
+#[should_panic]
+fn there_are_only_two_tile_maps() {
+    let gb = booted_gameboy(Model::Dmg);
+    gb.tile_map(2);
+}
@@ -0,0 +1,45 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::booted_gameboy;
+
+extern crate gameboy_rs;
+
+mod common;
+
+const TIMA: u16 = 0xFF05;
+const TAC: u16 = 0xFF07;
+
+fn cycles_until_tima_increments(gb: &mut GameBoy) -> u64 {
+    let tima = gb.read_byte(TIMA);
+    let mut cycles = 0;
+
+    while gb.read_byte(TIMA) == tima {
+        gb.run_cycles(1).unwrap();
+        cycles += 1;
+    }
+
+    cycles
+}
+
+#[test]
+fn tima_increments_at_the_same_cycle_after_load() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    // 4096Hz, once every 1024 cycles, so most of the phase is below DIV
+    gb.write_byte(TAC, 0b100);
+    gb.run_cycles(5_000).unwrap();
+
+    let state = gb.save_state();
+    let expected = cycles_until_tima_increments(&mut gb);
+    // a state taken at a DIV boundary wouldn't show anything
+    assert!(expected % 256 != 0);
+
+    gb.load_state(&state).unwrap();
+    assert_eq!(cycles_until_tima_increments(&mut gb), expected);
+
+    // somewhere else in the count before loading
+    let mut other_gb = booted_gameboy(Model::Dmg);
+    other_gb.write_byte(TAC, 0b100);
+    other_gb.run_cycles(777).unwrap();
+
+    other_gb.load_state(&state).unwrap();
+    assert_eq!(cycles_until_tima_increments(&mut other_gb), expected);
+}
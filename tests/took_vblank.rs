@@ -0,0 +1,35 @@
+use gameboy_rs::gameboy::Model;
+use common::{booted_gameboy, CYCLES_PER_SCREEN_DRAW};
+
+extern crate gameboy_rs;
+
+mod common;
+
+#[test]
+fn took_vblank_is_true_once_per_frame() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.took_vblank();
+
+    for _ in 0..10 {
+        gb.step_frame().unwrap();
+
+        assert!(gb.took_vblank());
+        assert!(!gb.took_vblank());
+    }
+}
+
+#[test]
+fn took_vblank_polled_every_cycle() {
+    let mut gb = booted_gameboy(Model::Dmg);
+    gb.took_vblank();
+
+    let mut vblanks = 0;
+    for _ in 0..CYCLES_PER_SCREEN_DRAW * 10 {
+        gb.tick();
+        if gb.took_vblank() {
+            vblanks += 1;
+        }
+    }
+
+    assert_eq!(vblanks, 10);
+}
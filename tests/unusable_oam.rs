@@ -0,0 +1,62 @@
+use gameboy_rs::gameboy::{GameBoy, UnusableOamPolicy};
+use common::{new_gameboy, rom_with_program, BOOT_FRAMES, CYCLES_PER_SCREEN_DRAW};
+
+extern crate gameboy_rs;
+
+mod common;
+
+// Waits for vblank then turns the lcd off, so OAM is never locked
+const PROGRAM: [u8; 11] = [
+    0xF0, 0x44,             // ldh a, (LY)
+    0xFE, 0x90,             // cp 144
+    0x20, 0xFA,             // jr nz, -6
+    0xAF,                   // xor a
+    0xE0, 0x40,             // ldh (LCDC), a
+    0x18, 0xFE              // jr -2
+];
+
+fn create_gameboy(policy: UnusableOamPolicy) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&PROGRAM));
+    gb.set_unusable_oam_policy(policy);
+
+    // step_frame would never return once the lcd is off
+    gb.run_cycles(CYCLES_PER_SCREEN_DRAW * (BOOT_FRAMES as u64 + 1)).unwrap();
+
+    // writes should never stick
+    for addr in 0xFEA0..=0xFEFF {
+        gb.write_byte(addr, 0x12);
+    }
+
+    gb
+}
+
+#[test]
+fn unusable_oam_zero() {
+    let gb = create_gameboy(UnusableOamPolicy::Zero);
+
+    for addr in 0xFEA0..=0xFEFF {
+        assert_eq!(gb.read_byte(addr), 0x00, "{:#06X}", addr);
+    }
+}
+
+#[test]
+fn unusable_oam_open_bus() {
+    let gb = create_gameboy(UnusableOamPolicy::OpenBus);
+
+    for addr in 0xFEA0..=0xFEFF {
+        assert_eq!(gb.read_byte(addr), 0xFF, "{:#06X}", addr);
+    }
+}
+
+#[test]
+fn unusable_oam_cgb_quirk() {
+    let gb = create_gameboy(UnusableOamPolicy::CgbQuirk);
+
+    assert_eq!(gb.read_byte(0xFEA0), 0xAA);
+    assert_eq!(gb.read_byte(0xFEA7), 0xAA);
+    assert_eq!(gb.read_byte(0xFEBF), 0xBB);
+    assert_eq!(gb.read_byte(0xFEC3), 0xCC);
+    assert_eq!(gb.read_byte(0xFED8), 0xDD);
+    assert_eq!(gb.read_byte(0xFEE1), 0xEE);
+    assert_eq!(gb.read_byte(0xFEFF), 0xFF);
+}
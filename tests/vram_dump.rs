@@ -0,0 +1,67 @@
+use gameboy_rs::gameboy::GameBoy;
+use common::{boot, new_gameboy, rom_with_program};
+
+extern crate gameboy_rs;
+
+mod common;
+
+fn create_gameboy(program: &[u8]) -> GameBoy {
+    new_gameboy(rom_with_program(program))
+}
+
+#[test]
+fn vram_round_trip_renders_same_frame() {
+    // the boot rom leaves the logo on screen
+    let mut gb = create_gameboy(&[0x18, 0xFE]);
+    boot(&mut gb);
+
+    let frame = gb.get_frame_buffer().to_vec();
+    let vram = gb.dump_vram();
+    assert_eq!(vram.len(), 0x2000);
+
+    gb.load_vram(&[0; 0x2000]);
+    gb.step_frame().unwrap();
+    assert!(gb.get_frame_buffer() != &frame[..]);
+    assert!(gb.dump_vram().iter().all(|val| *val == 0));
+
+    gb.load_vram(&vram);
+    assert!(gb.dump_vram() == vram);
+
+    gb.step_frame().unwrap();
+    assert!(gb.get_frame_buffer() == &frame[..]);
+}
+
+#[test]
+fn set_tile_updates_tileset_and_vram() {
+    let mut gb = create_gameboy(&[0x18, 0xFE]);
+
+    let mut tile = [[0; 8]; 8];
+    for y in 0..8 {
+        for x in 0..8 {
+            tile[y][x] = ((x + y) & 3) as u8;
+        }
+    }
+
+    gb.set_tile(383, tile);
+    assert_eq!(gb.get_tile(383), tile);
+
+    // row 0 is colours 0 1 2 3 0 1 2 3, row 1 is 1 2 3 0 1 2 3 0
+    let vram = gb.dump_vram();
+    let addr = 383 * 16;
+    assert_eq!(vram[addr], 0b0101_0101);
+    assert_eq!(vram[addr + 1], 0b0011_0011);
+    assert_eq!(vram[addr + 2], 0b1010_1010);
+    assert_eq!(vram[addr + 3], 0b0110_0110);
+
+    // decoding the vram again gives the same tile
+    gb.load_vram(&vram);
+    assert_eq!(gb.get_tile(383), tile);
+    assert_eq!(gb.get_tile(382), [[0; 8]; 8]);
+}
+
+#[test]
+#[should_panic]
+fn tile_index_is_bounds_checked() {
+    let mut gb = create_gameboy(&[0x18, 0xFE]);
+    gb.set_tile(384, [[0; 8]; 8]);
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "hound")]
+
+use std::{env, path::PathBuf};
+
+use gameboy_rs::gameboy::{GameBoy, spu::SAMPLE_RATE};
+use common::{boot, new_gameboy, rom_with_program};
+use hound::{SampleFormat, WavReader};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const RECORDED_FRAMES: usize = 10;
+
+// Plays a square wave on channel 1
+fn create_gameboy() -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&[
+        0x3E, 0x80,             // ld a, 0x80
+        0xE0, 0x26,             // ldh (NR52), a
+        0x3E, 0x77,             // ld a, 0x77
+        0xE0, 0x24,             // ldh (NR50), a
+        0x3E, 0xFF,             // ld a, 0xFF
+        0xE0, 0x25,             // ldh (NR51), a
+        0x3E, 0xF0,             // ld a, 0xF0
+        0xE0, 0x12,             // ldh (NR12), a
+        0x3E, 0x87,             // ld a, 0x87
+        0xE0, 0x14,             // ldh (NR14), a
+        0x18, 0xFE              // jr -2
+    ]));
+    boot(&mut gb);
+
+    gb
+}
+
+fn wav_path(name: &str) -> String {
+    let mut path = PathBuf::from(env::temp_dir());
+    path.push(name);
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn recording_matches_output() {
+    let mut gb = create_gameboy();
+    let path = wav_path("wav_tone.wav");
+
+    gb.drain_audio();
+    gb.start_audio_recording(&path).unwrap();
+    for _ in 0..RECORDED_FRAMES {
+        gb.step_frame().unwrap();
+    }
+    gb.stop_audio_recording().unwrap();
+
+    // not recorded
+    gb.step_frame().unwrap();
+
+    let mut played = gb.drain_audio();
+    let mut reader = WavReader::open(&path).unwrap();
+    let spec = reader.spec();
+    assert_eq!(spec.channels, 2);
+    assert_eq!(spec.sample_rate, SAMPLE_RATE);
+    assert_eq!(spec.bits_per_sample, 32);
+    assert_eq!(spec.sample_format, SampleFormat::Float);
+
+    let recorded: Vec<f32> = reader.samples::<f32>().map(|sample| sample.unwrap()).collect();
+    assert_eq!(recorded.len(), reader.len() as usize);
+    assert!(!recorded.is_empty());
+    assert!(recorded.len() < played.len());
+
+    played.truncate(recorded.len());
+    assert_eq!(recorded, played);
+
+    // the tone isn't silence
+    assert!(recorded.iter().any(|sample| *sample != recorded[0]));
+}
+
+#[test]
+fn stopping_without_recording_is_ok() {
+    let mut gb = create_gameboy();
+    assert!(gb.stop_audio_recording().is_ok());
+}
+
+#[test]
+fn bad_path_is_an_error() {
+    let mut gb = create_gameboy();
+    let path = wav_path("wav_missing_dir/tone.wav");
+
+    assert!(gb.start_audio_recording(&path).is_err());
+}
@@ -0,0 +1,111 @@
+use gameboy_rs::gameboy::{GameBoy, Model};
+use common::{new_gameboy, rom_with_program, SPIN};
+
+extern crate gameboy_rs;
+
+mod common;
+
+const NR30: u16 = 0xFF1A;
+const NR32: u16 = 0xFF1C;
+const NR33: u16 = 0xFF1D;
+const NR34: u16 = 0xFF1E;
+const WAVE_RAM: u16 = 0xFF30;
+
+fn create_gameboy(model: Model) -> GameBoy {
+    let mut gb = new_gameboy(rom_with_program(&SPIN));
+    gb.skip_boot_rom(model);
+    gb
+}
+
+// Every byte of wave ram is its own index, then channel 3 is started with
+// a new sample every 512 cycles
+fn play_wave(gb: &mut GameBoy) {
+    for i in 0..16 {
+        gb.write_byte(WAVE_RAM + i, i as u8);
+    }
+
+    gb.write_byte(NR30, 0x80);
+    gb.write_byte(NR32, 0x20);
+    gb.write_byte(NR33, 0x00);
+    gb.write_byte(NR34, 0x87);
+}
+
+fn stop_wave(gb: &mut GameBoy) {
+    gb.write_byte(NR30, 0x00);
+}
+
+#[test]
+fn dmg_wave_ram_only_accessible_as_the_channel_reads_it() {
+    let mut gb = create_gameboy(Model::Dmg);
+    play_wave(&mut gb);
+
+    let mut blocked = 0;
+    let mut current_byte = None;
+    for _ in 0..2048 {
+        gb.tick();
+
+        let val = gb.read_byte(WAVE_RAM);
+        if val == 0xFF {
+            blocked += 1;
+
+            // ignored
+            gb.write_byte(WAVE_RAM, 0xCD);
+        }
+        else if current_byte.is_none() {
+            assert!(val < 16);
+            current_byte = Some(val);
+
+            // goes to the byte being played, not the last one
+            gb.write_byte(WAVE_RAM + 15, 0xAB);
+        }
+    }
+
+    // the channel only reads wave ram every 512 cycles
+    assert!(blocked > 2000);
+    assert!(current_byte.is_some());
+
+    stop_wave(&mut gb);
+    let current_byte = current_byte.unwrap() as u16;
+    for i in 0..16 {
+        let expected = if i == current_byte { 0xAB } else { i as u8 };
+        assert_eq!(gb.read_byte(WAVE_RAM + i), expected);
+    }
+}
+
+#[test]
+fn cgb_wave_ram_accesses_the_current_byte() {
+    let mut gb = create_gameboy(Model::Cgb);
+    play_wave(&mut gb);
+
+    for _ in 0..100 {
+        gb.tick();
+    }
+
+    let current_byte = gb.read_byte(WAVE_RAM + 7);
+    assert!(current_byte < 16);
+    gb.write_byte(WAVE_RAM, 0xAB);
+
+    for _ in 0..2048 {
+        gb.tick();
+        assert_ne!(gb.read_byte(WAVE_RAM), 0xFF);
+    }
+
+    stop_wave(&mut gb);
+    let current_byte = current_byte as u16;
+    for i in 0..16 {
+        let expected = if i == current_byte { 0xAB } else { i as u8 };
+        assert_eq!(gb.read_byte(WAVE_RAM + i), expected);
+    }
+}
+
+#[test]
+fn wave_ram_is_normal_while_the_channel_is_off() {
+    let mut gb = create_gameboy(Model::Dmg);
+
+    for i in 0..16 {
+        gb.write_byte(WAVE_RAM + i, 0x10 + i as u8);
+    }
+    for i in 0..16 {
+        assert_eq!(gb.read_byte(WAVE_RAM + i), 0x10 + i as u8);
+    }
+}
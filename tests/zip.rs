@@ -0,0 +1,36 @@
+#![cfg(feature = "zip")]
+
+use std::path::PathBuf;
+
+use gameboy_rs::gameboy::{GameBoy, rom_archive::ZipRomError};
+
+extern crate gameboy_rs;
+
+fn zip_path(name: &str) -> String {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("./tests/roms/zip");
+    d.push(name);
+    d.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn zip_loads_first_rom() {
+    // readme.txt comes first in the archive and should be skipped
+    let gb = GameBoy::from_zip(&zip_path("roms.zip"), None, None).unwrap();
+    assert_eq!(gb.get_title(), "ZIPTEST");
+}
+
+#[test]
+fn zip_loads_named_rom() {
+    let gb = GameBoy::from_zip(&zip_path("roms.zip"), Some("other.gb"), None).unwrap();
+    assert_eq!(gb.get_title(), "OTHER");
+}
+
+#[test]
+fn zip_without_rom() {
+    let result = GameBoy::from_zip(&zip_path("no_roms.zip"), None, None);
+    assert!(matches!(result, Err(ZipRomError::NoRomEntry)));
+
+    let result = GameBoy::from_zip(&zip_path("roms.zip"), Some("missing.gb"), None);
+    assert!(matches!(result, Err(ZipRomError::NoRomEntry)));
+}